@@ -0,0 +1,136 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use ferrite_core::clock::Clock;
+use ferrite_core::mpmc::MpmcQueue;
+use ferrite_core::mpsc::MpscQueue;
+use ferrite_core::ring_buffer::RingBuffer;
+use std::thread;
+
+const CAPACITY: usize = 1024;
+
+fn bench_single_pair(c: &mut Criterion) {
+    let mut group = c.benchmark_group("queue_1p1c");
+    group.throughput(Throughput::Elements(1));
+
+    group.bench_function("spsc_ring", |b| {
+        b.iter_custom(|iters| {
+            let buffer = RingBuffer::<u64>::new(CAPACITY).unwrap();
+            let (mut producer, mut consumer) = buffer.split();
+
+            let start = Clock::global().ticks();
+
+            let producer_handle = thread::spawn(move || {
+                for i in 0..iters {
+                    while producer.push(i).is_err() {
+                        std::hint::spin_loop();
+                    }
+                }
+            });
+
+            for _ in 0..iters {
+                while consumer.pop().is_err() {
+                    std::hint::spin_loop();
+                }
+            }
+
+            producer_handle.join().unwrap();
+            Clock::global().elapsed(start)
+        });
+    });
+
+    group.bench_function("mpsc", |b| {
+        b.iter_custom(|iters| {
+            let queue = MpscQueue::<u64>::new(CAPACITY).unwrap();
+            let (producer, mut consumer) = queue.split();
+
+            let start = Clock::global().ticks();
+
+            let producer_handle = thread::spawn(move || {
+                for i in 0..iters {
+                    while producer.push(i).is_err() {
+                        std::hint::spin_loop();
+                    }
+                }
+            });
+
+            for _ in 0..iters {
+                while consumer.pop().is_err() {
+                    std::hint::spin_loop();
+                }
+            }
+
+            producer_handle.join().unwrap();
+            Clock::global().elapsed(start)
+        });
+    });
+
+    group.bench_function("mpmc", |b| {
+        b.iter_custom(|iters| {
+            let queue = MpmcQueue::<u64>::new(CAPACITY).unwrap();
+            let (sender, receiver) = queue.split();
+
+            let start = Clock::global().ticks();
+
+            let producer_handle = thread::spawn(move || {
+                for i in 0..iters {
+                    sender.push(i);
+                }
+            });
+
+            for _ in 0..iters {
+                receiver.pop();
+            }
+
+            producer_handle.join().unwrap();
+            Clock::global().elapsed(start)
+        });
+    });
+
+    group.finish();
+}
+
+fn bench_mpmc_contention(c: &mut Criterion) {
+    let mut group = c.benchmark_group("mpmc_contention");
+    group.throughput(Throughput::Elements(1));
+
+    for threads in [2u64, 4].iter() {
+        group.bench_with_input(
+            BenchmarkId::new("producers_consumers", format!("{}x{}", threads, threads)),
+            threads,
+            |b, &threads| {
+                b.iter_custom(|iters| {
+                    let queue = MpmcQueue::<u64>::new(CAPACITY).unwrap();
+                    let (sender, receiver) = queue.split();
+                    let per_thread = iters / threads;
+
+                    let start = Clock::global().ticks();
+
+                    let mut handles = Vec::new();
+                    for _ in 0..threads {
+                        let sender = sender.clone();
+                        handles.push(thread::spawn(move || {
+                            for i in 0..per_thread {
+                                sender.push(i);
+                            }
+                        }));
+                        let receiver = receiver.clone();
+                        handles.push(thread::spawn(move || {
+                            for _ in 0..per_thread {
+                                receiver.pop();
+                            }
+                        }));
+                    }
+
+                    for handle in handles {
+                        handle.join().unwrap();
+                    }
+                    Clock::global().elapsed(start)
+                });
+            },
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_single_pair, bench_mpmc_contention);
+criterion_main!(benches);
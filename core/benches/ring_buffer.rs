@@ -1,7 +1,8 @@
 use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
-use ::core::ring_buffer::RingBuffer;
+use ferrite_core::clock::Clock;
+use ferrite_core::ring_buffer::RingBuffer;
 use std::thread;
-use std::time::{Instant, Duration};
+use std::time::Duration;
 
 fn bench_spsc_throughput(c: &mut Criterion) {
     let mut group = c.benchmark_group("spsc_throughput");
@@ -18,7 +19,7 @@ fn bench_spsc_throughput(c: &mut Criterion) {
                     let buffer = RingBuffer::<u64>::new(capacity).unwrap();
                     let (mut producer, mut consumer) = buffer.split();
                     
-                    let start = Instant::now();
+                    let start = Clock::global().ticks();
                     
                     let producer_handle = thread::spawn(move || {
                         for i in 0..iters {
@@ -42,7 +43,7 @@ fn bench_spsc_throughput(c: &mut Criterion) {
                     producer_handle.join().unwrap();
                     consumer_handle.join().unwrap();
                     
-                    start.elapsed()
+                    Clock::global().elapsed(start)
                 });
             },
         );
@@ -93,7 +94,7 @@ fn bench_ops_per_second(c: &mut Criterion) {
             let buffer = RingBuffer::<u64>::new(1024).unwrap();
             let (mut producer, mut consumer) = buffer.split();
             
-            let start = Instant::now();
+            let start = Clock::global().ticks();
             
             let producer_handle = thread::spawn(move || {
                 for i in 0..iters {
@@ -117,7 +118,7 @@ fn bench_ops_per_second(c: &mut Criterion) {
             producer_handle.join().unwrap();
             consumer_handle.join().unwrap();
             
-            let elapsed = start.elapsed();
+            let elapsed = Clock::global().elapsed(start);
             
             // Print ops/sec for verification
             let ops_per_sec = iters as f64 / elapsed.as_secs_f64();
@@ -187,7 +188,7 @@ fn bench_contention(c: &mut Criterion) {
             let buffer = RingBuffer::<u64>::new(16).unwrap(); // Small buffer for high contention
             let (mut producer, mut consumer) = buffer.split();
             
-            let start = Instant::now();
+            let start = Clock::global().ticks();
             
             let producer_handle = thread::spawn(move || {
                 for i in 0..iters {
@@ -208,7 +209,7 @@ fn bench_contention(c: &mut Criterion) {
             producer_handle.join().unwrap();
             consumer_handle.join().unwrap();
             
-            start.elapsed()
+            Clock::global().elapsed(start)
         });
     });
     
@@ -222,7 +223,7 @@ fn bench_contention(c: &mut Criterion) {
                 producer.push(i).unwrap();
             }
             
-            let start = Instant::now();
+            let start = Clock::global().ticks();
             
             let producer_handle = thread::spawn(move || {
                 for i in 0..iters {
@@ -240,7 +241,7 @@ fn bench_contention(c: &mut Criterion) {
             producer_handle.join().unwrap();
             consumer_handle.join().unwrap();
             
-            start.elapsed()
+            Clock::global().elapsed(start)
         });
     });
     
@@ -1,11 +1,15 @@
-use ::core::ring_buffer::RingBuffer;
+use ferrite_core::clock::Clock;
+use ferrite_core::ring_buffer::RingBuffer;
 use std::thread;
-use std::time::Instant;
 
 fn main() {
     println!("Ring Buffer Performance Test");
     println!("============================\n");
     
+    // Calibrate the TSC clock before anything is timed
+    let clock = Clock::global();
+    println!("Clock: {:?} at {:.3} GHz\n", clock.source(), clock.ticks_per_second() as f64 / 1e9);
+    
     // Test different buffer sizes
     for &capacity_pow in &[10, 12, 14, 16, 18] {
         let capacity = 1 << capacity_pow;
@@ -16,23 +20,23 @@ fn main() {
         
         let iterations = 10_000_000u64;
         
-        let start = Instant::now();
+        let start = clock.ticks();
         
         let producer_handle = thread::spawn(move || {
-            let start = Instant::now();
+            let start = clock.ticks();
             for i in 0..iterations {
                 while producer.push(i).is_err() {
                     std::hint::spin_loop();
                 }
             }
-            let elapsed = start.elapsed();
+            let elapsed = clock.elapsed(start);
             println!("  Producer: {} ops in {:?}", iterations, elapsed);
             let ops_per_sec = iterations as f64 / elapsed.as_secs_f64();
             println!("  Producer rate: {:.2}M ops/sec", ops_per_sec / 1_000_000.0);
         });
         
         let consumer_handle = thread::spawn(move || {
-            let start = Instant::now();
+            let start = clock.ticks();
             for _ in 0..iterations {
                 loop {
                     if consumer.pop().is_ok() {
@@ -41,7 +45,7 @@ fn main() {
                     std::hint::spin_loop();
                 }
             }
-            let elapsed = start.elapsed();
+            let elapsed = clock.elapsed(start);
             println!("  Consumer: {} ops in {:?}", iterations, elapsed);
             let ops_per_sec = iterations as f64 / elapsed.as_secs_f64();
             println!("  Consumer rate: {:.2}M ops/sec", ops_per_sec / 1_000_000.0);
@@ -50,7 +54,7 @@ fn main() {
         producer_handle.join().unwrap();
         consumer_handle.join().unwrap();
         
-        let total_elapsed = start.elapsed();
+        let total_elapsed = clock.elapsed(start);
         let total_ops = iterations;
         let ops_per_sec = total_ops as f64 / total_elapsed.as_secs_f64();
         
@@ -80,21 +84,20 @@ fn main() {
     
     let mut latencies = Vec::with_capacity(1_000_000);
     
+    // Raw ticks per operation; rdtscp keeps the reads from drifting into the timed code
     for i in 0..1_000_000 {
-        let start = Instant::now();
+        let start = clock.ticks_ordered();
         producer.push(i).unwrap();
-        let push_time = start.elapsed();
-        
-        let start = Instant::now();
+        let pushed = clock.ticks_ordered();
         consumer.pop().unwrap();
-        let pop_time = start.elapsed();
+        let popped = clock.ticks_ordered();
         
-        latencies.push((push_time, pop_time));
+        latencies.push((pushed - start, popped - pushed));
     }
     
     // Calculate percentiles
-    let mut push_times: Vec<_> = latencies.iter().map(|(p, _)| p.as_nanos()).collect();
-    let mut pop_times: Vec<_> = latencies.iter().map(|(_, p)| p.as_nanos()).collect();
+    let mut push_times: Vec<_> = latencies.iter().map(|&(p, _)| clock.ticks_to_nanos(p)).collect();
+    let mut pop_times: Vec<_> = latencies.iter().map(|&(_, p)| clock.ticks_to_nanos(p)).collect();
     
     push_times.sort_unstable();
     pop_times.sort_unstable();
@@ -0,0 +1,539 @@
+use std::cell::UnsafeCell;
+use std::error::Error;
+use std::fmt;
+use std::mem::MaybeUninit;
+use std::ptr;
+use std::sync::atomic::{fence, AtomicU64, Ordering};
+use std::sync::Arc;
+
+use crate::cache_padded::CachePadded;
+use crate::sync::Backoff;
+
+/// Cursor value marking a subscriber slot as free
+const IDLE: u64 = u64::MAX;
+
+/// Error types for broadcast ring operations
+#[derive(Debug, Clone, PartialEq)]
+pub enum BroadcastError {
+    /// Capacity must be a power of two and greater than 0
+    InvalidCapacity(usize),
+    /// The slowest subscriber is a full buffer behind the publisher
+    BufferFull,
+    /// No new items have been published since the last receive
+    BufferEmpty,
+    /// The subscriber fell behind and skipped this many items
+    Lagged(u64),
+    /// Every subscriber slot is taken; carries the configured limit
+    TooManySubscribers(usize),
+}
+
+impl fmt::Display for BroadcastError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BroadcastError::InvalidCapacity(cap) => {
+                write!(f, "Invalid capacity: {}. Must be a power of two and greater than 0", cap)
+            }
+            BroadcastError::BufferFull => write!(f, "Buffer is full"),
+            BroadcastError::BufferEmpty => write!(f, "Buffer is empty"),
+            BroadcastError::Lagged(missed) => write!(f, "Subscriber lagged behind by {} items", missed),
+            BroadcastError::TooManySubscribers(limit) => {
+                write!(f, "Subscriber limit of {} reached", limit)
+            }
+        }
+    }
+}
+
+impl Error for BroadcastError {}
+
+/// What the publisher does when the slowest subscriber is a full buffer behind
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// The publisher waits for the slowest subscriber; no item is ever lost
+    Backpressure,
+    /// The publisher overwrites the oldest item; slow subscribers see `Lagged`
+    Lossy,
+}
+
+/// A single-producer broadcast ring where every subscriber sees every item
+///
+/// Each subscriber keeps its own cursor into one shared buffer, so fanning a
+/// stream out to N readers costs one write per item instead of N copies.
+/// Subscribers can join and leave at any time; a new subscriber starts at the
+/// next item to be published.
+///
+/// Items are copied out on receive, which is why `T` must be `Copy`. In
+/// [`OverflowPolicy::Lossy`] mode a subscriber may read a slot while the
+/// publisher overwrites it; every slot carries a sequence stamp that is checked
+/// before and after the copy, and a torn copy is discarded and reported as
+/// [`BroadcastError::Lagged`].
+///
+/// # Thread Safety
+///
+/// There is exactly one [`Publisher`]. Each [`Subscriber`] is owned by one
+/// thread at a time; use [`Subscriber::subscribe`] to create another.
+///
+/// # Example
+///
+/// ```
+/// use ferrite_core::broadcast::{BroadcastRing, OverflowPolicy};
+///
+/// let ring = BroadcastRing::<u64>::new(1024, 8, OverflowPolicy::Backpressure).unwrap();
+/// let (mut publisher, mut first) = ring.split();
+/// let mut second = publisher.subscribe().unwrap();
+///
+/// publisher.try_publish(42).unwrap();
+/// assert_eq!(first.try_recv(), Ok(42));
+/// assert_eq!(second.try_recv(), Ok(42));
+/// ```
+pub struct BroadcastRing<T> {
+    slots: Box<[Slot<T>]>,
+    max_subscribers: usize,
+    policy: OverflowPolicy,
+}
+
+/// A buffer slot guarded by a sequence stamp
+///
+/// For sequence `s` the stamp is `2s + 1` while the value is being written and
+/// `2s + 2` once it is complete, so readers can tell a fresh value from a stale
+/// or overwritten one.
+struct Slot<T> {
+    stamp: AtomicU64,
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+
+struct Shared<T> {
+    /// Next sequence the publisher will write
+    head: CachePadded<AtomicU64>,
+    /// Next sequence each subscriber will read, or `IDLE` for a free slot
+    cursors: Box<[CachePadded<AtomicU64>]>,
+    slots: Box<[Slot<T>]>,
+    mask: u64,
+    policy: OverflowPolicy,
+}
+
+impl<T: Copy> BroadcastRing<T> {
+    /// Creates a new broadcast ring
+    ///
+    /// # Arguments
+    ///
+    /// * `capacity` - Number of items buffered. Must be a power of two and greater than 0.
+    /// * `max_subscribers` - Number of subscriber slots to pre-allocate. Must be greater than 0.
+    /// * `policy` - Behaviour when the slowest subscriber is a full buffer behind
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(BroadcastRing<T>)` - A new broadcast ring
+    /// * `Err(BroadcastError)` - If capacity or the subscriber limit is invalid
+    pub fn new(
+        capacity: usize,
+        max_subscribers: usize,
+        policy: OverflowPolicy,
+    ) -> Result<Self, BroadcastError> {
+        if capacity == 0 || !capacity.is_power_of_two() {
+            return Err(BroadcastError::InvalidCapacity(capacity));
+        }
+        if max_subscribers == 0 {
+            return Err(BroadcastError::TooManySubscribers(0));
+        }
+
+        let mut slots = Vec::with_capacity(capacity);
+        for _ in 0..capacity {
+            slots.push(Slot {
+                stamp: AtomicU64::new(0),
+                value: UnsafeCell::new(MaybeUninit::uninit()),
+            });
+        }
+
+        Ok(BroadcastRing {
+            slots: slots.into_boxed_slice(),
+            max_subscribers,
+            policy,
+        })
+    }
+
+    /// Returns the capacity of the ring
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Splits the ring into its publisher and a first subscriber
+    ///
+    /// Further subscribers are created with [`Publisher::subscribe`] or
+    /// [`Subscriber::subscribe`].
+    pub fn split(self) -> (Publisher<T>, Subscriber<T>) {
+        let capacity = self.slots.len() as u64;
+        let mut cursors = Vec::with_capacity(self.max_subscribers);
+        for _ in 0..self.max_subscribers {
+            cursors.push(CachePadded { value: AtomicU64::new(IDLE) });
+        }
+        // The first subscriber starts at sequence 0 before anything is published
+        cursors[0].value.store(0, Ordering::Relaxed);
+
+        let shared = Arc::new(Shared {
+            head: CachePadded { value: AtomicU64::new(0) },
+            cursors: cursors.into_boxed_slice(),
+            slots: self.slots,
+            mask: capacity - 1,
+            policy: self.policy,
+        });
+
+        let subscriber = Subscriber {
+            shared: shared.clone(),
+            id: 0,
+            next: 0,
+        };
+
+        let publisher = Publisher {
+            shared,
+            head: 0,
+            limit: capacity,
+        };
+
+        (publisher, subscriber)
+    }
+}
+
+/// Publishing half of a broadcast ring
+pub struct Publisher<T> {
+    shared: Arc<Shared<T>>,
+    /// Local copy of the next sequence to write
+    head: u64,
+    /// Sequences below this are known to be free for writing
+    limit: u64,
+}
+
+/// A reader with its own cursor into a broadcast ring
+///
+/// Dropping the subscriber releases its slot; in backpressure mode the
+/// publisher stops waiting for it immediately.
+pub struct Subscriber<T> {
+    shared: Arc<Shared<T>>,
+    /// Index of this subscriber's cursor slot
+    id: usize,
+    /// Local copy of the next sequence to read
+    next: u64,
+}
+
+unsafe impl<T: Send> Send for Publisher<T> {}
+unsafe impl<T: Send> Send for Subscriber<T> {}
+
+impl<T: Copy> Publisher<T> {
+    /// Attempts to publish an item to every subscriber
+    ///
+    /// In lossy mode this always succeeds.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - Item was published
+    /// * `Err(BroadcastError::BufferFull)` - Backpressure mode only: the slowest subscriber is a full buffer behind
+    pub fn try_publish(&mut self, value: T) -> Result<(), BroadcastError> {
+        let seq = self.head;
+
+        if self.shared.policy == OverflowPolicy::Backpressure && seq >= self.limit {
+            self.limit = self.shared.slowest_cursor(seq) + self.shared.mask + 1;
+            if seq >= self.limit {
+                return Err(BroadcastError::BufferFull);
+            }
+        }
+
+        let slot = &self.shared.slots[(seq & self.shared.mask) as usize];
+        slot.stamp.store(2 * seq + 1, Ordering::Relaxed);
+        // Keeps the odd stamp ahead of the value writes for concurrent readers
+        fence(Ordering::Release);
+        unsafe { ptr::write_volatile(slot.value.get(), MaybeUninit::new(value)) };
+        slot.stamp.store(2 * seq + 2, Ordering::Release);
+
+        self.head = seq + 1;
+        self.shared.head.value.store(self.head, Ordering::Release);
+        Ok(())
+    }
+
+    /// Publishes an item, waiting for the slowest subscriber if necessary
+    ///
+    /// Spins briefly and then yields the thread between attempts.
+    pub fn publish(&mut self, value: T) {
+        let mut backoff = Backoff::new();
+        while self.try_publish(value).is_err() {
+            backoff.snooze();
+        }
+    }
+
+    /// Adds a subscriber that receives every item published from now on
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Subscriber<T>)` - A new subscriber
+    /// * `Err(BroadcastError::TooManySubscribers)` - Every subscriber slot is taken
+    pub fn subscribe(&self) -> Result<Subscriber<T>, BroadcastError> {
+        Subscriber::join(&self.shared)
+    }
+
+    /// Returns the number of active subscribers
+    pub fn subscriber_count(&self) -> usize {
+        self.shared
+            .cursors
+            .iter()
+            .filter(|cursor| cursor.value.load(Ordering::Relaxed) != IDLE)
+            .count()
+    }
+}
+
+impl<T> Shared<T> {
+    /// Returns the position of the slowest active subscriber, capped at `head`
+    fn slowest_cursor(&self, head: u64) -> u64 {
+        // Pairs with the fence in `Subscriber::join`: a joining subscriber either
+        // shows up here or is guaranteed to have started at or after `head`.
+        fence(Ordering::SeqCst);
+        self.cursors
+            .iter()
+            .map(|cursor| cursor.value.load(Ordering::Acquire))
+            .filter(|&position| position != IDLE)
+            .fold(head, u64::min)
+    }
+}
+
+impl<T: Copy> Subscriber<T> {
+    fn join(shared: &Arc<Shared<T>>) -> Result<Self, BroadcastError> {
+        for (id, cursor) in shared.cursors.iter().enumerate() {
+            let mut head = shared.head.value.load(Ordering::Acquire);
+            if cursor
+                .value
+                .compare_exchange(IDLE, head, Ordering::SeqCst, Ordering::Relaxed)
+                .is_err()
+            {
+                continue;
+            }
+
+            // Re-read the head until our cursor is known to be visible to the
+            // publisher before it could have lapped the position we chose.
+            loop {
+                fence(Ordering::SeqCst);
+                let current = shared.head.value.load(Ordering::Acquire);
+                if current == head {
+                    break;
+                }
+                head = current;
+                cursor.value.store(head, Ordering::SeqCst);
+            }
+
+            return Ok(Subscriber {
+                shared: shared.clone(),
+                id,
+                next: head,
+            });
+        }
+
+        Err(BroadcastError::TooManySubscribers(shared.cursors.len()))
+    }
+
+    /// Attempts to receive the next item
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(T)` - The next item
+    /// * `Err(BroadcastError::BufferEmpty)` - Nothing new has been published
+    /// * `Err(BroadcastError::Lagged(n))` - `n` items were overwritten before they
+    ///   could be read; the subscriber now points at the oldest item still buffered
+    pub fn try_recv(&mut self) -> Result<T, BroadcastError> {
+        let seq = self.next;
+        let expected = 2 * seq + 2;
+        let slot = &self.shared.slots[(seq & self.shared.mask) as usize];
+
+        let stamp = slot.stamp.load(Ordering::Acquire);
+        if stamp < expected {
+            return Err(BroadcastError::BufferEmpty);
+        }
+
+        if stamp == expected {
+            let value = unsafe { ptr::read_volatile(slot.value.get()) };
+            // Keeps the value reads ahead of the validating stamp load
+            fence(Ordering::Acquire);
+            if slot.stamp.load(Ordering::Relaxed) == expected {
+                self.advance(seq + 1);
+                return Ok(unsafe { value.assume_init() });
+            }
+        }
+
+        Err(self.skip_lagged(seq))
+    }
+
+    /// Returns the number of items published but not yet received
+    ///
+    /// In lossy mode this can exceed the capacity when the subscriber has lagged.
+    pub fn len(&self) -> usize {
+        let head = self.shared.head.value.load(Ordering::Acquire);
+        head.saturating_sub(self.next) as usize
+    }
+
+    /// Checks if there is nothing new to receive
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Adds another subscriber that receives every item published from now on
+    ///
+    /// The new subscriber does not inherit this subscriber's position.
+    pub fn subscribe(&self) -> Result<Subscriber<T>, BroadcastError> {
+        Subscriber::join(&self.shared)
+    }
+
+    fn advance(&mut self, next: u64) {
+        self.next = next;
+        self.shared.cursors[self.id].value.store(next, Ordering::Release);
+    }
+
+    /// Moves the cursor to the oldest item that has not been overwritten
+    fn skip_lagged(&mut self, seq: u64) -> BroadcastError {
+        let head = self.shared.head.value.load(Ordering::Acquire);
+        let capacity = self.shared.mask + 1;
+        // The publisher may already be rewriting the slot of `head - capacity`
+        let oldest = (head + 1).saturating_sub(capacity).max(seq + 1);
+        self.advance(oldest);
+        BroadcastError::Lagged(oldest - seq)
+    }
+}
+
+impl<T> Drop for Subscriber<T> {
+    fn drop(&mut self) {
+        self.shared.cursors[self.id].value.store(IDLE, Ordering::Release);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_invalid_arguments() {
+        assert!(matches!(
+            BroadcastRing::<u32>::new(12, 4, OverflowPolicy::Lossy),
+            Err(BroadcastError::InvalidCapacity(12))
+        ));
+        assert!(matches!(
+            BroadcastRing::<u32>::new(16, 0, OverflowPolicy::Lossy),
+            Err(BroadcastError::TooManySubscribers(0))
+        ));
+    }
+
+    #[test]
+    fn test_every_subscriber_sees_every_item() {
+        let ring = BroadcastRing::<u32>::new(8, 4, OverflowPolicy::Backpressure).unwrap();
+        let (mut publisher, mut first) = ring.split();
+        let mut second = first.subscribe().unwrap();
+
+        for i in 0..5 {
+            publisher.try_publish(i).unwrap();
+        }
+        assert_eq!(first.len(), 5);
+
+        for i in 0..5 {
+            assert_eq!(first.try_recv(), Ok(i));
+        }
+        assert_eq!(first.try_recv(), Err(BroadcastError::BufferEmpty));
+        for i in 0..5 {
+            assert_eq!(second.try_recv(), Ok(i));
+        }
+    }
+
+    #[test]
+    fn test_backpressure_waits_for_slowest() {
+        let ring = BroadcastRing::<u32>::new(4, 2, OverflowPolicy::Backpressure).unwrap();
+        let (mut publisher, mut fast) = ring.split();
+        let mut slow = publisher.subscribe().unwrap();
+
+        for i in 0..4 {
+            publisher.try_publish(i).unwrap();
+            assert_eq!(fast.try_recv(), Ok(i));
+        }
+        assert_eq!(publisher.try_publish(4), Err(BroadcastError::BufferFull));
+
+        assert_eq!(slow.try_recv(), Ok(0));
+        assert_eq!(publisher.try_publish(4), Ok(()));
+
+        // A departing subscriber no longer holds the publisher back
+        drop(slow);
+        for i in 5..20 {
+            publisher.try_publish(i).unwrap();
+            assert_eq!(fast.try_recv(), Ok(i - 1));
+        }
+    }
+
+    #[test]
+    fn test_lossy_reports_lag() {
+        let ring = BroadcastRing::<u32>::new(4, 1, OverflowPolicy::Lossy).unwrap();
+        let (mut publisher, mut subscriber) = ring.split();
+
+        for i in 0..10 {
+            publisher.try_publish(i).unwrap();
+        }
+
+        assert_eq!(subscriber.try_recv(), Err(BroadcastError::Lagged(7)));
+        assert_eq!(subscriber.try_recv(), Ok(7));
+        assert_eq!(subscriber.try_recv(), Ok(8));
+        assert_eq!(subscriber.try_recv(), Ok(9));
+        assert_eq!(subscriber.try_recv(), Err(BroadcastError::BufferEmpty));
+    }
+
+    #[test]
+    fn test_join_and_leave() {
+        let ring = BroadcastRing::<u32>::new(8, 2, OverflowPolicy::Backpressure).unwrap();
+        let (mut publisher, first) = ring.split();
+
+        publisher.try_publish(1).unwrap();
+        let mut late = publisher.subscribe().unwrap();
+        assert_eq!(publisher.subscriber_count(), 2);
+        assert_eq!(
+            publisher.subscribe().err(),
+            Some(BroadcastError::TooManySubscribers(2))
+        );
+
+        // Late joiners start at the next item published
+        assert_eq!(late.try_recv(), Err(BroadcastError::BufferEmpty));
+        publisher.try_publish(2).unwrap();
+        assert_eq!(late.try_recv(), Ok(2));
+
+        drop(first);
+        assert_eq!(publisher.subscriber_count(), 1);
+        assert!(publisher.subscribe().is_ok());
+    }
+
+    #[test]
+    fn test_concurrent_fanout() {
+        const ITEMS: u64 = 10_000;
+        let ring = BroadcastRing::<u64>::new(64, 4, OverflowPolicy::Backpressure).unwrap();
+        let (mut publisher, first) = ring.split();
+        let mut subscribers = vec![first];
+        for _ in 0..3 {
+            subscribers.push(publisher.subscribe().unwrap());
+        }
+
+        let readers: Vec<_> = subscribers
+            .into_iter()
+            .map(|mut subscriber| {
+                std::thread::spawn(move || {
+                    let mut sum = 0;
+                    for expected in 0..ITEMS {
+                        let value = loop {
+                            match subscriber.try_recv() {
+                                Ok(value) => break value,
+                                Err(BroadcastError::BufferEmpty) => std::thread::yield_now(),
+                                Err(err) => panic!("unexpected error: {}", err),
+                            }
+                        };
+                        assert_eq!(value, expected);
+                        sum += value;
+                    }
+                    sum
+                })
+            })
+            .collect();
+
+        for i in 0..ITEMS {
+            publisher.publish(i);
+        }
+
+        for reader in readers {
+            assert_eq!(reader.join().unwrap(), ITEMS * (ITEMS - 1) / 2);
+        }
+    }
+}
@@ -0,0 +1,6 @@
+/// Cache-line padding wrapper to avoid false sharing
+#[derive(Debug)]
+#[repr(align(64))]
+pub(crate) struct CachePadded<T> {
+    pub(crate) value: T,
+}
@@ -0,0 +1,696 @@
+//! `std::sync::mpsc`-style channels over the crate's lock-free queues
+//!
+//! [`bounded_spsc`], [`bounded_mpsc`] and [`bounded_mpmc`] wrap
+//! [`RingBuffer`](crate::ring_buffer::RingBuffer),
+//! [`MpscQueue`](crate::mpsc::MpscQueue) and [`MpmcQueue`](crate::mpmc::MpmcQueue)
+//! in the same [`Sender`] and [`Receiver`] types. The flavor is a type
+//! parameter, so a `Sender<T, Spsc>` cannot be cloned while a
+//! `Sender<T, Mpsc>` can, and every flavor shares one API.
+//!
+//! A channel is disconnected once every handle on the other side is dropped:
+//! sends fail straight away and receives fail once the queue is drained.
+//! Blocking calls wait by spinning and then yielding the thread; they never park.
+//!
+//! # Example
+//!
+//! ```
+//! use ferrite_core::channel::{bounded_mpsc, RecvError};
+//!
+//! let (sender, receiver) = bounded_mpsc::<u32>(64).unwrap();
+//!
+//! for id in 0..4 {
+//!     let sender = sender.clone();
+//!     std::thread::spawn(move || {
+//!         for i in 0..10 {
+//!             sender.send(id * 10 + i).unwrap();
+//!         }
+//!     });
+//! }
+//! drop(sender);
+//!
+//! // Ends once every sender is gone and the queue is drained
+//! assert_eq!(receiver.iter().count(), 40);
+//! assert_eq!(receiver.recv(), Err(RecvError));
+//! ```
+
+use std::cell::UnsafeCell;
+use std::error::Error;
+use std::fmt;
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::mpmc::{self, MpmcQueue};
+use crate::mpsc::{self, MpscQueue};
+use crate::ring_buffer::{self, RingBuffer, RingBufferError};
+use crate::sync::Backoff;
+
+/// Flavor of a channel with one sender and one receiver
+pub enum Spsc {}
+
+/// Flavor of a channel with cloneable senders and one receiver
+pub enum Mpsc {}
+
+/// Flavor of a channel with cloneable senders and receivers
+pub enum Mpmc {}
+
+/// Error returned by [`Sender::send`] when every receiver is gone; carries the value back
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct SendError<T>(pub T);
+
+/// Error returned by [`Sender::try_send`]
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum TrySendError<T> {
+    /// The channel is full; carries the value back
+    Full(T),
+    /// Every receiver is gone; carries the value back
+    Disconnected(T),
+}
+
+/// Error returned by [`Sender::send_timeout`]
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum SendTimeoutError<T> {
+    /// The channel stayed full until the timeout; carries the value back
+    Timeout(T),
+    /// Every receiver is gone; carries the value back
+    Disconnected(T),
+}
+
+/// Error returned by [`Receiver::recv`] when every sender is gone and the channel is empty
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecvError;
+
+/// Error returned by [`Receiver::try_recv`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TryRecvError {
+    /// The channel is empty
+    Empty,
+    /// Every sender is gone and the channel is empty
+    Disconnected,
+}
+
+/// Error returned by [`Receiver::recv_timeout`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecvTimeoutError {
+    /// The channel stayed empty until the timeout
+    Timeout,
+    /// Every sender is gone and the channel is empty
+    Disconnected,
+}
+
+// The value-carrying errors print without the value, so `T` needs no `Debug`
+impl<T> fmt::Debug for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SendError(..)")
+    }
+}
+
+impl<T> fmt::Debug for TrySendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrySendError::Full(_) => f.write_str("Full(..)"),
+            TrySendError::Disconnected(_) => f.write_str("Disconnected(..)"),
+        }
+    }
+}
+
+impl<T> fmt::Debug for SendTimeoutError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SendTimeoutError::Timeout(_) => f.write_str("Timeout(..)"),
+            SendTimeoutError::Disconnected(_) => f.write_str("Disconnected(..)"),
+        }
+    }
+}
+
+impl<T> fmt::Display for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Channel is disconnected")
+    }
+}
+
+impl<T> fmt::Display for TrySendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrySendError::Full(_) => write!(f, "Channel is full"),
+            TrySendError::Disconnected(_) => write!(f, "Channel is disconnected"),
+        }
+    }
+}
+
+impl<T> fmt::Display for SendTimeoutError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SendTimeoutError::Timeout(_) => write!(f, "Timed out waiting for room in the channel"),
+            SendTimeoutError::Disconnected(_) => write!(f, "Channel is disconnected"),
+        }
+    }
+}
+
+impl fmt::Display for RecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Channel is disconnected")
+    }
+}
+
+impl fmt::Display for TryRecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TryRecvError::Empty => write!(f, "Channel is empty"),
+            TryRecvError::Disconnected => write!(f, "Channel is disconnected"),
+        }
+    }
+}
+
+impl fmt::Display for RecvTimeoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecvTimeoutError::Timeout => write!(f, "Timed out waiting on the channel"),
+            RecvTimeoutError::Disconnected => write!(f, "Channel is disconnected"),
+        }
+    }
+}
+
+impl<T> Error for SendError<T> {}
+impl<T> Error for TrySendError<T> {}
+impl<T> Error for SendTimeoutError<T> {}
+impl Error for RecvError {}
+impl Error for TryRecvError {}
+impl Error for RecvTimeoutError {}
+
+/// Creates a channel for one sender and one receiver over a [`RingBuffer`]
+///
+/// Like the ring buffer, the channel holds up to `capacity - 1` values.
+///
+/// # Arguments
+///
+/// * `capacity` - The ring capacity. Must be a power of two and greater than 0.
+///
+/// # Returns
+///
+/// * `Ok((Sender, Receiver))` - The two ends of a new channel
+/// * `Err(RingBufferError)` - If capacity is invalid
+pub fn bounded_spsc<T>(capacity: usize) -> Result<Channel<T, Spsc>, RingBufferError> {
+    let (producer, consumer) = RingBuffer::new(capacity)?.split();
+    Ok(connect(
+        Tx::Spsc(UnsafeCell::new(producer)),
+        Rx::Spsc(UnsafeCell::new(consumer)),
+    ))
+}
+
+/// Creates a channel for cloneable senders and one receiver over an [`MpscQueue`]
+///
+/// The channel holds up to `capacity` values.
+///
+/// # Arguments
+///
+/// * `capacity` - The queue capacity. Must be a power of two and greater than 0.
+///
+/// # Returns
+///
+/// * `Ok((Sender, Receiver))` - The two ends of a new channel
+/// * `Err(RingBufferError)` - If capacity is invalid
+pub fn bounded_mpsc<T>(capacity: usize) -> Result<Channel<T, Mpsc>, RingBufferError> {
+    let (producer, consumer) = MpscQueue::new(capacity)?.split();
+    Ok(connect(Tx::Mpsc(producer), Rx::Mpsc(UnsafeCell::new(consumer))))
+}
+
+/// Creates a channel for cloneable senders and receivers over an [`MpmcQueue`]
+///
+/// The channel holds up to `capacity` values.
+///
+/// # Arguments
+///
+/// * `capacity` - The queue capacity. Must be a power of two and greater than 0.
+///
+/// # Returns
+///
+/// * `Ok((Sender, Receiver))` - The two ends of a new channel
+/// * `Err(RingBufferError)` - If capacity is invalid
+pub fn bounded_mpmc<T>(capacity: usize) -> Result<Channel<T, Mpmc>, RingBufferError> {
+    let (sender, receiver) = MpmcQueue::new(capacity)?.split();
+    Ok(connect(Tx::Mpmc(sender), Rx::Mpmc(receiver)))
+}
+
+fn connect<T, F>(tx: Tx<T>, rx: Rx<T>) -> Channel<T, F> {
+    let handles = Arc::new(Handles {
+        senders: AtomicUsize::new(1),
+        receivers: AtomicUsize::new(1),
+    });
+
+    let sender = Sender {
+        tx,
+        handles: handles.clone(),
+        _flavor: PhantomData,
+    };
+    let receiver = Receiver {
+        rx,
+        handles,
+        _flavor: PhantomData,
+    };
+
+    (sender, receiver)
+}
+
+/// Live handle counts, used to detect disconnection
+struct Handles {
+    senders: AtomicUsize,
+    receivers: AtomicUsize,
+}
+
+enum Tx<T> {
+    /// The ring producer needs `&mut`; the `Spsc` sender is neither `Sync` nor `Clone`
+    Spsc(UnsafeCell<ring_buffer::Producer<T>>),
+    Mpsc(mpsc::Producer<T>),
+    Mpmc(mpmc::Sender<T>),
+}
+
+enum Rx<T> {
+    /// The single consumers need `&mut`; their receivers are neither `Sync` nor `Clone`
+    Spsc(UnsafeCell<ring_buffer::Consumer<T>>),
+    Mpsc(UnsafeCell<mpsc::Consumer<T>>),
+    Mpmc(mpmc::Receiver<T>),
+}
+
+/// The two ends of a new channel
+pub type Channel<T, F> = (Sender<T, F>, Receiver<T, F>);
+
+/// Sending end of a channel
+pub struct Sender<T, F> {
+    tx: Tx<T>,
+    handles: Arc<Handles>,
+    _flavor: PhantomData<F>,
+}
+
+/// Receiving end of a channel
+pub struct Receiver<T, F> {
+    rx: Rx<T>,
+    handles: Arc<Handles>,
+    _flavor: PhantomData<F>,
+}
+
+// These flavors never hold the `UnsafeCell` variants that make the handles `!Sync`
+unsafe impl<T: Send> Sync for Sender<T, Mpsc> {}
+unsafe impl<T: Send> Sync for Sender<T, Mpmc> {}
+unsafe impl<T: Send> Sync for Receiver<T, Mpmc> {}
+
+impl<T, F> Sender<T, F> {
+    /// Attempts to send a value without waiting
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - The value was sent
+    /// * `Err(TrySendError::Full(value))` - The channel is full
+    /// * `Err(TrySendError::Disconnected(value))` - Every receiver is gone
+    pub fn try_send(&self, value: T) -> Result<(), TrySendError<T>> {
+        if self.handles.receivers.load(Ordering::Acquire) == 0 {
+            return Err(TrySendError::Disconnected(value));
+        }
+
+        match &self.tx {
+            Tx::Spsc(producer) => {
+                // Only this handle touches the producer, and it is not `Sync`
+                let producer = unsafe { &mut *producer.get() };
+                if producer.is_full() {
+                    return Err(TrySendError::Full(value));
+                }
+                // A single producer cannot lose the room it just saw
+                let _ = producer.push(value);
+                Ok(())
+            }
+            Tx::Mpsc(producer) => producer.offer(value).map_err(TrySendError::Full),
+            Tx::Mpmc(sender) => sender.offer(value).map_err(TrySendError::Full),
+        }
+    }
+
+    /// Sends a value, waiting for room if the channel is full
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - The value was sent
+    /// * `Err(SendError(value))` - Every receiver is gone
+    pub fn send(&self, value: T) -> Result<(), SendError<T>> {
+        self.send_until(value, None).map_err(|err| match err {
+            SendTimeoutError::Timeout(value) | SendTimeoutError::Disconnected(value) => SendError(value),
+        })
+    }
+
+    /// Sends a value, waiting up to `timeout` for room if the channel is full
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - The value was sent
+    /// * `Err(SendTimeoutError::Timeout(value))` - The channel stayed full
+    /// * `Err(SendTimeoutError::Disconnected(value))` - Every receiver is gone
+    pub fn send_timeout(&self, value: T, timeout: Duration) -> Result<(), SendTimeoutError<T>> {
+        self.send_until(value, Instant::now().checked_add(timeout))
+    }
+
+    fn send_until(&self, mut value: T, deadline: Option<Instant>) -> Result<(), SendTimeoutError<T>> {
+        let mut backoff = Backoff::new();
+        loop {
+            match self.try_send(value) {
+                Ok(()) => return Ok(()),
+                Err(TrySendError::Disconnected(rejected)) => {
+                    return Err(SendTimeoutError::Disconnected(rejected))
+                }
+                Err(TrySendError::Full(rejected)) => value = rejected,
+            }
+
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                return Err(SendTimeoutError::Timeout(value));
+            }
+            backoff.snooze();
+        }
+    }
+
+    /// Checks if every receiver is gone
+    pub fn is_disconnected(&self) -> bool {
+        self.handles.receivers.load(Ordering::Acquire) == 0
+    }
+}
+
+impl<T, F> Receiver<T, F> {
+    /// Attempts to receive a value without waiting
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(T)` - A value
+    /// * `Err(TryRecvError::Empty)` - The channel is empty
+    /// * `Err(TryRecvError::Disconnected)` - Every sender is gone and the channel is empty
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        if let Some(value) = self.pop() {
+            return Ok(value);
+        }
+        if self.handles.senders.load(Ordering::Acquire) == 0 {
+            // A sender may have sent just before it was dropped
+            return self.pop().ok_or(TryRecvError::Disconnected);
+        }
+        Err(TryRecvError::Empty)
+    }
+
+    /// Receives a value, waiting for one if the channel is empty
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(T)` - A value
+    /// * `Err(RecvError)` - Every sender is gone and the channel is empty
+    pub fn recv(&self) -> Result<T, RecvError> {
+        self.recv_until(None).map_err(|_| RecvError)
+    }
+
+    /// Receives a value, waiting up to `timeout` for one if the channel is empty
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(T)` - A value
+    /// * `Err(RecvTimeoutError::Timeout)` - The channel stayed empty
+    /// * `Err(RecvTimeoutError::Disconnected)` - Every sender is gone and the channel is empty
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<T, RecvTimeoutError> {
+        self.recv_until(Instant::now().checked_add(timeout))
+    }
+
+    fn recv_until(&self, deadline: Option<Instant>) -> Result<T, RecvTimeoutError> {
+        let mut backoff = Backoff::new();
+        loop {
+            match self.try_recv() {
+                Ok(value) => return Ok(value),
+                Err(TryRecvError::Disconnected) => return Err(RecvTimeoutError::Disconnected),
+                Err(TryRecvError::Empty) => {}
+            }
+
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                return Err(RecvTimeoutError::Timeout);
+            }
+            backoff.snooze();
+        }
+    }
+
+    /// Returns an iterator that waits for values until the channel is disconnected
+    pub fn iter(&self) -> Iter<'_, T, F> {
+        Iter { receiver: self }
+    }
+
+    /// Returns an iterator over the values available without waiting
+    pub fn try_iter(&self) -> TryIter<'_, T, F> {
+        TryIter { receiver: self }
+    }
+
+    /// Returns the number of values waiting to be received
+    pub fn len(&self) -> usize {
+        match &self.rx {
+            // Only this handle touches the consumer, and it is not `Sync`
+            Rx::Spsc(consumer) => unsafe { &*consumer.get() }.len(),
+            Rx::Mpsc(consumer) => unsafe { &*consumer.get() }.len(),
+            Rx::Mpmc(receiver) => receiver.len(),
+        }
+    }
+
+    /// Checks if no values are waiting to be received
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Checks if every sender is gone
+    ///
+    /// Values sent before the last sender was dropped can still be received.
+    pub fn is_disconnected(&self) -> bool {
+        self.handles.senders.load(Ordering::Acquire) == 0
+    }
+
+    fn pop(&self) -> Option<T> {
+        match &self.rx {
+            // Only this handle touches the consumer, and it is not `Sync`
+            Rx::Spsc(consumer) => unsafe { &mut *consumer.get() }.pop().ok(),
+            Rx::Mpsc(consumer) => unsafe { &mut *consumer.get() }.pop().ok(),
+            Rx::Mpmc(receiver) => receiver.try_pop().ok(),
+        }
+    }
+}
+
+impl<T> Clone for Sender<T, Mpsc> {
+    fn clone(&self) -> Self {
+        let Tx::Mpsc(producer) = &self.tx else {
+            unreachable!("MPSC senders always hold an MPSC producer")
+        };
+        self.handles.senders.fetch_add(1, Ordering::Relaxed);
+        Sender {
+            tx: Tx::Mpsc(producer.clone()),
+            handles: self.handles.clone(),
+            _flavor: PhantomData,
+        }
+    }
+}
+
+impl<T> Clone for Sender<T, Mpmc> {
+    fn clone(&self) -> Self {
+        let Tx::Mpmc(sender) = &self.tx else {
+            unreachable!("MPMC senders always hold an MPMC sender")
+        };
+        self.handles.senders.fetch_add(1, Ordering::Relaxed);
+        Sender {
+            tx: Tx::Mpmc(sender.clone()),
+            handles: self.handles.clone(),
+            _flavor: PhantomData,
+        }
+    }
+}
+
+impl<T> Clone for Receiver<T, Mpmc> {
+    fn clone(&self) -> Self {
+        let Rx::Mpmc(receiver) = &self.rx else {
+            unreachable!("MPMC receivers always hold an MPMC receiver")
+        };
+        self.handles.receivers.fetch_add(1, Ordering::Relaxed);
+        Receiver {
+            rx: Rx::Mpmc(receiver.clone()),
+            handles: self.handles.clone(),
+            _flavor: PhantomData,
+        }
+    }
+}
+
+impl<T, F> Drop for Sender<T, F> {
+    fn drop(&mut self) {
+        // Release makes this sender's values visible to a receiver that sees the count drop
+        self.handles.senders.fetch_sub(1, Ordering::Release);
+    }
+}
+
+impl<T, F> Drop for Receiver<T, F> {
+    fn drop(&mut self) {
+        self.handles.receivers.fetch_sub(1, Ordering::Release);
+    }
+}
+
+impl<T, F> fmt::Debug for Sender<T, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sender").finish_non_exhaustive()
+    }
+}
+
+impl<T, F> fmt::Debug for Receiver<T, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Receiver").finish_non_exhaustive()
+    }
+}
+
+/// Waiting iterator over a [`Receiver`]; see [`Receiver::iter`]
+pub struct Iter<'a, T, F> {
+    receiver: &'a Receiver<T, F>,
+}
+
+/// Non-waiting iterator over a [`Receiver`]; see [`Receiver::try_iter`]
+pub struct TryIter<'a, T, F> {
+    receiver: &'a Receiver<T, F>,
+}
+
+/// Owning waiting iterator over a [`Receiver`]
+pub struct IntoIter<T, F> {
+    receiver: Receiver<T, F>,
+}
+
+impl<T, F> Iterator for Iter<'_, T, F> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.receiver.recv().ok()
+    }
+}
+
+impl<T, F> Iterator for TryIter<'_, T, F> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.receiver.try_recv().ok()
+    }
+}
+
+impl<T, F> Iterator for IntoIter<T, F> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.receiver.recv().ok()
+    }
+}
+
+impl<'a, T, F> IntoIterator for &'a Receiver<T, F> {
+    type Item = T;
+    type IntoIter = Iter<'a, T, F>;
+
+    fn into_iter(self) -> Iter<'a, T, F> {
+        self.iter()
+    }
+}
+
+impl<T, F> IntoIterator for Receiver<T, F> {
+    type Item = T;
+    type IntoIter = IntoIter<T, F>;
+
+    fn into_iter(self) -> IntoIter<T, F> {
+        IntoIter { receiver: self }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invalid_capacity() {
+        assert_eq!(bounded_spsc::<u32>(3).unwrap_err(), RingBufferError::InvalidCapacity(3));
+        assert_eq!(bounded_mpsc::<u32>(0).unwrap_err(), RingBufferError::InvalidCapacity(0));
+        assert_eq!(bounded_mpmc::<u32>(5).unwrap_err(), RingBufferError::InvalidCapacity(5));
+    }
+
+    #[test]
+    fn test_try_send_full_and_disconnect() {
+        let (sender, receiver) = bounded_spsc::<u32>(2).unwrap();
+        sender.try_send(1).unwrap();
+        assert_eq!(sender.try_send(2), Err(TrySendError::Full(2)));
+        assert_eq!(
+            sender.send_timeout(2, Duration::from_millis(1)),
+            Err(SendTimeoutError::Timeout(2))
+        );
+
+        drop(sender);
+        assert!(receiver.is_disconnected());
+        assert_eq!(receiver.try_recv(), Ok(1));
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::Disconnected));
+        assert_eq!(receiver.recv(), Err(RecvError));
+
+        let (sender, receiver) = bounded_mpsc::<u32>(2).unwrap();
+        drop(receiver);
+        assert!(sender.is_disconnected());
+        assert_eq!(sender.send(7), Err(SendError(7)));
+    }
+
+    #[test]
+    fn test_recv_timeout() {
+        let (sender, receiver) = bounded_mpmc::<u32>(4).unwrap();
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::Empty));
+        assert_eq!(
+            receiver.recv_timeout(Duration::from_millis(1)),
+            Err(RecvTimeoutError::Timeout)
+        );
+
+        sender.send(5).unwrap();
+        assert_eq!(receiver.len(), 1);
+        assert_eq!(receiver.recv_timeout(Duration::from_millis(1)), Ok(5));
+
+        drop(sender);
+        assert_eq!(
+            receiver.recv_timeout(Duration::from_secs(60)),
+            Err(RecvTimeoutError::Disconnected)
+        );
+    }
+
+    #[test]
+    fn test_clones_keep_channel_connected() {
+        let (sender, receiver) = bounded_mpmc::<u32>(8).unwrap();
+        let other_sender = sender.clone();
+        let other_receiver = receiver.clone();
+
+        drop(sender);
+        other_sender.send(1).unwrap();
+        drop(receiver);
+        assert!(!other_sender.is_disconnected());
+        assert_eq!(other_receiver.try_recv(), Ok(1));
+
+        drop(other_sender);
+        assert_eq!(other_receiver.try_iter().count(), 0);
+        assert!(other_receiver.is_disconnected());
+    }
+
+    #[test]
+    fn test_iterators_across_threads() {
+        let (sender, receiver) = bounded_spsc::<u32>(4).unwrap();
+        let handle = std::thread::spawn(move || {
+            for i in 0..100 {
+                sender.send(i).unwrap();
+            }
+        });
+
+        let received: Vec<u32> = receiver.into_iter().collect();
+        handle.join().unwrap();
+        assert_eq!(received, (0..100).collect::<Vec<_>>());
+
+        let (sender, receiver) = bounded_mpmc::<u32>(4).unwrap();
+        let workers: Vec<_> = (0..2)
+            .map(|_| {
+                let receiver = receiver.clone();
+                std::thread::spawn(move || receiver.iter().sum::<u32>())
+            })
+            .collect();
+        drop(receiver);
+
+        for i in 0..100 {
+            sender.send(i).unwrap();
+        }
+        drop(sender);
+
+        let total: u32 = workers.into_iter().map(|worker| worker.join().unwrap()).sum();
+        assert_eq!(total, 4950);
+    }
+}
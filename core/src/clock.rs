@@ -0,0 +1,389 @@
+//! Low-overhead timestamps from the CPU's time-stamp counter
+//!
+//! Reading `Instant::now()` goes through the vDSO and costs around 20ns, too
+//! much to pay per message. [`Clock`] reads the invariant TSC directly with
+//! `rdtsc`/`rdtscp` and converts ticks to nanoseconds using a ratio
+//! calibrated against `CLOCK_MONOTONIC`, so [`Clock::now_nanos`] stays on the
+//! same timeline as the system's monotonic clock.
+//!
+//! Where there is no invariant TSC (non-x86 targets, or CPUs and VMs that do
+//! not advertise one) the clock falls back to reading `CLOCK_MONOTONIC` itself,
+//! and one tick is one nanosecond.
+//!
+//! # Example
+//!
+//! ```
+//! use ferrite_core::clock::Clock;
+//!
+//! let clock = Clock::global();
+//! let start = clock.ticks();
+//! let sum: u64 = (0..1000).sum();
+//! let elapsed = clock.elapsed(start);
+//!
+//! assert_eq!(sum, 499500);
+//! assert!(elapsed.as_secs() < 1);
+//! ```
+//!
+//! Code that should be testable against a controlled timeline takes a
+//! [`TimeSource`] instead, which both [`Clock`] and [`ManualClock`] implement.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::thread;
+use std::time::Duration;
+
+/// Fixed-point shift of [`Clock`]'s nanoseconds-per-tick multiplier
+const SHIFT: u32 = 32;
+
+/// How long [`Clock::global`] calibrates for
+const CALIBRATION_WINDOW: Duration = Duration::from_millis(10);
+
+/// Where a [`Clock`] reads its ticks from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClockSource {
+    /// The invariant time-stamp counter
+    Tsc,
+    /// `CLOCK_MONOTONIC`, in nanoseconds
+    Monotonic,
+}
+
+/// Tick counter calibrated against `CLOCK_MONOTONIC`
+///
+/// Ticks are only meaningful as differences on the same machine; convert them
+/// with [`Clock::ticks_to_nanos`] or read [`Clock::now_nanos`] directly.
+#[derive(Debug, Clone, Copy)]
+pub struct Clock {
+    source: ClockSource,
+    rdtscp: bool,
+    /// Nanoseconds per tick, shifted left by `SHIFT`
+    mult: u64,
+    /// A tick reading and the monotonic time it was taken at
+    base_ticks: u64,
+    base_nanos: u64,
+}
+
+impl Clock {
+    /// Returns the process-wide clock, calibrating it on first use
+    ///
+    /// The first call blocks for about 10ms; call it during startup to keep
+    /// that off the hot path.
+    pub fn global() -> &'static Clock {
+        static GLOBAL: OnceLock<Clock> = OnceLock::new();
+        GLOBAL.get_or_init(|| Clock::calibrate(CALIBRATION_WINDOW))
+    }
+
+    /// Calibrates a new clock by comparing ticks to `CLOCK_MONOTONIC` over `window`
+    ///
+    /// Longer windows give a more accurate tick rate. Blocks for `window`
+    /// unless the TSC is unavailable.
+    pub fn calibrate(window: Duration) -> Self {
+        let mut clock = Clock {
+            source: ClockSource::Monotonic,
+            rdtscp: false,
+            mult: 1 << SHIFT,
+            base_ticks: 0,
+            base_nanos: 0,
+        };
+        if !tsc::is_invariant() {
+            return clock;
+        }
+
+        clock.source = ClockSource::Tsc;
+        clock.rdtscp = tsc::has_rdtscp();
+        let (start_ticks, start_nanos) = clock.sample();
+        thread::sleep(window);
+        let (end_ticks, end_nanos) = clock.sample();
+
+        // A counter that did not advance is no use as a clock
+        if end_ticks <= start_ticks || end_nanos <= start_nanos {
+            clock.source = ClockSource::Monotonic;
+            return clock;
+        }
+
+        let mult = (u128::from(end_nanos - start_nanos) << SHIFT) / u128::from(end_ticks - start_ticks);
+        clock.mult = mult.min(u128::from(u64::MAX)) as u64;
+        clock.base_ticks = end_ticks;
+        clock.base_nanos = end_nanos;
+        clock
+    }
+
+    /// Returns where this clock reads its ticks from
+    pub fn source(&self) -> ClockSource {
+        self.source
+    }
+
+    /// Returns the calibrated tick rate in ticks per second
+    pub fn ticks_per_second(&self) -> u64 {
+        ((1_000_000_000u128 << SHIFT) / u128::from(self.mult.max(1))) as u64
+    }
+
+    /// Reads the tick counter
+    ///
+    /// The read may be reordered with neighbouring instructions; use
+    /// [`Clock::ticks_ordered`] to time a short section precisely.
+    #[inline]
+    pub fn ticks(&self) -> u64 {
+        match self.source {
+            ClockSource::Tsc => tsc::read(),
+            ClockSource::Monotonic => monotonic_nanos(),
+        }
+    }
+
+    /// Reads the tick counter after every earlier instruction has completed
+    ///
+    /// Uses `rdtscp` where the CPU supports it and `lfence; rdtsc` otherwise.
+    #[inline]
+    pub fn ticks_ordered(&self) -> u64 {
+        match self.source {
+            ClockSource::Tsc => tsc::read_ordered(self.rdtscp),
+            ClockSource::Monotonic => monotonic_nanos(),
+        }
+    }
+
+    /// Converts a tick count to nanoseconds
+    #[inline]
+    pub fn ticks_to_nanos(&self, ticks: u64) -> u64 {
+        ((u128::from(ticks) * u128::from(self.mult)) >> SHIFT) as u64
+    }
+
+    /// Returns the current time in nanoseconds on the `CLOCK_MONOTONIC` timeline
+    #[inline]
+    pub fn now_nanos(&self) -> u64 {
+        let ticks = self.ticks();
+        if ticks >= self.base_ticks {
+            self.base_nanos + self.ticks_to_nanos(ticks - self.base_ticks)
+        } else {
+            self.base_nanos.saturating_sub(self.ticks_to_nanos(self.base_ticks - ticks))
+        }
+    }
+
+    /// Returns the nanoseconds since `start`, a value from [`Clock::ticks`]
+    #[inline]
+    pub fn elapsed_nanos(&self, start: u64) -> u64 {
+        self.ticks_to_nanos(self.ticks().saturating_sub(start))
+    }
+
+    /// Returns the time since `start`, a value from [`Clock::ticks`]
+    #[inline]
+    pub fn elapsed(&self, start: u64) -> Duration {
+        Duration::from_nanos(self.elapsed_nanos(start))
+    }
+
+    /// Reads ticks and monotonic time together, keeping the tightest of a few tries
+    fn sample(&self) -> (u64, u64) {
+        let mut best = (0, 0);
+        let mut best_gap = u64::MAX;
+        for _ in 0..5 {
+            let before = monotonic_nanos();
+            let ticks = self.ticks_ordered();
+            let after = monotonic_nanos();
+
+            let gap = after.saturating_sub(before);
+            if gap < best_gap {
+                best_gap = gap;
+                best = (ticks, before + gap / 2);
+            }
+        }
+        best
+    }
+}
+
+/// Returns the current time in nanoseconds on the `CLOCK_MONOTONIC` timeline
+///
+/// Shorthand for [`Clock::global`] followed by [`Clock::now_nanos`].
+#[inline]
+pub fn now_nanos() -> u64 {
+    Clock::global().now_nanos()
+}
+
+/// A source of monotonic timestamps in nanoseconds
+///
+/// Implemented by [`Clock`] for real time and by [`ManualClock`] for tests
+/// that need to control time explicitly.
+pub trait TimeSource {
+    /// Returns the current time in nanoseconds
+    ///
+    /// Only differences between readings are meaningful. Readings must never
+    /// go backwards.
+    fn now_nanos(&self) -> u64;
+}
+
+impl TimeSource for Clock {
+    #[inline]
+    fn now_nanos(&self) -> u64 {
+        Clock::now_nanos(self)
+    }
+}
+
+/// A clock that only moves when told to
+///
+/// Clones share the same time, so a test can keep one handle and advance the
+/// time seen by whatever it passed the other to.
+///
+/// # Example
+///
+/// ```
+/// use ferrite_core::clock::{ManualClock, TimeSource};
+/// use std::time::Duration;
+///
+/// let clock = ManualClock::new(1_000);
+/// let handle = clock.clone();
+///
+/// handle.advance(Duration::from_micros(5));
+/// assert_eq!(clock.now_nanos(), 6_000);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ManualClock {
+    nanos: Arc<AtomicU64>,
+}
+
+impl ManualClock {
+    /// Creates a clock reading `start_nanos`
+    pub fn new(start_nanos: u64) -> Self {
+        ManualClock {
+            nanos: Arc::new(AtomicU64::new(start_nanos)),
+        }
+    }
+
+    /// Moves the clock forward by `by`
+    pub fn advance(&self, by: Duration) {
+        self.nanos.fetch_add(by.as_nanos() as u64, Ordering::SeqCst);
+    }
+
+    /// Sets the clock to `nanos`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `nanos` is earlier than the current reading.
+    pub fn set(&self, nanos: u64) {
+        let previous = self.nanos.fetch_max(nanos, Ordering::SeqCst);
+        assert!(nanos >= previous, "ManualClock cannot go backwards from {} to {}", previous, nanos);
+    }
+}
+
+impl TimeSource for ManualClock {
+    #[inline]
+    fn now_nanos(&self) -> u64 {
+        self.nanos.load(Ordering::SeqCst)
+    }
+}
+
+#[cfg(unix)]
+fn monotonic_nanos() -> u64 {
+    let mut ts = libc::timespec { tv_sec: 0, tv_nsec: 0 };
+    unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut ts) };
+    ts.tv_sec as u64 * 1_000_000_000 + ts.tv_nsec as u64
+}
+
+#[cfg(not(unix))]
+fn monotonic_nanos() -> u64 {
+    static EPOCH: OnceLock<std::time::Instant> = OnceLock::new();
+    EPOCH.get_or_init(std::time::Instant::now).elapsed().as_nanos() as u64
+}
+
+#[cfg(target_arch = "x86_64")]
+mod tsc {
+    use std::arch::x86_64::{__cpuid, __rdtscp, _mm_lfence, _rdtsc};
+
+    /// Checks CPUID for a TSC that ticks at a constant rate in every power state
+    pub(super) fn is_invariant() -> bool {
+        let max_extended = __cpuid(0x8000_0000).eax;
+        max_extended >= 0x8000_0007 && __cpuid(0x8000_0007).edx & (1 << 8) != 0
+    }
+
+    pub(super) fn has_rdtscp() -> bool {
+        let max_extended = __cpuid(0x8000_0000).eax;
+        max_extended >= 0x8000_0001 && __cpuid(0x8000_0001).edx & (1 << 27) != 0
+    }
+
+    #[inline]
+    pub(super) fn read() -> u64 {
+        unsafe { _rdtsc() }
+    }
+
+    #[inline]
+    pub(super) fn read_ordered(rdtscp: bool) -> u64 {
+        if rdtscp {
+            let mut aux = 0;
+            unsafe { __rdtscp(&mut aux) }
+        } else {
+            unsafe {
+                _mm_lfence();
+                _rdtsc()
+            }
+        }
+    }
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+mod tsc {
+    pub(super) fn is_invariant() -> bool {
+        false
+    }
+
+    pub(super) fn has_rdtscp() -> bool {
+        false
+    }
+
+    pub(super) fn read() -> u64 {
+        unreachable!("no TSC on this target")
+    }
+
+    pub(super) fn read_ordered(_rdtscp: bool) -> u64 {
+        unreachable!("no TSC on this target")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tracks_monotonic_clock() {
+        let clock = Clock::global();
+        let before = monotonic_nanos();
+        let now = clock.now_nanos();
+        let after = monotonic_nanos();
+
+        // Calibration error grows with distance from the base reading; allow 1ms
+        assert!(now + 1_000_000 >= before, "{now} ran behind {before}");
+        assert!(now <= after + 1_000_000, "{now} ran ahead of {after}");
+    }
+
+    #[test]
+    fn test_elapsed_matches_sleep() {
+        let clock = Clock::global();
+        let start = clock.ticks_ordered();
+        thread::sleep(Duration::from_millis(20));
+        let elapsed = clock.elapsed(start);
+
+        assert!(elapsed >= Duration::from_millis(19), "{elapsed:?}");
+        assert!(elapsed < Duration::from_secs(1), "{elapsed:?}");
+    }
+
+    #[test]
+    fn test_conversion() {
+        let clock = Clock::global();
+        assert_eq!(clock.ticks_to_nanos(0), 0);
+
+        let per_second = clock.ticks_per_second();
+        let nanos = clock.ticks_to_nanos(per_second);
+        assert!(nanos.abs_diff(1_000_000_000) < 1_000, "{nanos}");
+
+        if clock.source() == ClockSource::Monotonic {
+            assert_eq!(clock.ticks_to_nanos(12345), 12345);
+        }
+    }
+
+    #[test]
+    fn test_manual_clock() {
+        let clock = ManualClock::new(100);
+        let other = clock.clone();
+        other.advance(Duration::from_nanos(50));
+        assert_eq!(clock.now_nanos(), 150);
+
+        clock.set(1_000);
+        assert_eq!(other.now_nanos(), 1_000);
+        assert!(std::panic::catch_unwind(|| clock.set(999)).is_err());
+    }
+}
@@ -0,0 +1,471 @@
+use std::mem::MaybeUninit;
+use std::panic::{RefUnwindSafe, UnwindSafe};
+
+use crate::cache_padded::CachePadded;
+use crate::ring_buffer::RingBufferError;
+use crate::sync::{fence, Arc, AtomicUsize, Ordering};
+
+/// A bounded Chase-Lev work-stealing deque
+///
+/// One [`Worker`] owns the bottom end and pushes and pops there in LIFO
+/// order, which keeps recently spawned work hot in its cache. Any number of
+/// [`Stealer`]s take from the top end in FIFO order. The owner's operations
+/// are wait-free except when racing a stealer for the last item; steals are
+/// lock-free and report [`Steal::Retry`] when they lose a race.
+///
+/// Follows the C11 formulation by Lê, Pop, Cohen and Zappa Nardelli, with
+/// a fixed power-of-two buffer instead of a growable one: a push onto a full
+/// deque hands the value back.
+///
+/// # Example
+///
+/// ```
+/// use ferrite_core::deque::{Deque, Steal};
+///
+/// let (mut worker, stealer) = Deque::new(16).unwrap().split();
+/// worker.push(1).unwrap();
+/// worker.push(2).unwrap();
+/// worker.push(3).unwrap();
+///
+/// let thief = std::thread::spawn(move || stealer.steal());
+///
+/// // The owner pops its newest item, thieves take the oldest
+/// assert_eq!(worker.pop(), Some(3));
+/// assert_eq!(thief.join().unwrap(), Steal::Success(1));
+/// assert_eq!(worker.pop(), Some(2));
+/// assert_eq!(worker.pop(), None);
+/// ```
+pub struct Deque<T> {
+    slots: Box<[Entry<T>]>,
+}
+
+struct Shared<T> {
+    /// Index of the oldest item; advanced by stealers and by the owner taking the last item
+    top: CachePadded<AtomicUsize>,
+    /// Index one past the newest item; only written by the owner
+    bottom: CachePadded<AtomicUsize>,
+    slots: Box<[Entry<T>]>,
+    mask: usize,
+}
+
+/// Outcome of [`Stealer::steal`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Steal<T> {
+    /// The deque was empty
+    Empty,
+    /// An item was stolen
+    Success(T),
+    /// Another thread took the item first; trying again may succeed
+    Retry,
+}
+
+impl<T> Steal<T> {
+    /// Returns the stolen item, if any
+    pub fn success(self) -> Option<T> {
+        match self {
+            Steal::Success(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Checks if the deque was empty
+    pub fn is_empty(&self) -> bool {
+        matches!(self, Steal::Empty)
+    }
+
+    /// Checks if the steal lost a race and may be retried
+    pub fn is_retry(&self) -> bool {
+        matches!(self, Steal::Retry)
+    }
+}
+
+impl<T> Deque<T> {
+    /// Creates a new deque with the specified capacity
+    ///
+    /// # Arguments
+    ///
+    /// * `capacity` - The desired capacity. Must be a power of two and greater than 0.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Deque<T>)` - A new deque
+    /// * `Err(RingBufferError)` - If capacity is invalid
+    pub fn new(capacity: usize) -> Result<Self, RingBufferError> {
+        if capacity == 0 || !capacity.is_power_of_two() {
+            return Err(RingBufferError::InvalidCapacity(capacity));
+        }
+
+        Ok(Deque {
+            slots: (0..capacity).map(|_| Entry::new()).collect(),
+        })
+    }
+
+    /// Returns the capacity of the deque
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Splits the deque into its owning worker and a cloneable stealer
+    pub fn split(self) -> (Worker<T>, Stealer<T>) {
+        let mask = self.slots.len() - 1;
+        let shared = Arc::new(Shared {
+            top: CachePadded { value: AtomicUsize::new(0) },
+            bottom: CachePadded { value: AtomicUsize::new(0) },
+            slots: self.slots,
+            mask,
+        });
+
+        let worker = Worker {
+            shared: shared.clone(),
+        };
+        let stealer = Stealer { shared };
+
+        (worker, stealer)
+    }
+}
+
+/// Owning end of a deque; pushes and pops the newest items
+pub struct Worker<T> {
+    shared: Arc<Shared<T>>,
+}
+
+/// Stealing end of a deque; clone it to add thieves
+pub struct Stealer<T> {
+    shared: Arc<Shared<T>>,
+}
+
+unsafe impl<T: Send> Send for Worker<T> {}
+unsafe impl<T: Send> Send for Stealer<T> {}
+unsafe impl<T: Send> Sync for Stealer<T> {}
+
+// Items are moved in and out whole by bitwise copies, which cannot panic, so
+// a panic never leaves an item half-published or half-taken.
+impl<T: UnwindSafe> UnwindSafe for Worker<T> {}
+impl<T: RefUnwindSafe> RefUnwindSafe for Worker<T> {}
+impl<T: UnwindSafe> UnwindSafe for Stealer<T> {}
+impl<T: RefUnwindSafe> RefUnwindSafe for Stealer<T> {}
+
+impl<T> Clone for Stealer<T> {
+    fn clone(&self) -> Self {
+        Stealer {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<T> Worker<T> {
+    /// Pushes an item onto the bottom of the deque
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - The item was pushed
+    /// * `Err(value)` - The deque is full; the item is handed back
+    pub fn push(&mut self, value: T) -> Result<(), T> {
+        let shared = &*self.shared;
+        let bottom = shared.bottom.value.load(Ordering::Relaxed);
+        let top = shared.top.value.load(Ordering::Acquire);
+
+        if bottom.wrapping_sub(top) as isize >= shared.slots.len() as isize {
+            return Err(value);
+        }
+
+        unsafe { shared.slots[bottom & shared.mask].write(value) };
+        // Publishes the item to stealers that acquire `bottom`
+        shared.bottom.value.store(bottom.wrapping_add(1), Ordering::Release);
+        Ok(())
+    }
+
+    /// Pops the newest item from the bottom of the deque
+    ///
+    /// # Returns
+    ///
+    /// * `Some(T)` - The newest item
+    /// * `None` - If the deque is empty, or a stealer took the last item first
+    pub fn pop(&mut self) -> Option<T> {
+        let shared = &*self.shared;
+        let bottom = shared.bottom.value.load(Ordering::Relaxed).wrapping_sub(1);
+        shared.bottom.value.store(bottom, Ordering::Relaxed);
+
+        // Orders the claim on `bottom` before reading `top`; pairs with the fence in `steal`
+        fence(Ordering::SeqCst);
+        let top = shared.top.value.load(Ordering::Relaxed);
+
+        let len = bottom.wrapping_sub(top) as isize;
+        if len < 0 {
+            shared.bottom.value.store(bottom.wrapping_add(1), Ordering::Relaxed);
+            return None;
+        }
+
+        let value = unsafe { shared.slots[bottom & shared.mask].read() };
+        if len > 0 {
+            return Some(unsafe { value.assume_init() });
+        }
+
+        // Last item: race the stealers for it through `top`
+        let won = shared
+            .top
+            .value
+            .compare_exchange(top, top.wrapping_add(1), Ordering::SeqCst, Ordering::Relaxed)
+            .is_ok();
+        shared.bottom.value.store(bottom.wrapping_add(1), Ordering::Relaxed);
+
+        // The copy is only ours if we won; otherwise a stealer owns the item
+        won.then(|| unsafe { value.assume_init() })
+    }
+
+    /// Returns a new stealer for this deque
+    pub fn stealer(&self) -> Stealer<T> {
+        Stealer {
+            shared: self.shared.clone(),
+        }
+    }
+
+    /// Returns the number of items in the deque
+    pub fn len(&self) -> usize {
+        self.shared.len()
+    }
+
+    /// Checks if the deque is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the capacity of the deque
+    pub fn capacity(&self) -> usize {
+        self.shared.slots.len()
+    }
+}
+
+impl<T> Stealer<T> {
+    /// Attempts to steal the oldest item from the top of the deque
+    ///
+    /// # Returns
+    ///
+    /// * `Steal::Success(T)` - The oldest item
+    /// * `Steal::Empty` - The deque is empty
+    /// * `Steal::Retry` - The owner or another stealer took the item first
+    pub fn steal(&self) -> Steal<T> {
+        let shared = &*self.shared;
+        let top = shared.top.value.load(Ordering::Acquire);
+
+        // Orders reading `top` before `bottom`; pairs with the fence in `pop`
+        fence(Ordering::SeqCst);
+        let bottom = shared.bottom.value.load(Ordering::Acquire);
+
+        if bottom.wrapping_sub(top) as isize <= 0 {
+            return Steal::Empty;
+        }
+
+        // May race with the owner refilling this slot after others advanced
+        // `top`; the copy is discarded unless the exchange below succeeds
+        let value = unsafe { shared.slots[top & shared.mask].read() };
+        match shared
+            .top
+            .value
+            .compare_exchange(top, top.wrapping_add(1), Ordering::SeqCst, Ordering::Relaxed)
+        {
+            Ok(_) => Steal::Success(unsafe { value.assume_init() }),
+            Err(_) => Steal::Retry,
+        }
+    }
+
+    /// Returns the number of items in the deque
+    ///
+    /// The result may be stale by the time it is used.
+    pub fn len(&self) -> usize {
+        self.shared.len()
+    }
+
+    /// Checks if the deque is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<T> Shared<T> {
+    fn len(&self) -> usize {
+        let top = self.top.value.load(Ordering::Acquire);
+        let bottom = self.bottom.value.load(Ordering::Acquire);
+        (bottom.wrapping_sub(top) as isize).max(0) as usize
+    }
+}
+
+impl<T> Drop for Shared<T> {
+    fn drop(&mut self) {
+        let top = self.top.value.load(Ordering::Relaxed);
+        let bottom = self.bottom.value.load(Ordering::Relaxed);
+
+        let mut index = top;
+        while index != bottom {
+            unsafe { drop(self.slots[index & self.mask].read().assume_init()) };
+            index = index.wrapping_add(1);
+        }
+    }
+}
+
+/// One item's storage, copied with volatile accesses that may race with the owner
+#[cfg(not(loom))]
+struct Entry<T>(std::cell::UnsafeCell<MaybeUninit<T>>);
+
+#[cfg(not(loom))]
+impl<T> Entry<T> {
+    fn new() -> Self {
+        Entry(std::cell::UnsafeCell::new(MaybeUninit::uninit()))
+    }
+
+    /// Copies the item out without taking ownership of it
+    ///
+    /// # Safety
+    ///
+    /// The caller may only `assume_init` the copy once it owns the item.
+    unsafe fn read(&self) -> MaybeUninit<T> {
+        std::ptr::read_volatile(self.0.get())
+    }
+
+    /// # Safety
+    ///
+    /// The caller must be the owner, writing a slot no stealer can claim.
+    unsafe fn write(&self, value: T) {
+        std::ptr::write_volatile(self.0.get(), MaybeUninit::new(value))
+    }
+}
+
+/// One item's storage, split into `Relaxed` atomic words so loom can run the racy reads
+///
+/// Copies `T` byte for byte through `usize` words, so loom tests must use
+/// types without padding.
+#[cfg(loom)]
+struct Entry<T> {
+    words: Box<[AtomicUsize]>,
+    _marker: std::marker::PhantomData<T>,
+}
+
+#[cfg(loom)]
+impl<T> Entry<T> {
+    const WORDS: usize = std::mem::size_of::<T>().div_ceil(std::mem::size_of::<usize>());
+
+    fn new() -> Self {
+        Entry {
+            words: (0..Self::WORDS).map(|_| AtomicUsize::new(0)).collect(),
+            _marker: std::marker::PhantomData,
+        }
+    }
+
+    unsafe fn read(&self) -> MaybeUninit<T> {
+        let words: Vec<usize> = self.words.iter().map(|word| word.load(Ordering::Relaxed)).collect();
+        let mut value = MaybeUninit::<T>::uninit();
+        std::ptr::copy_nonoverlapping(
+            words.as_ptr() as *const u8,
+            value.as_mut_ptr() as *mut u8,
+            std::mem::size_of::<T>(),
+        );
+        value
+    }
+
+    unsafe fn write(&self, value: T) {
+        let mut words = vec![0usize; Self::WORDS];
+        std::ptr::copy_nonoverlapping(
+            &value as *const T as *const u8,
+            words.as_mut_ptr() as *mut u8,
+            std::mem::size_of::<T>(),
+        );
+        std::mem::forget(value);
+        for (word, bits) in self.words.iter().zip(words) {
+            word.store(bits, Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invalid_capacity() {
+        assert!(matches!(Deque::<u32>::new(0), Err(RingBufferError::InvalidCapacity(0))));
+        assert!(matches!(Deque::<u32>::new(6), Err(RingBufferError::InvalidCapacity(6))));
+    }
+
+    #[test]
+    fn test_lifo_owner_fifo_stealer() {
+        let (mut worker, stealer) = Deque::new(4).unwrap().split();
+        for i in 0..4 {
+            worker.push(i).unwrap();
+        }
+        assert_eq!(worker.push(4), Err(4));
+        assert_eq!(worker.len(), 4);
+
+        assert_eq!(stealer.steal(), Steal::Success(0));
+        assert_eq!(worker.pop(), Some(3));
+        assert_eq!(stealer.steal(), Steal::Success(1));
+        assert_eq!(worker.pop(), Some(2));
+        assert_eq!(worker.pop(), None);
+        assert!(stealer.steal().is_empty());
+
+        // Indices keep advancing past the capacity
+        worker.push(5).unwrap();
+        assert_eq!(stealer.len(), 1);
+        assert_eq!(worker.pop(), Some(5));
+    }
+
+    #[test]
+    fn test_drops_remaining_items() {
+        let token = std::sync::Arc::new(());
+        let (mut worker, stealer) = Deque::new(8).unwrap().split();
+        for _ in 0..5 {
+            worker.push(token.clone()).unwrap();
+        }
+        drop(stealer.steal());
+        drop(worker.pop());
+
+        drop(worker);
+        drop(stealer);
+        assert_eq!(std::sync::Arc::strong_count(&token), 1);
+    }
+
+    #[test]
+    fn test_concurrent_steals_take_each_item_once() {
+        let (mut worker, stealer) = Deque::new(64).unwrap().split();
+        let thieves: Vec<_> = (0..2)
+            .map(|_| {
+                let stealer = stealer.clone();
+                std::thread::spawn(move || {
+                    let mut taken = Vec::new();
+                    let mut empty = 0;
+                    while empty < 1000 {
+                        match stealer.steal() {
+                            Steal::Success(value) => taken.push(value),
+                            Steal::Empty => {
+                                empty += 1;
+                                std::thread::yield_now();
+                            }
+                            Steal::Retry => {}
+                        }
+                    }
+                    taken
+                })
+            })
+            .collect();
+
+        let mut taken = Vec::new();
+        for i in 0..10_000u32 {
+            let mut value = i;
+            while let Err(rejected) = worker.push(value) {
+                value = rejected;
+                taken.extend(worker.pop());
+            }
+            if i % 3 == 0 {
+                taken.extend(worker.pop());
+            }
+        }
+        while let Some(value) = worker.pop() {
+            taken.push(value);
+        }
+
+        for thief in thieves {
+            taken.extend(thief.join().unwrap());
+        }
+        taken.sort_unstable();
+        assert_eq!(taken, (0..10_000).collect::<Vec<_>>());
+    }
+}
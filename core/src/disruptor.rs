@@ -0,0 +1,544 @@
+use std::panic::{RefUnwindSafe, UnwindSafe};
+use std::time::Duration;
+
+use crate::cache_padded::CachePadded;
+use crate::ring_buffer::RingBufferError;
+use crate::sync::{Arc, AtomicBool, AtomicUsize, Ordering, UnsafeCell};
+
+/// A disruptor-style ring of pre-allocated events shared by a pipeline of handlers
+///
+/// Events are created once by a factory and then reused: the [`Publisher`]
+/// claims the next slot and fills it in place, and every registered handler
+/// sees every event through a shared reference. Each handler tracks its own
+/// sequence and may depend on other handlers, so one buffer carries a whole
+/// processing graph instead of one ring per stage.
+///
+/// This implementation provides:
+/// - A single publisher sequence and one cache-padded sequence per handler
+/// - Dependency barriers: a handler only sees an event once every handler it
+///   depends on has finished with it
+/// - Batching: handlers drain every available event in one pass and are told
+///   which event ends the batch
+/// - A selectable [`WaitStrategy`] for both the publisher and the handlers
+///
+/// The publisher only reuses a slot once every handler has moved past it.
+///
+/// # Example
+///
+/// A diamond: `journal` and `replicate` run in parallel, `apply` runs after both.
+///
+/// ```
+/// use std::sync::atomic::{AtomicU64, Ordering};
+/// use std::sync::Arc;
+/// use ferrite_core::disruptor::{Disruptor, WaitStrategy};
+///
+/// let applied = Arc::new(AtomicU64::new(0));
+/// let mut disruptor = Disruptor::new(64, || 0u64)
+///     .unwrap()
+///     .with_wait_strategy(WaitStrategy::Yielding);
+///
+/// let journal = disruptor.add_handler(&[], |_: &u64, _: u64, _: bool| {});
+/// let replicate = disruptor.add_handler(&[], |_: &u64, _: u64, _: bool| {});
+/// let total = applied.clone();
+/// disruptor.add_handler(&[journal, replicate], move |event: &u64, _: u64, _: bool| {
+///     total.fetch_add(*event, Ordering::Relaxed);
+/// });
+///
+/// let (mut publisher, processors) = disruptor.split();
+/// let threads: Vec<_> = processors
+///     .into_iter()
+///     .map(|mut processor| std::thread::spawn(move || processor.run()))
+///     .collect();
+///
+/// for value in 1..=100 {
+///     publisher.publish_with(|event, _sequence| *event = value);
+/// }
+/// drop(publisher);
+///
+/// threads.into_iter().for_each(|thread| thread.join().unwrap());
+/// assert_eq!(applied.load(Ordering::Relaxed), 5050);
+/// ```
+pub struct Disruptor<T> {
+    events: Box<[UnsafeCell<T>]>,
+    handlers: Vec<HandlerSpec<T>>,
+    wait_strategy: WaitStrategy,
+}
+
+struct HandlerSpec<T> {
+    depends_on: Box<[usize]>,
+    handler: Box<dyn EventHandler<T> + Send>,
+}
+
+struct Shared<T> {
+    /// Number of events published so far
+    cursor: CachePadded<AtomicUsize>,
+    /// Number of events each handler has finished with
+    sequences: Box<[CachePadded<AtomicUsize>]>,
+    /// Set once the publisher is dropped
+    closed: AtomicBool,
+    events: Box<[UnsafeCell<T>]>,
+    mask: usize,
+    wait_strategy: WaitStrategy,
+}
+
+/// Identifies a handler registered with [`Disruptor::add_handler`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HandlerId(usize);
+
+/// Callback invoked for every event that reaches a handler
+///
+/// Implemented for any `FnMut(&T, u64, bool)` closure.
+pub trait EventHandler<T> {
+    /// Handles one event
+    ///
+    /// # Arguments
+    ///
+    /// * `event` - The event, shared with every other handler
+    /// * `sequence` - The event's position in the publish order, starting at 0
+    /// * `end_of_batch` - Whether this is the last event currently available,
+    ///   e.g. the point to flush buffered output
+    fn on_event(&mut self, event: &T, sequence: u64, end_of_batch: bool);
+}
+
+impl<T, F: FnMut(&T, u64, bool)> EventHandler<T> for F {
+    fn on_event(&mut self, event: &T, sequence: u64, end_of_batch: bool) {
+        self(event, sequence, end_of_batch)
+    }
+}
+
+/// How publishers and handlers wait for a sequence to advance
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WaitStrategy {
+    /// Spin on the sequence; lowest latency, but keeps a core busy
+    BusySpin,
+    /// Spin briefly, then yield the thread between checks
+    #[default]
+    Yielding,
+    /// Spin and yield briefly, then sleep for the given duration between checks
+    Sleeping(Duration),
+}
+
+impl WaitStrategy {
+    /// Checks that spin before `Yielding` and `Sleeping` back off
+    const SPIN_TRIES: u32 = 100;
+
+    /// Checks that yield before `Sleeping` starts to sleep
+    const YIELD_TRIES: u32 = 100;
+
+    /// Waits once; `attempt` counts consecutive unsuccessful checks
+    fn wait(&self, attempt: &mut u32) {
+        *attempt = attempt.saturating_add(1);
+
+        #[cfg(loom)]
+        loom::thread::yield_now();
+
+        #[cfg(not(loom))]
+        match *self {
+            WaitStrategy::BusySpin => std::hint::spin_loop(),
+            _ if *attempt <= Self::SPIN_TRIES => std::hint::spin_loop(),
+            WaitStrategy::Sleeping(duration) if *attempt > Self::SPIN_TRIES + Self::YIELD_TRIES => {
+                std::thread::sleep(duration)
+            }
+            _ => std::thread::yield_now(),
+        }
+    }
+}
+
+impl<T> Disruptor<T> {
+    /// Creates a new disruptor, filling every slot from `factory`
+    ///
+    /// # Arguments
+    ///
+    /// * `capacity` - The number of events. Must be a power of two and greater than 0.
+    /// * `factory` - Builds the initial value of each event
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Disruptor<T>)` - A new disruptor with no handlers
+    /// * `Err(RingBufferError)` - If capacity is invalid
+    pub fn new(capacity: usize, mut factory: impl FnMut() -> T) -> Result<Self, RingBufferError> {
+        if capacity == 0 || !capacity.is_power_of_two() {
+            return Err(RingBufferError::InvalidCapacity(capacity));
+        }
+
+        Ok(Disruptor {
+            events: (0..capacity).map(|_| UnsafeCell::new(factory())).collect(),
+            handlers: Vec::new(),
+            wait_strategy: WaitStrategy::default(),
+        })
+    }
+
+    /// Sets how the publisher and handlers wait; defaults to [`WaitStrategy::Yielding`]
+    pub fn with_wait_strategy(mut self, wait_strategy: WaitStrategy) -> Self {
+        self.wait_strategy = wait_strategy;
+        self
+    }
+
+    /// Returns the number of events in the ring
+    pub fn capacity(&self) -> usize {
+        self.events.len()
+    }
+
+    /// Registers a handler that runs after every handler in `depends_on`
+    ///
+    /// # Arguments
+    ///
+    /// * `depends_on` - Handlers that must finish with an event before this one sees it;
+    ///   empty to follow the publisher directly
+    /// * `handler` - Called for every event, in sequence order
+    ///
+    /// # Returns
+    ///
+    /// The id to pass as a dependency of later handlers
+    ///
+    /// # Panics
+    ///
+    /// Panics if an id in `depends_on` was not returned by this disruptor.
+    pub fn add_handler<H>(&mut self, depends_on: &[HandlerId], handler: H) -> HandlerId
+    where
+        H: EventHandler<T> + Send + 'static,
+    {
+        let id = self.handlers.len();
+        for dependency in depends_on {
+            assert!(dependency.0 < id, "unknown handler dependency {:?}", dependency);
+        }
+
+        self.handlers.push(HandlerSpec {
+            depends_on: depends_on.iter().map(|dependency| dependency.0).collect(),
+            handler: Box::new(handler),
+        });
+        HandlerId(id)
+    }
+
+    /// Splits the disruptor into its publisher and one processor per handler
+    ///
+    /// Processors are returned in registration order. Every processor must be
+    /// driven, by [`EventProcessor::run`] on its own thread or by calling
+    /// [`EventProcessor::poll`], or the publisher eventually stalls.
+    pub fn split(self) -> (Publisher<T>, Vec<EventProcessor<T>>) {
+        let mask = self.events.len() - 1;
+        let shared = Arc::new(Shared {
+            cursor: CachePadded { value: AtomicUsize::new(0) },
+            sequences: (0..self.handlers.len())
+                .map(|_| CachePadded { value: AtomicUsize::new(0) })
+                .collect(),
+            closed: AtomicBool::new(false),
+            events: self.events,
+            mask,
+            wait_strategy: self.wait_strategy,
+        });
+
+        let processors = self
+            .handlers
+            .into_iter()
+            .enumerate()
+            .map(|(index, spec)| EventProcessor {
+                shared: shared.clone(),
+                index,
+                depends_on: spec.depends_on,
+                handler: spec.handler,
+                next: 0,
+            })
+            .collect();
+
+        let publisher = Publisher {
+            shared,
+            next: 0,
+            cached_gate: 0,
+        };
+
+        (publisher, processors)
+    }
+}
+
+/// The single publisher of a disruptor
+///
+/// Dropping the publisher lets every [`EventProcessor::run`] return once it
+/// has handled the remaining events.
+pub struct Publisher<T> {
+    shared: Arc<Shared<T>>,
+    /// Local copy of the cursor
+    next: usize,
+    /// Cached lowest handler sequence
+    cached_gate: usize,
+}
+
+/// Drives one handler over the events it is allowed to see
+pub struct EventProcessor<T> {
+    shared: Arc<Shared<T>>,
+    index: usize,
+    depends_on: Box<[usize]>,
+    handler: Box<dyn EventHandler<T> + Send>,
+    /// Local copy of this handler's sequence
+    next: usize,
+}
+
+// Events are written by the publisher and read by several handler threads at once
+unsafe impl<T: Send + Sync> Send for Publisher<T> {}
+unsafe impl<T: Send + Sync> Send for EventProcessor<T> {}
+
+// An event is only published once the publisher's closure returns, and a
+// handler's sequence only advances past events it has fully handled.
+impl<T: UnwindSafe> UnwindSafe for Publisher<T> {}
+impl<T: RefUnwindSafe> RefUnwindSafe for Publisher<T> {}
+impl<T: UnwindSafe> UnwindSafe for EventProcessor<T> {}
+impl<T: RefUnwindSafe> RefUnwindSafe for EventProcessor<T> {}
+
+impl<T> Publisher<T> {
+    /// Attempts to claim the next event, fill it in place and publish it
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - Called with the event and its sequence; the event still holds
+    ///   whatever the previous lap left in it
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - The event was published
+    /// * `Err(RingBufferError::BufferFull)` - The slowest handler is a full ring behind
+    pub fn try_publish_with<F: FnOnce(&mut T, u64)>(&mut self, f: F) -> Result<(), RingBufferError> {
+        if !self.has_room() {
+            return Err(RingBufferError::BufferFull);
+        }
+        self.write(f);
+        Ok(())
+    }
+
+    /// Claims the next event, waiting for the slowest handler if necessary,
+    /// then fills it in place and publishes it
+    pub fn publish_with<F: FnOnce(&mut T, u64)>(&mut self, f: F) {
+        let mut attempt = 0;
+        while !self.has_room() {
+            self.shared.wait_strategy.wait(&mut attempt);
+        }
+        self.write(f);
+    }
+
+    /// Returns the number of events that can be published without waiting
+    pub fn remaining_capacity(&mut self) -> usize {
+        self.cached_gate = self.shared.gate(self.next);
+        self.capacity() - self.next.wrapping_sub(self.cached_gate)
+    }
+
+    /// Returns the number of events in the ring
+    pub fn capacity(&self) -> usize {
+        self.shared.mask + 1
+    }
+
+    fn has_room(&mut self) -> bool {
+        if self.next.wrapping_sub(self.cached_gate) < self.capacity() {
+            return true;
+        }
+        self.cached_gate = self.shared.gate(self.next);
+        self.next.wrapping_sub(self.cached_gate) < self.capacity()
+    }
+
+    fn write<F: FnOnce(&mut T, u64)>(&mut self, f: F) {
+        let sequence = self.next;
+        let event = &self.shared.events[sequence & self.shared.mask];
+
+        // Every handler has moved past the previous lap of this slot and no
+        // handler can reach it until the cursor store below.
+        event.with_mut(|ptr| f(unsafe { &mut *ptr }, sequence as u64));
+
+        self.next = sequence.wrapping_add(1);
+        self.shared.cursor.value.store(self.next, Ordering::Release);
+    }
+}
+
+impl<T> Drop for Publisher<T> {
+    fn drop(&mut self) {
+        self.shared.closed.store(true, Ordering::Release);
+    }
+}
+
+impl<T> EventProcessor<T> {
+    /// Returns the id this processor's handler was registered under
+    pub fn id(&self) -> HandlerId {
+        HandlerId(self.index)
+    }
+
+    /// Returns the number of events this handler has finished with
+    pub fn sequence(&self) -> u64 {
+        self.next as u64
+    }
+
+    /// Hands every currently available event to the handler as one batch
+    ///
+    /// # Returns
+    ///
+    /// The number of events handled, 0 if none were available
+    pub fn poll(&mut self) -> usize {
+        let shared = &*self.shared;
+        let available = self.barrier();
+        let count = available.wrapping_sub(self.next);
+
+        while self.next != available {
+            let sequence = self.next;
+            // Published by the cursor's release store; the publisher will not
+            // touch it again until this handler's sequence moves past it.
+            shared.events[sequence & shared.mask].with(|ptr| {
+                let event = unsafe { &*ptr };
+                self.handler.on_event(event, sequence as u64, sequence.wrapping_add(1) == available);
+            });
+            self.next = sequence.wrapping_add(1);
+        }
+
+        if count > 0 {
+            shared.sequences[self.index].value.store(self.next, Ordering::Release);
+        }
+        count
+    }
+
+    /// Handles events until the publisher is dropped and every published event is handled
+    ///
+    /// Waits with the disruptor's [`WaitStrategy`] whenever nothing is available.
+    /// If a handler this one depends on is never driven, this never returns.
+    pub fn run(&mut self) {
+        let mut attempt = 0;
+        loop {
+            let closed = self.shared.closed.load(Ordering::Acquire);
+            if self.poll() > 0 {
+                attempt = 0;
+                continue;
+            }
+            if closed && self.next == self.shared.cursor.value.load(Ordering::Acquire) {
+                return;
+            }
+            self.shared.wait_strategy.wait(&mut attempt);
+        }
+    }
+
+    /// Returns the highest sequence this handler may handle up to
+    fn barrier(&self) -> usize {
+        let shared = &*self.shared;
+        let cursor = shared.cursor.value.load(Ordering::Acquire);
+
+        self.depends_on.iter().fold(cursor, |available, &dependency| {
+            let sequence = shared.sequences[dependency].value.load(Ordering::Acquire);
+            // Sequences wrap, so compare by distance from this handler
+            if sequence.wrapping_sub(self.next) < available.wrapping_sub(self.next) {
+                sequence
+            } else {
+                available
+            }
+        })
+    }
+}
+
+impl<T> Shared<T> {
+    /// Returns the lowest handler sequence, or `cursor` if there are no handlers
+    fn gate(&self, cursor: usize) -> usize {
+        self.sequences.iter().fold(cursor, |lowest, sequence| {
+            let sequence = sequence.value.load(Ordering::Acquire);
+            if cursor.wrapping_sub(sequence) > cursor.wrapping_sub(lowest) {
+                sequence
+            } else {
+                lowest
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    fn recorder(log: &std::sync::Arc<Mutex<Vec<(u64, bool)>>>) -> impl EventHandler<u64> + Send {
+        let log = log.clone();
+        move |event: &u64, _sequence: u64, end_of_batch: bool| {
+            log.lock().unwrap().push((*event, end_of_batch));
+        }
+    }
+
+    #[test]
+    fn test_new_invalid_capacity() {
+        assert!(matches!(
+            Disruptor::new(0, || 0u64),
+            Err(RingBufferError::InvalidCapacity(0))
+        ));
+        assert!(matches!(
+            Disruptor::new(12, || 0u64),
+            Err(RingBufferError::InvalidCapacity(12))
+        ));
+    }
+
+    #[test]
+    fn test_batches_and_dependency_barrier() {
+        let first_log = std::sync::Arc::new(Mutex::new(Vec::new()));
+        let second_log = std::sync::Arc::new(Mutex::new(Vec::new()));
+
+        let mut disruptor = Disruptor::new(4, || 0u64).unwrap();
+        let first = disruptor.add_handler(&[], recorder(&first_log));
+        disruptor.add_handler(&[first], recorder(&second_log));
+        let (mut publisher, mut processors) = disruptor.split();
+
+        for value in 1..=3 {
+            publisher.try_publish_with(|event, sequence| *event = value + sequence * 10).unwrap();
+        }
+
+        // The dependent handler cannot see anything until the first one has run
+        assert_eq!(processors[1].poll(), 0);
+        assert_eq!(processors[0].poll(), 3);
+        assert_eq!(*first_log.lock().unwrap(), vec![(1, false), (12, false), (23, true)]);
+
+        assert_eq!(processors[1].poll(), 3);
+        assert_eq!(processors[1].sequence(), 3);
+        assert_eq!(*second_log.lock().unwrap(), *first_log.lock().unwrap());
+    }
+
+    #[test]
+    fn test_publisher_gated_by_slowest_handler() {
+        let mut disruptor = Disruptor::new(2, || 0u64).unwrap();
+        let fast = disruptor.add_handler(&[], |_: &u64, _: u64, _: bool| {});
+        disruptor.add_handler(&[], |_: &u64, _: u64, _: bool| {});
+        let (mut publisher, mut processors) = disruptor.split();
+        assert_eq!(processors[0].id(), fast);
+
+        publisher.try_publish_with(|event, _| *event = 1).unwrap();
+        publisher.try_publish_with(|event, _| *event = 2).unwrap();
+        assert_eq!(publisher.try_publish_with(|event, _| *event = 3), Err(RingBufferError::BufferFull));
+
+        processors[0].poll();
+        assert_eq!(publisher.remaining_capacity(), 0);
+
+        processors[1].poll();
+        assert_eq!(publisher.remaining_capacity(), 2);
+
+        // The slot still holds the previous lap's event until it is overwritten
+        publisher.try_publish_with(|event, _| assert_eq!(*event, 1)).unwrap();
+    }
+
+    #[test]
+    fn test_diamond_pipeline_threads() {
+        const EVENTS: u64 = 5_000;
+        let total = std::sync::Arc::new(std::sync::atomic::AtomicU64::new(0));
+
+        let mut disruptor = Disruptor::new(16, || 0u64).unwrap();
+        let mut journal_next = 0;
+        let journal = disruptor.add_handler(&[], move |_: &u64, sequence: u64, _: bool| {
+            assert_eq!(sequence, journal_next);
+            journal_next += 1;
+        });
+        let replicate = disruptor.add_handler(&[], |_: &u64, _: u64, _: bool| {});
+        let apply_total = total.clone();
+        disruptor.add_handler(&[journal, replicate], move |event: &u64, _: u64, _: bool| {
+            apply_total.fetch_add(*event, std::sync::atomic::Ordering::Relaxed);
+        });
+
+        let (mut publisher, processors) = disruptor.split();
+        let threads: Vec<_> = processors
+            .into_iter()
+            .map(|mut processor| std::thread::spawn(move || processor.run()))
+            .collect();
+
+        for value in 0..EVENTS {
+            publisher.publish_with(|event, _| *event = value);
+        }
+        drop(publisher);
+
+        threads.into_iter().for_each(|thread| thread.join().unwrap());
+        assert_eq!(total.load(std::sync::atomic::Ordering::Relaxed), EVENTS * (EVENTS - 1) / 2);
+    }
+}
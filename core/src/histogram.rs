@@ -0,0 +1,248 @@
+/// Number of bits of precision kept below the leading bit of a value
+const SUB_BUCKET_BITS: u32 = 4;
+
+/// Sub-buckets per power of two
+const SUB_BUCKETS: usize = 1 << SUB_BUCKET_BITS;
+
+/// Total bucket count covering the full `u64` range
+const BUCKETS: usize = (64 - SUB_BUCKET_BITS as usize + 1) * SUB_BUCKETS;
+
+/// A fixed-bucket log-linear histogram for latency measurements
+///
+/// Values below 16 are counted exactly. Larger values fall into one of 16
+/// linear sub-buckets per power of two, so every reported percentile is within
+/// about 6% of the true value. The bucket array is allocated once in `new`;
+/// recording never allocates.
+///
+/// Units are up to the caller; the ring buffer records nanoseconds.
+///
+/// # Example
+///
+/// ```
+/// use ferrite_core::histogram::Histogram;
+///
+/// let mut histogram = Histogram::new();
+/// for value in 1..=100 {
+///     histogram.record(value);
+/// }
+/// assert_eq!(histogram.count(), 100);
+/// assert!(histogram.percentile(50.0) >= 50);
+/// ```
+#[derive(Clone)]
+pub struct Histogram {
+    buckets: Box<[u64]>,
+    count: u64,
+    sum: u128,
+    min: u64,
+    max: u64,
+}
+
+impl Histogram {
+    /// Creates an empty histogram
+    pub fn new() -> Self {
+        Histogram {
+            buckets: vec![0; BUCKETS].into_boxed_slice(),
+            count: 0,
+            sum: 0,
+            min: u64::MAX,
+            max: 0,
+        }
+    }
+
+    /// Records a single value
+    #[inline]
+    pub fn record(&mut self, value: u64) {
+        self.record_n(value, 1);
+    }
+
+    /// Records `count` occurrences of the same value
+    pub fn record_n(&mut self, value: u64, count: u64) {
+        if count == 0 {
+            return;
+        }
+        self.buckets[bucket_index(value)] += count;
+        self.count += count;
+        self.sum += value as u128 * count as u128;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+    }
+
+    /// Returns the number of recorded values
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Returns the smallest recorded value, or 0 if empty
+    pub fn min(&self) -> u64 {
+        if self.count == 0 {
+            0
+        } else {
+            self.min
+        }
+    }
+
+    /// Returns the largest recorded value, or 0 if empty
+    pub fn max(&self) -> u64 {
+        self.max
+    }
+
+    /// Returns the arithmetic mean of the recorded values, or 0 if empty
+    pub fn mean(&self) -> f64 {
+        if self.count == 0 {
+            0.0
+        } else {
+            self.sum as f64 / self.count as f64
+        }
+    }
+
+    /// Returns the value at the given percentile
+    ///
+    /// The result is the upper bound of the bucket containing the percentile,
+    /// clamped to the recorded minimum and maximum.
+    ///
+    /// # Arguments
+    ///
+    /// * `percentile` - A percentile between 0 and 100, e.g. `99.9`
+    pub fn percentile(&self, percentile: f64) -> u64 {
+        if self.count == 0 {
+            return 0;
+        }
+
+        let rank = ((percentile.clamp(0.0, 100.0) / 100.0) * self.count as f64).ceil() as u64;
+        let rank = rank.max(1);
+
+        let mut seen = 0;
+        for (index, &bucket) in self.buckets.iter().enumerate() {
+            seen += bucket;
+            if seen >= rank {
+                return bucket_upper_bound(index).clamp(self.min, self.max);
+            }
+        }
+        self.max
+    }
+
+    /// Adds every value recorded in `other` to this histogram
+    pub fn merge(&mut self, other: &Histogram) {
+        for (mine, theirs) in self.buckets.iter_mut().zip(other.buckets.iter()) {
+            *mine += theirs;
+        }
+        self.count += other.count;
+        self.sum += other.sum;
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+    }
+
+    /// Clears all recorded values
+    pub fn reset(&mut self) {
+        self.buckets.iter_mut().for_each(|bucket| *bucket = 0);
+        self.count = 0;
+        self.sum = 0;
+        self.min = u64::MAX;
+        self.max = 0;
+    }
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Histogram::new()
+    }
+}
+
+impl std::fmt::Debug for Histogram {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Histogram")
+            .field("count", &self.count)
+            .field("min", &self.min())
+            .field("p50", &self.percentile(50.0))
+            .field("p99", &self.percentile(99.0))
+            .field("max", &self.max)
+            .finish()
+    }
+}
+
+/// Maps a value to its bucket
+#[inline]
+pub(crate) fn bucket_index(value: u64) -> usize {
+    if value < SUB_BUCKETS as u64 {
+        return value as usize;
+    }
+    let exponent = 63 - value.leading_zeros();
+    let shift = exponent - SUB_BUCKET_BITS;
+    let sub_bucket = (value >> shift) as usize & (SUB_BUCKETS - 1);
+    (shift as usize + 1) * SUB_BUCKETS + sub_bucket
+}
+
+/// Returns the largest value that maps to the given bucket
+pub(crate) fn bucket_upper_bound(index: usize) -> u64 {
+    if index < SUB_BUCKETS {
+        return index as u64;
+    }
+    let shift = (index / SUB_BUCKETS - 1) as u32;
+    let sub_bucket = (index % SUB_BUCKETS) as u64;
+    let lower = (SUB_BUCKETS as u64 + sub_bucket) << shift;
+    lower + ((1u64 << shift) - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bucket_boundaries() {
+        for value in 0..16 {
+            assert_eq!(bucket_index(value), value as usize);
+        }
+        assert_eq!(bucket_index(16), 16);
+        assert_eq!(bucket_index(31), 31);
+        assert_eq!(bucket_index(32), 32);
+        assert_eq!(bucket_index(33), 32);
+        assert_eq!(bucket_index(u64::MAX), BUCKETS - 1);
+        assert_eq!(bucket_upper_bound(BUCKETS - 1), u64::MAX);
+
+        for value in [17u64, 100, 1_000, 123_456, 1 << 40, u64::MAX / 3] {
+            let upper = bucket_upper_bound(bucket_index(value));
+            assert!(upper >= value);
+            assert!((upper - value) as f64 <= value as f64 / 16.0);
+        }
+    }
+
+    #[test]
+    fn test_percentiles() {
+        let mut histogram = Histogram::new();
+        assert_eq!(histogram.percentile(99.0), 0);
+
+        for value in 1..=1000 {
+            histogram.record(value);
+        }
+
+        assert_eq!(histogram.count(), 1000);
+        assert_eq!(histogram.min(), 1);
+        assert_eq!(histogram.max(), 1000);
+        assert_eq!(histogram.mean(), 500.5);
+        assert_eq!(histogram.percentile(0.0), 1);
+        assert_eq!(histogram.percentile(100.0), 1000);
+
+        let p50 = histogram.percentile(50.0);
+        assert!((500..=532).contains(&p50), "p50 = {}", p50);
+        let p99 = histogram.percentile(99.0);
+        assert!((990..=1000).contains(&p99), "p99 = {}", p99);
+    }
+
+    #[test]
+    fn test_merge_and_reset() {
+        let mut a = Histogram::new();
+        let mut b = Histogram::new();
+        a.record(10);
+        b.record_n(1_000, 3);
+
+        a.merge(&b);
+        assert_eq!(a.count(), 4);
+        assert_eq!(a.min(), 10);
+        assert_eq!(a.max(), 1_000);
+
+        a.reset();
+        assert_eq!(a.count(), 0);
+        assert_eq!(a.min(), 0);
+        assert_eq!(a.max(), 0);
+    }
+}
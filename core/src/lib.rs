@@ -1 +1,25 @@
-pub mod ring_buffer;
\ No newline at end of file
+pub mod ring_buffer;
+pub mod broadcast;
+pub mod channel;
+pub mod clock;
+pub mod deque;
+pub mod disruptor;
+pub mod histogram;
+pub mod logger;
+pub mod metrics;
+pub mod mpmc;
+pub mod mpsc;
+pub mod pool;
+pub mod rate_limit;
+pub mod runtime;
+pub mod seqlock;
+pub mod thread_pool;
+pub mod timer_wheel;
+pub mod triple_buffer;
+pub mod unbounded;
+#[cfg(target_os = "linux")]
+pub mod notify;
+
+mod cache_padded;
+mod slot;
+mod sync;
@@ -0,0 +1,1005 @@
+//! Asynchronous logger for latency-sensitive threads
+//!
+//! Logging a line never formats or writes on the calling thread. Each thread
+//! gets its own SPSC [`RingBuffer`] the first time it logs, and every log
+//! statement pushes one fixed-size binary record into it:
+//!
+//! - A pointer to the statement's static [`Callsite`], which identifies the
+//!   format string, level and source location
+//! - A timestamp from the TSC [`Clock`]
+//! - The arguments, encoded raw into an inline buffer of `ARG_BYTES` bytes
+//!
+//! A background thread drains every ring, orders each batch by timestamp,
+//! formats the records and writes them to stderr, a file or any writer.
+//!
+//! # Formatting
+//!
+//! Each `{}` in the format string takes the next argument, `{{` and `}}` print
+//! literal braces. Format specs inside the braces are ignored, since arguments
+//! are formatted with `Display` on the background thread. Arguments are
+//! limited to types that implement [`LogArg`]: integers, floats, `bool`,
+//! `char` and strings. Strings are copied and truncated to fit the record;
+//! a record whose arguments do not all fit ends with `[truncated]`.
+//!
+//! # Drop policy
+//!
+//! A thread whose ring is full follows the [`DropPolicy`] given at install:
+//! [`DropPolicy::Drop`] discards the record at once, [`DropPolicy::Wait`]
+//! retries for at most the given duration and then discards it. Logging
+//! therefore never blocks for longer than the policy allows. The background
+//! thread counts discarded records per thread and reports them with a
+//! `WARN` line of its own.
+//!
+//! # Example
+//!
+//! ```
+//! use ferrite_core::logger::{Level, Logger};
+//! use ferrite_core::log_info;
+//!
+//! let guard = Logger::new().with_level(Level::Info).install().unwrap();
+//! log_info!("order {} filled at {}", 42u64, 101.25);
+//!
+//! // Dropping the guard flushes every record logged so far
+//! drop(guard);
+//! ```
+//!
+//! With the `log` feature enabled, [`install_log_facade`] routes records
+//! from the `log` crate into the same rings.
+
+use std::cell::RefCell;
+use std::fmt::{self, Write as _};
+use std::fs::OpenOptions;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicU8, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::clock::Clock;
+use crate::ring_buffer::{Consumer, Producer, RingBuffer};
+
+/// Bytes of encoded arguments a record can hold
+pub const ARG_BYTES: usize = 110;
+
+/// Records the background thread takes from one ring per pass
+const BATCH: usize = 256;
+
+/// How long the background thread sleeps when every ring is empty
+const IDLE_SLEEP: Duration = Duration::from_millis(1);
+
+const TAG_I64: u8 = 0;
+const TAG_U64: u8 = 1;
+const TAG_F64: u8 = 2;
+const TAG_BOOL: u8 = 3;
+const TAG_CHAR: u8 = 4;
+const TAG_STR: u8 = 5;
+const TAG_STR_TRUNCATED: u8 = 6;
+
+/// Severity of a log record, most severe first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(u8)]
+pub enum Level {
+    Error = 1,
+    Warn = 2,
+    Info = 3,
+    Debug = 4,
+    Trace = 5,
+}
+
+impl Level {
+    fn as_str(self) -> &'static str {
+        match self {
+            Level::Error => "ERROR",
+            Level::Warn => "WARN",
+            Level::Info => "INFO",
+            Level::Debug => "DEBUG",
+            Level::Trace => "TRACE",
+        }
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.as_str())
+    }
+}
+
+/// Most verbose level currently logged; 0 while no logger is installed
+static MAX_LEVEL: AtomicU8 = AtomicU8::new(0);
+
+/// Checks if records at `level` are currently logged
+#[inline]
+pub fn enabled(level: Level) -> bool {
+    level as u8 <= MAX_LEVEL.load(Ordering::Relaxed)
+}
+
+/// What a thread does when its ring is full
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DropPolicy {
+    /// Discard the record immediately
+    #[default]
+    Drop,
+    /// Retry for at most the given duration, then discard the record
+    Wait(Duration),
+}
+
+/// Static metadata of one log statement; its address is the record's format ID
+#[derive(Debug)]
+pub struct Callsite {
+    level: Level,
+    target: &'static str,
+    format: &'static str,
+    file: &'static str,
+    line: u32,
+}
+
+impl Callsite {
+    /// Creates the metadata for a log statement; used by the logging macros
+    pub const fn new(level: Level, target: &'static str, format: &'static str, file: &'static str, line: u32) -> Self {
+        Callsite {
+            level,
+            target,
+            format,
+            file,
+            line,
+        }
+    }
+
+    /// Returns the statement's level
+    pub fn level(&self) -> Level {
+        self.level
+    }
+
+    /// Returns the module path the statement was written in
+    pub fn target(&self) -> &'static str {
+        self.target
+    }
+
+    /// Returns the statement's format string
+    pub fn format(&self) -> &'static str {
+        self.format
+    }
+
+    /// Returns the source file and line of the statement
+    pub fn location(&self) -> (&'static str, u32) {
+        (self.file, self.line)
+    }
+}
+
+/// A value that can be encoded into a log record
+pub trait LogArg {
+    /// Appends the raw value to the record
+    fn encode(&self, record: &mut Record);
+}
+
+macro_rules! impl_log_arg {
+    ($tag:expr, $as:ty, $($t:ty),*) => {
+        $(
+            impl LogArg for $t {
+                #[inline]
+                fn encode(&self, record: &mut Record) {
+                    record.put($tag, &(*self as $as).to_le_bytes());
+                }
+            }
+        )*
+    };
+}
+
+impl_log_arg!(TAG_I64, i64, i8, i16, i32, i64, isize);
+impl_log_arg!(TAG_U64, u64, u8, u16, u32, u64, usize);
+impl_log_arg!(TAG_F64, f64, f32, f64);
+
+impl LogArg for bool {
+    #[inline]
+    fn encode(&self, record: &mut Record) {
+        record.put(TAG_BOOL, &[*self as u8]);
+    }
+}
+
+impl LogArg for char {
+    #[inline]
+    fn encode(&self, record: &mut Record) {
+        record.put(TAG_CHAR, &u32::from(*self).to_le_bytes());
+    }
+}
+
+impl LogArg for str {
+    #[inline]
+    fn encode(&self, record: &mut Record) {
+        record.put_str(self);
+    }
+}
+
+impl LogArg for String {
+    #[inline]
+    fn encode(&self, record: &mut Record) {
+        record.put_str(self);
+    }
+}
+
+impl<T: LogArg + ?Sized> LogArg for &T {
+    #[inline]
+    fn encode(&self, record: &mut Record) {
+        (**self).encode(record);
+    }
+}
+
+/// One log statement in binary form, as it travels through a thread's ring
+pub struct Record {
+    callsite: &'static Callsite,
+    /// Nanoseconds on the `CLOCK_MONOTONIC` timeline
+    timestamp: u64,
+    len: u8,
+    truncated: bool,
+    args: [u8; ARG_BYTES],
+}
+
+impl Record {
+    /// Starts a record for `callsite`, timestamped now; used by the logging macros
+    #[inline]
+    pub fn new(callsite: &'static Callsite) -> Self {
+        Record {
+            callsite,
+            timestamp: Clock::global().now_nanos(),
+            len: 0,
+            truncated: false,
+            args: [0; ARG_BYTES],
+        }
+    }
+
+    /// Appends an argument
+    #[inline]
+    pub fn arg<A: LogArg + ?Sized>(&mut self, arg: &A) -> &mut Self {
+        arg.encode(self);
+        self
+    }
+
+    /// Pushes the record into the calling thread's ring, following the drop policy
+    #[inline]
+    pub fn submit(self) {
+        submit(self);
+    }
+
+    fn put(&mut self, tag: u8, bytes: &[u8]) {
+        let start = self.len as usize;
+        if self.truncated || start + 1 + bytes.len() > ARG_BYTES {
+            self.truncated = true;
+            return;
+        }
+        self.args[start] = tag;
+        self.args[start + 1..start + 1 + bytes.len()].copy_from_slice(bytes);
+        self.len = (start + 1 + bytes.len()) as u8;
+    }
+
+    fn put_str(&mut self, value: &str) {
+        let start = self.len as usize;
+        // Tag and length byte, then as much of the string as fits
+        if self.truncated || start + 2 > ARG_BYTES {
+            self.truncated = true;
+            return;
+        }
+
+        let room = (ARG_BYTES - start - 2).min(u8::MAX as usize);
+        let mut end = value.len().min(room);
+        while !value.is_char_boundary(end) {
+            end -= 1;
+        }
+
+        let tag = if end < value.len() { TAG_STR_TRUNCATED } else { TAG_STR };
+        self.args[start] = tag;
+        self.args[start + 1] = end as u8;
+        self.args[start + 2..start + 2 + end].copy_from_slice(&value.as_bytes()[..end]);
+        self.len = (start + 2 + end) as u8;
+    }
+
+    /// Writes the format string with every placeholder replaced by the next argument
+    fn format_message(&self, out: &mut String) {
+        let mut args = Args {
+            bytes: &self.args[..self.len as usize],
+        };
+        let mut chars = self.callsite.format.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    out.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    out.push('}');
+                }
+                '{' => {
+                    // Skip any format spec up to the closing brace
+                    for c in chars.by_ref() {
+                        if c == '}' {
+                            break;
+                        }
+                    }
+                    if !args.write_next(out) {
+                        out.push_str("{}");
+                    }
+                }
+                c => out.push(c),
+            }
+        }
+
+        if self.truncated {
+            out.push_str(" [truncated]");
+        }
+    }
+}
+
+/// Decoder over a record's argument bytes
+struct Args<'a> {
+    bytes: &'a [u8],
+}
+
+impl Args<'_> {
+    /// Writes the next argument, or returns `false` if there is none
+    fn write_next(&mut self, out: &mut String) -> bool {
+        let Some((&tag, rest)) = self.bytes.split_first() else {
+            return false;
+        };
+
+        let word = |rest: &[u8]| u64::from_le_bytes(rest[..8].try_into().unwrap());
+        let _ = match tag {
+            TAG_I64 => {
+                self.bytes = &rest[8..];
+                write!(out, "{}", word(rest) as i64)
+            }
+            TAG_U64 => {
+                self.bytes = &rest[8..];
+                write!(out, "{}", word(rest))
+            }
+            TAG_F64 => {
+                self.bytes = &rest[8..];
+                write!(out, "{}", f64::from_bits(word(rest)))
+            }
+            TAG_BOOL => {
+                self.bytes = &rest[1..];
+                write!(out, "{}", rest[0] != 0)
+            }
+            TAG_CHAR => {
+                let code = u32::from_le_bytes(rest[..4].try_into().unwrap());
+                self.bytes = &rest[4..];
+                write!(out, "{}", char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER))
+            }
+            _ => {
+                let len = rest[0] as usize;
+                let value = std::str::from_utf8(&rest[1..1 + len]).unwrap_or("");
+                self.bytes = &rest[1 + len..];
+                out.push_str(value);
+                if tag == TAG_STR_TRUNCATED {
+                    out.push_str("...");
+                }
+                Ok(())
+            }
+        };
+        true
+    }
+}
+
+/// Logs a record at the given level
+///
+/// ```
+/// use ferrite_core::logger::Level;
+///
+/// ferrite_core::log_at!(Level::Warn, "queue {} is {}% full", "orders", 90u8);
+/// ```
+#[macro_export]
+macro_rules! log_at {
+    ($level:expr, $format:literal $(, $arg:expr)* $(,)?) => {{
+        static CALLSITE: $crate::logger::Callsite =
+            $crate::logger::Callsite::new($level, module_path!(), $format, file!(), line!());
+        if $crate::logger::enabled($level) {
+            #[allow(unused_mut)]
+            let mut record = $crate::logger::Record::new(&CALLSITE);
+            $( record.arg(&$arg); )*
+            record.submit();
+        }
+    }};
+}
+
+/// Logs a record at [`Level::Error`](crate::logger::Level::Error)
+#[macro_export]
+macro_rules! log_error {
+    ($($args:tt)+) => { $crate::log_at!($crate::logger::Level::Error, $($args)+) };
+}
+
+/// Logs a record at [`Level::Warn`](crate::logger::Level::Warn)
+#[macro_export]
+macro_rules! log_warn {
+    ($($args:tt)+) => { $crate::log_at!($crate::logger::Level::Warn, $($args)+) };
+}
+
+/// Logs a record at [`Level::Info`](crate::logger::Level::Info)
+#[macro_export]
+macro_rules! log_info {
+    ($($args:tt)+) => { $crate::log_at!($crate::logger::Level::Info, $($args)+) };
+}
+
+/// Logs a record at [`Level::Debug`](crate::logger::Level::Debug)
+#[macro_export]
+macro_rules! log_debug {
+    ($($args:tt)+) => { $crate::log_at!($crate::logger::Level::Debug, $($args)+) };
+}
+
+/// Logs a record at [`Level::Trace`](crate::logger::Level::Trace)
+#[macro_export]
+macro_rules! log_trace {
+    ($($args:tt)+) => { $crate::log_at!($crate::logger::Level::Trace, $($args)+) };
+}
+
+/// Configures and installs the process-wide logger
+pub struct Logger {
+    level: Level,
+    ring_capacity: usize,
+    drop_policy: DropPolicy,
+    writer: Box<dyn Write + Send>,
+}
+
+impl Logger {
+    /// Creates a logger writing [`Level::Info`] and above to stderr
+    ///
+    /// Each thread gets a ring of capacity 1024, holding 1023 records, and full
+    /// rings drop records at once.
+    pub fn new() -> Self {
+        Logger {
+            level: Level::Info,
+            ring_capacity: 1024,
+            drop_policy: DropPolicy::Drop,
+            writer: Box::new(io::stderr()),
+        }
+    }
+
+    /// Sets the most verbose level that is logged
+    pub fn with_level(mut self, level: Level) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// Sets the capacity of each thread's ring; must be a power of two of at
+    /// least 2, since the ring holds one record fewer
+    pub fn with_ring_capacity(mut self, capacity: usize) -> Self {
+        self.ring_capacity = capacity;
+        self
+    }
+
+    /// Sets what a thread does when its ring is full
+    pub fn with_drop_policy(mut self, drop_policy: DropPolicy) -> Self {
+        self.drop_policy = drop_policy;
+        self
+    }
+
+    /// Writes formatted lines to `writer` instead of stderr
+    pub fn with_writer(mut self, writer: impl Write + Send + 'static) -> Self {
+        self.writer = Box::new(writer);
+        self
+    }
+
+    /// Appends formatted lines to the file at `path`, creating it if needed
+    pub fn with_file(self, path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(self.with_writer(file))
+    }
+
+    /// Starts the background thread and makes this the process-wide logger
+    ///
+    /// Also calibrates the shared [`Clock`] if it has not been already.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(LoggerGuard)` - Records are logged until the guard is dropped
+    /// * `Err(io::Error)` - If a logger is already installed, the ring capacity
+    ///   is invalid, or the thread could not be spawned
+    pub fn install(self) -> io::Result<LoggerGuard> {
+        // A ring of capacity 1 holds no records, so every log call would be dropped
+        if self.ring_capacity < 2 || !self.ring_capacity.is_power_of_two() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "ring capacity must be a power of two of at least 2",
+            ));
+        }
+
+        let mut current = lock(&CURRENT);
+        if current.is_some() {
+            return Err(io::Error::new(io::ErrorKind::AlreadyExists, "a logger is already installed"));
+        }
+
+        let clock = Clock::global();
+        let wall_nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_nanos() as u64);
+        let shared = Arc::new(Shared {
+            ring_capacity: self.ring_capacity,
+            drop_policy: self.drop_policy,
+            pending: Mutex::new(Vec::new()),
+            stopping: AtomicBool::new(false),
+        });
+
+        let writer = Writer {
+            out: BufWriter::new(self.writer),
+            wall_offset: wall_nanos as i128 - clock.now_nanos() as i128,
+            line: String::new(),
+        };
+        let background = shared.clone();
+        let handle = thread::Builder::new()
+            .name("ferrite-logger".to_string())
+            .spawn(move || run_background(&background, writer))?;
+
+        *current = Some(shared.clone());
+        MAX_LEVEL.store(self.level as u8, Ordering::Relaxed);
+
+        Ok(LoggerGuard {
+            shared,
+            handle: Some(handle),
+        })
+    }
+}
+
+impl Default for Logger {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Keeps the logger installed; dropping it flushes every record and stops the background thread
+///
+/// Records logged after the guard is dropped are discarded until another
+/// logger is installed.
+pub struct LoggerGuard {
+    shared: Arc<Shared>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl fmt::Debug for LoggerGuard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LoggerGuard").finish_non_exhaustive()
+    }
+}
+
+impl Drop for LoggerGuard {
+    fn drop(&mut self) {
+        MAX_LEVEL.store(0, Ordering::Relaxed);
+        *lock(&CURRENT) = None;
+
+        self.shared.stopping.store(true, Ordering::Release);
+        if let Some(handle) = self.handle.take() {
+            handle.thread().unpark();
+            let _ = handle.join();
+        }
+    }
+}
+
+/// The installed logger, read only when a thread registers its ring
+static CURRENT: Mutex<Option<Arc<Shared>>> = Mutex::new(None);
+
+struct Shared {
+    ring_capacity: usize,
+    drop_policy: DropPolicy,
+    /// Rings registered since the background thread last looked
+    pending: Mutex<Vec<ThreadRing>>,
+    stopping: AtomicBool,
+}
+
+/// Background thread's end of one thread's ring
+struct ThreadRing {
+    consumer: Consumer<Record>,
+    dropped: Arc<AtomicU64>,
+    reported: u64,
+    exited: Arc<AtomicBool>,
+    thread: String,
+}
+
+/// Logging thread's end of its ring
+struct ThreadLog {
+    producer: Producer<Record>,
+    dropped: Arc<AtomicU64>,
+    exited: Arc<AtomicBool>,
+    shared: Arc<Shared>,
+}
+
+impl ThreadLog {
+    /// Creates a ring for the calling thread and hands its consumer to the installed logger
+    fn register() -> Option<ThreadLog> {
+        let shared = lock(&CURRENT).clone()?;
+        let (producer, consumer) = RingBuffer::new(shared.ring_capacity).ok()?.split();
+        let dropped = Arc::new(AtomicU64::new(0));
+        let exited = Arc::new(AtomicBool::new(false));
+
+        lock(&shared.pending).push(ThreadRing {
+            consumer,
+            dropped: dropped.clone(),
+            reported: 0,
+            exited: exited.clone(),
+            thread: thread::current().name().unwrap_or("unnamed").to_string(),
+        });
+
+        Some(ThreadLog {
+            producer,
+            dropped,
+            exited,
+            shared,
+        })
+    }
+
+    fn push(&mut self, record: Record) {
+        if !push_with_policy(&mut self.producer, record, self.shared.drop_policy) {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+impl Drop for ThreadLog {
+    fn drop(&mut self) {
+        self.exited.store(true, Ordering::Release);
+    }
+}
+
+thread_local! {
+    static THREAD_LOG: RefCell<Option<ThreadLog>> = const { RefCell::new(None) };
+}
+
+fn submit(record: Record) {
+    // Fails only while the thread is being torn down; the record is discarded
+    let _ = THREAD_LOG.try_with(|slot| {
+        let Ok(mut slot) = slot.try_borrow_mut() else {
+            return;
+        };
+        let stale = slot.as_ref().is_none_or(|log| log.shared.stopping.load(Ordering::Relaxed));
+        if stale {
+            *slot = ThreadLog::register();
+        }
+        if let Some(log) = slot.as_mut() {
+            log.push(record);
+        }
+    });
+}
+
+/// Pushes `record`, retrying while the policy allows
+///
+/// # Returns
+///
+/// * `true` - The record is in the ring
+/// * `false` - The ring stayed full and the record was discarded
+fn push_with_policy(producer: &mut Producer<Record>, record: Record, drop_policy: DropPolicy) -> bool {
+    if !producer.is_full() {
+        let _ = producer.push(record);
+        return true;
+    }
+
+    let DropPolicy::Wait(max_wait) = drop_policy else {
+        return false;
+    };
+
+    let clock = Clock::global();
+    let start = clock.ticks();
+    let max_wait = max_wait.as_nanos() as u64;
+    while producer.is_full() {
+        if clock.elapsed_nanos(start) >= max_wait {
+            return false;
+        }
+        std::hint::spin_loop();
+    }
+    let _ = producer.push(record);
+    true
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Formats records into lines and writes them out
+struct Writer {
+    out: BufWriter<Box<dyn Write + Send>>,
+    /// Wall-clock nanoseconds minus monotonic nanoseconds at install
+    wall_offset: i128,
+    line: String,
+}
+
+impl Writer {
+    fn write_record(&mut self, record: &Record) {
+        let callsite = record.callsite;
+        self.line.clear();
+        self.write_prefix(record.timestamp, callsite.level, callsite.target);
+        record.format_message(&mut self.line);
+        self.line.push('\n');
+        let _ = self.out.write_all(self.line.as_bytes());
+    }
+
+    fn write_dropped(&mut self, ring: &ThreadRing, dropped: u64) {
+        self.line.clear();
+        self.write_prefix(Clock::global().now_nanos(), Level::Warn, module_path!());
+        let _ = writeln!(
+            self.line,
+            "dropped {} records from thread {} with a full ring",
+            dropped, ring.thread
+        );
+        let _ = self.out.write_all(self.line.as_bytes());
+    }
+
+    fn write_prefix(&mut self, timestamp: u64, level: Level, target: &str) {
+        let wall = (timestamp as i128 + self.wall_offset).max(0) as u64;
+        write_utc(&mut self.line, wall);
+        let _ = write!(self.line, " {:<5} ", level);
+        if !target.is_empty() {
+            self.line.push_str(target);
+            self.line.push_str(": ");
+        }
+    }
+}
+
+/// Writes nanoseconds since the Unix epoch as an RFC 3339 UTC timestamp
+fn write_utc(out: &mut String, nanos: u64) {
+    let secs = nanos / 1_000_000_000;
+    let days = (secs / 86_400) as i64;
+    let of_day = secs % 86_400;
+
+    // Civil date from days since 1970-01-01
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    let _ = write!(
+        out,
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:09}Z",
+        year,
+        month,
+        day,
+        of_day / 3600,
+        of_day / 60 % 60,
+        of_day % 60,
+        nanos % 1_000_000_000
+    );
+}
+
+fn run_background(shared: &Shared, mut writer: Writer) {
+    let mut rings: Vec<ThreadRing> = Vec::new();
+    let mut batch: Vec<Record> = Vec::with_capacity(BATCH);
+
+    loop {
+        // Read before draining, so a final pass after stopping sees every record
+        let stopping = shared.stopping.load(Ordering::Acquire);
+        rings.append(&mut lock(&shared.pending));
+
+        let mut written = 0;
+        for ring in &mut rings {
+            ring.consumer.pop_batch(BATCH, |record| batch.push(record));
+
+            let dropped = ring.dropped.load(Ordering::Relaxed);
+            if dropped > ring.reported {
+                writer.write_dropped(ring, dropped - ring.reported);
+                ring.reported = dropped;
+            }
+        }
+
+        // Interleave threads in time order within each pass
+        batch.sort_by_key(|record| record.timestamp);
+        for record in batch.drain(..) {
+            writer.write_record(&record);
+            written += 1;
+        }
+
+        rings.retain(|ring| !ring.exited.load(Ordering::Acquire) || !ring.consumer.is_empty());
+
+        if written == 0 {
+            let _ = writer.out.flush();
+            if stopping {
+                break;
+            }
+            thread::park_timeout(IDLE_SLEEP);
+        }
+    }
+}
+
+/// Routes records from the `log` crate into the installed logger
+///
+/// Messages from `log` are formatted on the calling thread, since the crate
+/// hands over `fmt::Arguments`, and are truncated to fit a record like any
+/// other string argument.
+///
+/// # Returns
+///
+/// * `Ok(())` - The facade is the `log` crate's logger
+/// * `Err(SetLoggerError)` - If another `log` logger was already set
+#[cfg(feature = "log")]
+pub fn install_log_facade() -> Result<(), log::SetLoggerError> {
+    log::set_logger(&LogFacade)?;
+    log::set_max_level(log::LevelFilter::Trace);
+    Ok(())
+}
+
+#[cfg(feature = "log")]
+struct LogFacade;
+
+/// Callsites for `log` records, indexed by level; the target travels as the first argument
+#[cfg(feature = "log")]
+static LOG_CALLSITES: [Callsite; 5] = [
+    Callsite::new(Level::Error, "", "{}: {}", "", 0),
+    Callsite::new(Level::Warn, "", "{}: {}", "", 0),
+    Callsite::new(Level::Info, "", "{}: {}", "", 0),
+    Callsite::new(Level::Debug, "", "{}: {}", "", 0),
+    Callsite::new(Level::Trace, "", "{}: {}", "", 0),
+];
+
+#[cfg(feature = "log")]
+impl LogFacade {
+    fn level(level: log::Level) -> Level {
+        match level {
+            log::Level::Error => Level::Error,
+            log::Level::Warn => Level::Warn,
+            log::Level::Info => Level::Info,
+            log::Level::Debug => Level::Debug,
+            log::Level::Trace => Level::Trace,
+        }
+    }
+}
+
+#[cfg(feature = "log")]
+impl log::Log for LogFacade {
+    fn enabled(&self, metadata: &log::Metadata<'_>) -> bool {
+        enabled(Self::level(metadata.level()))
+    }
+
+    fn log(&self, record: &log::Record<'_>) {
+        let level = Self::level(record.level());
+        if !enabled(level) {
+            return;
+        }
+
+        let mut entry = Record::new(&LOG_CALLSITES[level as usize - 1]);
+        entry.arg(record.target());
+        match record.args().as_str() {
+            Some(message) => entry.arg(message),
+            None => entry.arg(&record.args().to_string()),
+        };
+        entry.submit();
+    }
+
+    fn flush(&self) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static CALLSITE: Callsite = Callsite::new(Level::Info, "test", "a={} b={:?} {{c}} d={} e={}", "", 0);
+
+    fn format(record: &Record) -> String {
+        let mut out = String::new();
+        record.format_message(&mut out);
+        out
+    }
+
+    #[derive(Clone, Default)]
+    struct Capture(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Capture {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_format_placeholders_and_truncation() {
+        let mut record = Record::new(&CALLSITE);
+        record.arg(&-5i32).arg("héllo").arg(&'x').arg(&2.5f64);
+        assert_eq!(format(&record), "a=-5 b=héllo {c} d=x e=2.5");
+
+        // Missing arguments leave the placeholder in place
+        let mut record = Record::new(&CALLSITE);
+        record.arg(&true);
+        assert_eq!(format(&record), "a=true b={} {c} d={} e={}");
+
+        let long = "y".repeat(200);
+        let mut record = Record::new(&CALLSITE);
+        record.arg(&1u8).arg(&long).arg(&2u8);
+        let formatted = format(&record);
+        assert!(formatted.starts_with("a=1 b=yyy"));
+        assert!(formatted.ends_with("... {c} d={} e={} [truncated]"));
+    }
+
+    #[test]
+    fn test_utc_timestamps() {
+        let mut out = String::new();
+        write_utc(&mut out, 0);
+        assert_eq!(out, "1970-01-01T00:00:00.000000000Z");
+
+        out.clear();
+        write_utc(&mut out, 1_709_210_096_123_456_789);
+        assert_eq!(out, "2024-02-29T12:34:56.123456789Z");
+    }
+
+    #[test]
+    fn test_drop_policy_is_bounded() {
+        static FULL: Callsite = Callsite::new(Level::Info, "", "", "", 0);
+        let (mut producer, _consumer) = RingBuffer::new(2).unwrap().split();
+        assert!(push_with_policy(&mut producer, Record::new(&FULL), DropPolicy::Drop));
+        assert!(!push_with_policy(&mut producer, Record::new(&FULL), DropPolicy::Drop));
+
+        let clock = Clock::global();
+        let start = clock.ticks();
+        let wait = DropPolicy::Wait(Duration::from_millis(5));
+        assert!(!push_with_policy(&mut producer, Record::new(&FULL), wait));
+        let waited = clock.elapsed(start);
+        assert!(waited >= Duration::from_millis(5) && waited < Duration::from_secs(1), "{waited:?}");
+    }
+
+    #[test]
+    fn test_install_rejects_ring_without_room() {
+        for capacity in [0, 1, 3] {
+            let err = Logger::new().with_ring_capacity(capacity).install().err().unwrap();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        }
+    }
+
+    #[test]
+    fn test_install_logs_from_threads_in_order() {
+        let output = Capture::default();
+        let guard = Logger::new()
+            .with_level(Level::Debug)
+            .with_writer(output.clone())
+            .install()
+            .unwrap();
+        assert_eq!(Logger::new().install().unwrap_err().kind(), io::ErrorKind::AlreadyExists);
+
+        let threads: Vec<_> = (0..2u64)
+            .map(|id| {
+                thread::Builder::new()
+                    .name(format!("worker-{}", id))
+                    .spawn(move || {
+                        for i in 0..50u64 {
+                            crate::log_debug!("worker {} message {}", id, i);
+                        }
+                    })
+                    .unwrap()
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        crate::log_trace!("filtered out");
+        crate::log_error!("last {}", "line");
+
+        #[cfg(feature = "log")]
+        {
+            install_log_facade().unwrap();
+            log::info!(target: "external", "from the {} crate", "log");
+        }
+        drop(guard);
+
+        let text = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        for id in 0..2 {
+            let messages: Vec<&str> = lines
+                .iter()
+                .filter(|line| line.contains(&format!("worker {} message", id)))
+                .copied()
+                .collect();
+            assert_eq!(messages.len(), 50);
+            assert!(messages[49].ends_with(&format!("worker {} message 49", id)));
+            assert!(messages[0].contains(" DEBUG ferrite_core::logger::tests: "));
+        }
+        assert!(!text.contains("filtered out"));
+        assert!(text.contains(" ERROR ferrite_core::logger::tests: last line"));
+        #[cfg(feature = "log")]
+        assert!(text.contains(" INFO  external: from the log crate"));
+
+        // Nothing is logged once the guard is gone
+        crate::log_error!("after drop");
+        assert!(!enabled(Level::Error));
+    }
+}
@@ -0,0 +1,642 @@
+//! Application metrics that hot threads can update without contention
+//!
+//! - [`Counter`] spreads its value over cache-padded shards, one per thread
+//!   slot, so concurrent increments never touch the same cache line
+//! - [`Gauge`] holds a single signed value that is set or adjusted
+//! - [`BucketHistogram`] counts values into fixed, caller-chosen buckets,
+//!   sharded the same way as counters
+//!
+//! Every metric is a cheap handle that can be cloned into the threads that
+//! update it. A [`Registry`] names metrics for export, and a [`Collector`]
+//! snapshots the registry on a background thread at a fixed interval.
+//!
+//! # Example
+//!
+//! ```
+//! use ferrite_core::metrics::{MetricValue, Registry};
+//!
+//! let registry = Registry::new();
+//! let messages = registry.counter("messages_handled").unwrap();
+//! let sizes = registry.histogram("message_bytes", &[64, 512, 4096]).unwrap();
+//!
+//! for size in [40, 100, 3000, 9000] {
+//!     messages.inc();
+//!     sizes.record(size);
+//! }
+//!
+//! let snapshot = registry.snapshot();
+//! assert_eq!(snapshot[1].name, "messages_handled");
+//! assert_eq!(snapshot[1].value, MetricValue::Counter(4));
+//! ```
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::cache_padded::CachePadded;
+
+/// Upper bound on shards per metric, however many CPUs there are
+const MAX_SHARDS: usize = 64;
+
+/// Counters per cache line in a histogram shard
+const CELLS_PER_LINE: usize = 8;
+
+/// Error type for metric registration
+#[derive(Debug, Clone, PartialEq)]
+pub enum MetricsError {
+    /// The name is already registered as a different kind of metric
+    KindMismatch(String),
+    /// The name is already registered as a histogram with different bounds
+    BoundsMismatch(String),
+    /// Histogram bounds must be non-empty and strictly increasing
+    InvalidBounds,
+}
+
+impl fmt::Display for MetricsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MetricsError::KindMismatch(name) => {
+                write!(f, "Metric {} is registered as a different kind", name)
+            }
+            MetricsError::BoundsMismatch(name) => {
+                write!(f, "Histogram {} is registered with different bounds", name)
+            }
+            MetricsError::InvalidBounds => {
+                write!(f, "Histogram bounds must be non-empty and strictly increasing")
+            }
+        }
+    }
+}
+
+impl Error for MetricsError {}
+
+/// Returns the number of shards per metric, the CPU count rounded up to a power of two
+fn shard_count() -> usize {
+    static SHARDS: OnceLock<usize> = OnceLock::new();
+    *SHARDS.get_or_init(|| {
+        let cpus = thread::available_parallelism().map_or(1, |n| n.get());
+        cpus.next_power_of_two().min(MAX_SHARDS)
+    })
+}
+
+/// Returns the calling thread's slot, handed out round-robin on first use
+#[inline]
+fn thread_slot() -> usize {
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+    thread_local! {
+        static SLOT: usize = NEXT.fetch_add(1, Ordering::Relaxed);
+    }
+    SLOT.with(|slot| *slot)
+}
+
+/// Monotonic counter sharded across cache lines
+///
+/// Each thread increments its own shard with a relaxed add, so hot threads
+/// never contend; [`Counter::value`] sums the shards.
+///
+/// # Example
+///
+/// ```
+/// use ferrite_core::metrics::Counter;
+///
+/// let counter = Counter::new();
+/// let handles: Vec<_> = (0..4)
+///     .map(|_| {
+///         let counter = counter.clone();
+///         std::thread::spawn(move || {
+///             for _ in 0..1000 {
+///                 counter.inc();
+///             }
+///         })
+///     })
+///     .collect();
+///
+/// for handle in handles {
+///     handle.join().unwrap();
+/// }
+/// assert_eq!(counter.value(), 4000);
+/// ```
+#[derive(Clone)]
+pub struct Counter {
+    shards: Arc<[CachePadded<AtomicU64>]>,
+}
+
+impl Counter {
+    /// Creates a counter starting at zero
+    pub fn new() -> Self {
+        Counter {
+            shards: (0..shard_count())
+                .map(|_| CachePadded { value: AtomicU64::new(0) })
+                .collect(),
+        }
+    }
+
+    /// Adds one
+    #[inline]
+    pub fn inc(&self) {
+        self.add(1);
+    }
+
+    /// Adds `n`
+    #[inline]
+    pub fn add(&self, n: u64) {
+        let shard = thread_slot() & (self.shards.len() - 1);
+        self.shards[shard].value.fetch_add(n, Ordering::Relaxed);
+    }
+
+    /// Returns the sum of every shard
+    ///
+    /// Increments racing with this call may or may not be included.
+    pub fn value(&self) -> u64 {
+        self.shards
+            .iter()
+            .fold(0u64, |sum, shard| sum.wrapping_add(shard.value.load(Ordering::Relaxed)))
+    }
+}
+
+impl Default for Counter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for Counter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Counter").field(&self.value()).finish()
+    }
+}
+
+/// Signed value that is set or adjusted, such as a queue depth
+#[derive(Clone, Default)]
+pub struct Gauge {
+    value: Arc<AtomicI64>,
+}
+
+impl Gauge {
+    /// Creates a gauge reading zero
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replaces the value
+    #[inline]
+    pub fn set(&self, value: i64) {
+        self.value.store(value, Ordering::Relaxed);
+    }
+
+    /// Adds `delta`, which may be negative
+    #[inline]
+    pub fn add(&self, delta: i64) {
+        self.value.fetch_add(delta, Ordering::Relaxed);
+    }
+
+    /// Returns the current value
+    pub fn value(&self) -> i64 {
+        self.value.load(Ordering::Relaxed)
+    }
+}
+
+impl fmt::Debug for Gauge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Gauge").field(&self.value()).finish()
+    }
+}
+
+/// Histogram with fixed, caller-chosen bucket bounds, sharded like [`Counter`]
+///
+/// A value lands in the first bucket whose upper bound is at least the value;
+/// values above the last bound land in an extra overflow bucket. For latency
+/// percentiles on a single thread see [`crate::histogram::Histogram`].
+#[derive(Clone)]
+pub struct BucketHistogram {
+    inner: Arc<HistogramInner>,
+}
+
+struct HistogramInner {
+    bounds: Box<[u64]>,
+    /// Per shard: one counter per bucket, then the overflow bucket, then the sum
+    lines: Box<[CachePadded<[AtomicU64; CELLS_PER_LINE]>]>,
+    lines_per_shard: usize,
+}
+
+impl BucketHistogram {
+    /// Creates a histogram with the given inclusive upper bucket bounds
+    ///
+    /// # Arguments
+    ///
+    /// * `bounds` - Upper bound of each bucket. Must be non-empty and strictly increasing.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(BucketHistogram)` - A new, empty histogram
+    /// * `Err(MetricsError::InvalidBounds)` - If the bounds are empty or out of order
+    pub fn new(bounds: &[u64]) -> Result<Self, MetricsError> {
+        if bounds.is_empty() || bounds.windows(2).any(|pair| pair[0] >= pair[1]) {
+            return Err(MetricsError::InvalidBounds);
+        }
+
+        // Buckets, overflow bucket and sum
+        let cells = bounds.len() + 2;
+        let lines_per_shard = cells.div_ceil(CELLS_PER_LINE);
+        let lines = (0..shard_count() * lines_per_shard)
+            .map(|_| CachePadded {
+                value: std::array::from_fn(|_| AtomicU64::new(0)),
+            })
+            .collect();
+
+        Ok(BucketHistogram {
+            inner: Arc::new(HistogramInner {
+                bounds: bounds.into(),
+                lines,
+                lines_per_shard,
+            }),
+        })
+    }
+
+    /// Records one value
+    #[inline]
+    pub fn record(&self, value: u64) {
+        let inner = &*self.inner;
+        let bucket = inner.bounds.partition_point(|&bound| bound < value);
+        let shard = thread_slot() & (inner.lines.len() / inner.lines_per_shard - 1);
+
+        inner.cell(shard, bucket).fetch_add(1, Ordering::Relaxed);
+        inner.cell(shard, inner.bounds.len() + 1).fetch_add(value, Ordering::Relaxed);
+    }
+
+    /// Returns the bucket bounds
+    pub fn bounds(&self) -> &[u64] {
+        &self.inner.bounds
+    }
+
+    /// Sums every shard into a snapshot
+    pub fn snapshot(&self) -> HistogramSnapshot {
+        let inner = &*self.inner;
+        let shards = inner.lines.len() / inner.lines_per_shard;
+        let mut counts = vec![0u64; inner.bounds.len() + 1];
+        let mut sum = 0u64;
+
+        for shard in 0..shards {
+            for (bucket, count) in counts.iter_mut().enumerate() {
+                *count = count.wrapping_add(inner.cell(shard, bucket).load(Ordering::Relaxed));
+            }
+            sum = sum.wrapping_add(inner.cell(shard, inner.bounds.len() + 1).load(Ordering::Relaxed));
+        }
+
+        HistogramSnapshot {
+            bounds: inner.bounds.to_vec(),
+            count: counts.iter().sum(),
+            counts,
+            sum,
+        }
+    }
+}
+
+impl HistogramInner {
+    #[inline]
+    fn cell(&self, shard: usize, index: usize) -> &AtomicU64 {
+        let line = shard * self.lines_per_shard + index / CELLS_PER_LINE;
+        &self.lines[line].value[index % CELLS_PER_LINE]
+    }
+}
+
+impl fmt::Debug for BucketHistogram {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("BucketHistogram").field(&self.snapshot()).finish()
+    }
+}
+
+/// Point-in-time totals of a [`BucketHistogram`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistogramSnapshot {
+    /// Inclusive upper bound of each bucket
+    pub bounds: Vec<u64>,
+    /// Values per bucket, with one extra entry for values above the last bound
+    pub counts: Vec<u64>,
+    /// Number of values recorded
+    pub count: u64,
+    /// Sum of the values recorded
+    pub sum: u64,
+}
+
+/// A named metric held by a [`Registry`]
+#[derive(Debug, Clone)]
+pub enum Metric {
+    Counter(Counter),
+    Gauge(Gauge),
+    Histogram(BucketHistogram),
+}
+
+/// Value of one metric at the time of a snapshot
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MetricValue {
+    Counter(u64),
+    Gauge(i64),
+    Histogram(HistogramSnapshot),
+}
+
+/// A named value from [`Registry::snapshot`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MetricSnapshot {
+    pub name: String,
+    pub value: MetricValue,
+}
+
+/// Names metrics for export
+///
+/// Registration takes a lock and is meant for startup; keep the returned
+/// handle and update it directly on hot paths. Asking for a name that is
+/// already registered returns a handle to the same metric. Clones share the
+/// same set of metrics.
+#[derive(Debug, Clone, Default)]
+pub struct Registry {
+    metrics: Arc<Mutex<BTreeMap<String, Metric>>>,
+}
+
+impl Registry {
+    /// Creates an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the counter registered under `name`, registering it if needed
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Counter)` - A handle to the named counter
+    /// * `Err(MetricsError::KindMismatch)` - If `name` is registered as another kind
+    pub fn counter(&self, name: &str) -> Result<Counter, MetricsError> {
+        match self.get_or_insert(name, || Ok(Metric::Counter(Counter::new())))? {
+            Metric::Counter(counter) => Ok(counter),
+            _ => Err(MetricsError::KindMismatch(name.to_string())),
+        }
+    }
+
+    /// Returns the gauge registered under `name`, registering it if needed
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Gauge)` - A handle to the named gauge
+    /// * `Err(MetricsError::KindMismatch)` - If `name` is registered as another kind
+    pub fn gauge(&self, name: &str) -> Result<Gauge, MetricsError> {
+        match self.get_or_insert(name, || Ok(Metric::Gauge(Gauge::new())))? {
+            Metric::Gauge(gauge) => Ok(gauge),
+            _ => Err(MetricsError::KindMismatch(name.to_string())),
+        }
+    }
+
+    /// Returns the histogram registered under `name`, registering it if needed
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(BucketHistogram)` - A handle to the named histogram
+    /// * `Err(MetricsError::KindMismatch)` - If `name` is registered as another kind
+    /// * `Err(MetricsError::BoundsMismatch)` - If `name` is registered with other bounds
+    /// * `Err(MetricsError::InvalidBounds)` - If the bounds are empty or out of order
+    pub fn histogram(&self, name: &str, bounds: &[u64]) -> Result<BucketHistogram, MetricsError> {
+        let metric = self.get_or_insert(name, || BucketHistogram::new(bounds).map(Metric::Histogram))?;
+        match metric {
+            Metric::Histogram(histogram) if histogram.bounds() == bounds => Ok(histogram),
+            Metric::Histogram(_) => Err(MetricsError::BoundsMismatch(name.to_string())),
+            _ => Err(MetricsError::KindMismatch(name.to_string())),
+        }
+    }
+
+    /// Returns the current value of every metric, sorted by name
+    pub fn snapshot(&self) -> Vec<MetricSnapshot> {
+        // Clone the handles so values are read without holding the lock
+        let metrics: Vec<(String, Metric)> = self
+            .lock()
+            .iter()
+            .map(|(name, metric)| (name.clone(), metric.clone()))
+            .collect();
+
+        metrics
+            .into_iter()
+            .map(|(name, metric)| MetricSnapshot {
+                name,
+                value: match metric {
+                    Metric::Counter(counter) => MetricValue::Counter(counter.value()),
+                    Metric::Gauge(gauge) => MetricValue::Gauge(gauge.value()),
+                    Metric::Histogram(histogram) => MetricValue::Histogram(histogram.snapshot()),
+                },
+            })
+            .collect()
+    }
+
+    /// Returns the number of registered metrics
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Checks if no metrics are registered
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    fn get_or_insert(
+        &self,
+        name: &str,
+        create: impl FnOnce() -> Result<Metric, MetricsError>,
+    ) -> Result<Metric, MetricsError> {
+        let mut metrics = self.lock();
+        if let Some(metric) = metrics.get(name) {
+            return Ok(metric.clone());
+        }
+
+        let metric = create()?;
+        metrics.insert(name.to_string(), metric.clone());
+        Ok(metric)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BTreeMap<String, Metric>> {
+        // The map is never left half-updated, so a poisoned lock is still usable
+        self.metrics.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Background thread that snapshots a [`Registry`] at a fixed interval
+///
+/// Each snapshot is handed to an export callback. Stopping the collector,
+/// or dropping it, exports one final snapshot so the last interval is not lost.
+pub struct Collector {
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Collector {
+    /// Starts collecting from `registry` every `interval`
+    ///
+    /// # Arguments
+    ///
+    /// * `registry` - The metrics to snapshot
+    /// * `interval` - Time between snapshots
+    /// * `export` - Called on the collector thread with each snapshot
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Collector)` - The collector thread is running
+    /// * `Err(io::Error)` - If the thread could not be spawned
+    pub fn start<F>(registry: Registry, interval: Duration, mut export: F) -> io::Result<Self>
+    where
+        F: FnMut(&[MetricSnapshot]) + Send + 'static,
+    {
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopped = stop.clone();
+
+        let handle = thread::Builder::new()
+            .name("metrics-collector".to_string())
+            .spawn(move || {
+                let mut deadline = Instant::now() + interval;
+                while !stopped.load(Ordering::Acquire) {
+                    let now = Instant::now();
+                    if now < deadline {
+                        thread::park_timeout(deadline - now);
+                        continue;
+                    }
+
+                    export(&registry.snapshot());
+                    deadline += interval;
+                }
+                export(&registry.snapshot());
+            })?;
+
+        Ok(Collector {
+            stop,
+            handle: Some(handle),
+        })
+    }
+
+    /// Stops the collector after one final export and waits for its thread
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - The collector stopped
+    /// * `Err(payload)` - The panic payload if the export callback panicked
+    pub fn stop(mut self) -> thread::Result<()> {
+        self.shutdown()
+    }
+
+    fn shutdown(&mut self) -> thread::Result<()> {
+        let Some(handle) = self.handle.take() else {
+            return Ok(());
+        };
+        self.stop.store(true, Ordering::Release);
+        handle.thread().unpark();
+        handle.join()
+    }
+}
+
+impl Drop for Collector {
+    fn drop(&mut self) {
+        let _ = self.shutdown();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counter_and_gauge() {
+        let counter = Counter::new();
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                let counter = counter.clone();
+                thread::spawn(move || {
+                    for _ in 0..10_000 {
+                        counter.inc();
+                    }
+                    counter.add(5);
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        assert_eq!(counter.value(), 40_020);
+
+        let gauge = Gauge::new();
+        gauge.set(10);
+        gauge.add(-15);
+        assert_eq!(gauge.value(), -5);
+    }
+
+    #[test]
+    fn test_histogram_buckets() {
+        assert_eq!(BucketHistogram::new(&[]).unwrap_err(), MetricsError::InvalidBounds);
+        assert_eq!(BucketHistogram::new(&[10, 10]).unwrap_err(), MetricsError::InvalidBounds);
+
+        // Enough bounds that the cells span more than one cache line
+        let bounds: Vec<u64> = (1..=10).map(|i| i * 10).collect();
+        let histogram = BucketHistogram::new(&bounds).unwrap();
+        for value in [0, 10, 11, 100, 101, u64::MAX / 2] {
+            histogram.record(value);
+        }
+
+        let snapshot = histogram.snapshot();
+        assert_eq!(snapshot.count, 6);
+        assert_eq!(snapshot.sum, 222 + u64::MAX / 2);
+        assert_eq!(snapshot.counts, vec![2, 1, 0, 0, 0, 0, 0, 0, 0, 1, 2]);
+    }
+
+    #[test]
+    fn test_registry_names_metrics() {
+        let registry = Registry::new();
+        let hits = registry.counter("hits").unwrap();
+        registry.counter("hits").unwrap().add(2);
+        hits.inc();
+        registry.gauge("depth").unwrap().set(7);
+        registry.histogram("latency", &[100, 1000]).unwrap().record(50);
+
+        assert_eq!(registry.gauge("hits").unwrap_err(), MetricsError::KindMismatch("hits".to_string()));
+        assert_eq!(
+            registry.histogram("latency", &[100]).unwrap_err(),
+            MetricsError::BoundsMismatch("latency".to_string())
+        );
+        assert_eq!(registry.histogram("bad", &[5, 1]).unwrap_err(), MetricsError::InvalidBounds);
+        assert_eq!(registry.len(), 3);
+
+        let snapshot = registry.snapshot();
+        let names: Vec<_> = snapshot.iter().map(|metric| metric.name.as_str()).collect();
+        assert_eq!(names, ["depth", "hits", "latency"]);
+        assert_eq!(snapshot[0].value, MetricValue::Gauge(7));
+        assert_eq!(snapshot[1].value, MetricValue::Counter(3));
+        let MetricValue::Histogram(latency) = &snapshot[2].value else {
+            panic!("latency is a histogram");
+        };
+        assert_eq!(latency.counts, vec![1, 0, 0]);
+    }
+
+    #[test]
+    fn test_collector_exports_periodically_and_on_stop() {
+        let registry = Registry::new();
+        let events = registry.counter("events").unwrap();
+        let exports = Arc::new(Mutex::new(Vec::new()));
+        let seen = exports.clone();
+
+        let collector = Collector::start(registry, Duration::from_millis(5), move |snapshot| {
+            let MetricValue::Counter(value) = snapshot[0].value else {
+                panic!("events is a counter");
+            };
+            seen.lock().unwrap().push(value);
+        })
+        .unwrap();
+
+        events.add(3);
+        while exports.lock().unwrap().len() < 2 {
+            thread::sleep(Duration::from_millis(1));
+        }
+        events.add(4);
+        collector.stop().unwrap();
+
+        let exports = exports.lock().unwrap();
+        assert!(exports.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert_eq!(*exports.last().unwrap(), 7);
+    }
+}
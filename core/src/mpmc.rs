@@ -0,0 +1,382 @@
+use std::panic::{RefUnwindSafe, UnwindSafe};
+
+use crate::cache_padded::CachePadded;
+use crate::ring_buffer::RingBufferError;
+use crate::slot::{self, readable, writable, Slot};
+use crate::sync::{Arc, AtomicUsize, Backoff, Ordering};
+
+/// A bounded lock-free multi-producer multi-consumer (MPMC) queue
+///
+/// This is Dmitry Vyukov's bounded array queue: producers and consumers each
+/// claim a position with a single CAS and hand the slot over through its
+/// sequence stamp, so a slow thread only ever delays the slot it holds.
+///
+/// This implementation provides:
+/// - Cache-line padding between the producer and consumer positions, as in
+///   the SPSC ring's shared state
+/// - Power-of-two capacity for efficient mask-based wrapping
+/// - Zero allocations in the hot path
+/// - Non-blocking `try_push`/`try_pop` and spinning `push`/`pop`
+///
+/// Every slot is usable: a queue of capacity 8 holds 8 items.
+///
+/// # Thread Safety
+///
+/// Both [`Sender`] and [`Receiver`] can be cloned and shared freely between
+/// threads. Prefer [`RingBuffer`](crate::ring_buffer::RingBuffer) or
+/// [`MpscQueue`](crate::mpsc::MpscQueue) when there is only one producer or
+/// consumer; they avoid the CAS on the uncontended side.
+///
+/// # Example
+///
+/// ```
+/// use ferrite_core::mpmc::MpmcQueue;
+///
+/// let queue = MpmcQueue::<u64>::new(256).unwrap();
+/// let (sender, receiver) = queue.split();
+///
+/// let workers: Vec<_> = (0..2)
+///     .map(|_| {
+///         let receiver = receiver.clone();
+///         std::thread::spawn(move || (0..50).map(|_| receiver.pop()).sum::<u64>())
+///     })
+///     .collect();
+///
+/// for job in 0..100 {
+///     sender.push(job);
+/// }
+///
+/// let total: u64 = workers.into_iter().map(|worker| worker.join().unwrap()).sum();
+/// assert_eq!(total, (0..100).sum());
+/// ```
+pub struct MpmcQueue<T> {
+    slots: Box<[Slot<T>]>,
+}
+
+struct Shared<T> {
+    /// Next position a sender will claim
+    head: CachePadded<AtomicUsize>,
+    /// Next position a receiver will claim
+    tail: CachePadded<AtomicUsize>,
+    slots: Box<[Slot<T>]>,
+    mask: usize,
+}
+
+impl<T> MpmcQueue<T> {
+    /// Creates a new queue with the specified capacity
+    ///
+    /// # Arguments
+    ///
+    /// * `capacity` - The desired capacity. Must be a power of two and greater than 0.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(MpmcQueue<T>)` - A new queue
+    /// * `Err(RingBufferError)` - If capacity is invalid
+    pub fn new(capacity: usize) -> Result<Self, RingBufferError> {
+        if capacity == 0 || !capacity.is_power_of_two() {
+            return Err(RingBufferError::InvalidCapacity(capacity));
+        }
+
+        Ok(MpmcQueue {
+            slots: Slot::array(capacity),
+        })
+    }
+
+    /// Returns the capacity of the queue
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Splits the queue into cloneable sender and receiver handles
+    pub fn split(self) -> (Sender<T>, Receiver<T>) {
+        let mask = self.slots.len() - 1;
+        let shared = Arc::new(Shared {
+            head: CachePadded { value: AtomicUsize::new(0) },
+            tail: CachePadded { value: AtomicUsize::new(0) },
+            slots: self.slots,
+            mask,
+        });
+
+        let sender = Sender {
+            shared: shared.clone(),
+        };
+        let receiver = Receiver { shared };
+
+        (sender, receiver)
+    }
+}
+
+/// Sending handle of an MPMC queue; clone it to add producers
+pub struct Sender<T> {
+    shared: Arc<Shared<T>>,
+}
+
+/// Receiving handle of an MPMC queue; clone it to add consumers
+pub struct Receiver<T> {
+    shared: Arc<Shared<T>>,
+}
+
+unsafe impl<T: Send> Send for Sender<T> {}
+unsafe impl<T: Send> Sync for Sender<T> {}
+unsafe impl<T: Send> Send for Receiver<T> {}
+unsafe impl<T: Send> Sync for Receiver<T> {}
+
+// Slots are only published once fully written and only released once fully
+// read, so a panic cannot leave the queue in a state visible to other handles.
+impl<T: UnwindSafe> UnwindSafe for Sender<T> {}
+impl<T: RefUnwindSafe> RefUnwindSafe for Sender<T> {}
+impl<T: UnwindSafe> UnwindSafe for Receiver<T> {}
+impl<T: RefUnwindSafe> RefUnwindSafe for Receiver<T> {}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        Sender {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<T> Clone for Receiver<T> {
+    fn clone(&self) -> Self {
+        Receiver {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<T> Sender<T> {
+    /// Attempts to push an item into the queue
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - Item was successfully pushed
+    /// * `Err(RingBufferError::BufferFull)` - Queue is full
+    pub fn try_push(&self, value: T) -> Result<(), RingBufferError> {
+        self.offer(value).map_err(|_| RingBufferError::BufferFull)
+    }
+
+    /// Pushes `value`, handing it back if the queue is full
+    pub(crate) fn offer(&self, value: T) -> Result<(), T> {
+        slot::push(&self.shared.slots, &self.shared.head.value, value)
+    }
+
+    /// Pushes an item, spinning and then yielding until there is room
+    pub fn push(&self, value: T) {
+        let mut value = value;
+        let mut backoff = Backoff::new();
+        while let Err(rejected) = self.offer(value) {
+            value = rejected;
+            backoff.snooze();
+        }
+    }
+
+    /// Returns the number of items that can be pushed before the queue is full
+    ///
+    /// Other handles may push or pop concurrently, so this is only a snapshot.
+    pub fn remaining_capacity(&self) -> usize {
+        (self.shared.mask + 1).saturating_sub(self.shared.len())
+    }
+
+    /// Checks if the queue is full
+    pub fn is_full(&self) -> bool {
+        self.remaining_capacity() == 0
+    }
+}
+
+impl<T> Receiver<T> {
+    /// Attempts to pop an item from the queue
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(T)` - Successfully popped an item
+    /// * `Err(RingBufferError::BufferEmpty)` - Queue is empty, or the next
+    ///   claimed slot is still being written
+    pub fn try_pop(&self) -> Result<T, RingBufferError> {
+        self.shared.try_pop().ok_or(RingBufferError::BufferEmpty)
+    }
+
+    /// Pops an item, spinning and then yielding until one is available
+    pub fn pop(&self) -> T {
+        let mut backoff = Backoff::new();
+        loop {
+            if let Some(value) = self.shared.try_pop() {
+                return value;
+            }
+            backoff.snooze();
+        }
+    }
+
+    /// Returns the number of items claimed by senders and not yet claimed by receivers
+    ///
+    /// Other handles may push or pop concurrently, so this is only a snapshot.
+    pub fn len(&self) -> usize {
+        self.shared.len()
+    }
+
+    /// Checks if the queue is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<T> Shared<T> {
+    fn try_pop(&self) -> Option<T> {
+        let mut tail = self.tail.value.load(Ordering::Relaxed);
+
+        loop {
+            let slot = &self.slots[tail & self.mask];
+            let sequence = slot.sequence.load(Ordering::Acquire);
+            let lag = sequence.wrapping_sub(readable(tail)) as isize;
+
+            if lag == 0 {
+                match self.tail.value.compare_exchange_weak(
+                    tail,
+                    tail.wrapping_add(1),
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => {
+                        // The CAS gave this receiver exclusive ownership of the
+                        // published slot until it is released below.
+                        let value = slot.value.with_mut(|ptr| unsafe { (*ptr).assume_init_read() });
+                        slot.sequence.store(
+                            writable(tail.wrapping_add(self.mask + 1)),
+                            Ordering::Release,
+                        );
+                        return Some(value);
+                    }
+                    Err(current) => tail = current,
+                }
+            } else if lag < 0 {
+                // Not written yet for this lap
+                return None;
+            } else {
+                tail = self.tail.value.load(Ordering::Relaxed);
+            }
+        }
+    }
+
+    fn len(&self) -> usize {
+        loop {
+            let tail = self.tail.value.load(Ordering::Acquire);
+            let head = self.head.value.load(Ordering::Acquire);
+            // Retry if a receiver moved the tail while we read the head
+            if self.tail.value.load(Ordering::Acquire) == tail {
+                return head.wrapping_sub(tail).min(self.mask + 1);
+            }
+        }
+    }
+}
+
+impl<T> Drop for Shared<T> {
+    fn drop(&mut self) {
+        // Every handle is gone, so every claimed slot has been written
+        let head = self.head.value.load(Ordering::Relaxed);
+        let mut tail = self.tail.value.load(Ordering::Relaxed);
+
+        while tail != head {
+            let slot = &self.slots[tail & self.mask];
+            tail = tail.wrapping_add(1);
+            slot.value.with_mut(|ptr| unsafe { (*ptr).assume_init_drop() });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_invalid_capacity() {
+        assert!(matches!(
+            MpmcQueue::<u32>::new(0),
+            Err(RingBufferError::InvalidCapacity(0))
+        ));
+        assert!(matches!(
+            MpmcQueue::<u32>::new(6),
+            Err(RingBufferError::InvalidCapacity(6))
+        ));
+    }
+
+    #[test]
+    fn test_try_push_try_pop() {
+        let queue = MpmcQueue::<u32>::new(2).unwrap();
+        let (sender, receiver) = queue.split();
+
+        assert_eq!(receiver.try_pop(), Err(RingBufferError::BufferEmpty));
+        sender.try_push(1).unwrap();
+        sender.try_push(2).unwrap();
+        assert!(sender.is_full());
+        assert_eq!(sender.try_push(3), Err(RingBufferError::BufferFull));
+        assert_eq!(receiver.len(), 2);
+
+        assert_eq!(receiver.try_pop(), Ok(1));
+        sender.try_push(3).unwrap();
+        assert_eq!(receiver.try_pop(), Ok(2));
+        assert_eq!(receiver.try_pop(), Ok(3));
+        assert!(receiver.is_empty());
+        assert_eq!(sender.remaining_capacity(), 2);
+    }
+
+    #[test]
+    fn test_capacity_one() {
+        let queue = MpmcQueue::<u32>::new(1).unwrap();
+        let (sender, receiver) = queue.split();
+
+        for i in 0..10 {
+            sender.try_push(i).unwrap();
+            assert_eq!(sender.try_push(i), Err(RingBufferError::BufferFull));
+            assert_eq!(receiver.try_pop(), Ok(i));
+        }
+    }
+
+    #[test]
+    fn test_blocking_many_to_many() {
+        const PER_SENDER: u64 = 2_000;
+        let queue = MpmcQueue::<u64>::new(16).unwrap();
+        let (sender, receiver) = queue.split();
+
+        let senders: Vec<_> = (0..3)
+            .map(|id| {
+                let sender = sender.clone();
+                std::thread::spawn(move || {
+                    for i in 0..PER_SENDER {
+                        sender.push(id * PER_SENDER + i);
+                    }
+                })
+            })
+            .collect();
+
+        let receivers: Vec<_> = (0..3)
+            .map(|_| {
+                let receiver = receiver.clone();
+                std::thread::spawn(move || (0..PER_SENDER).map(|_| receiver.pop()).collect::<Vec<_>>())
+            })
+            .collect();
+
+        senders.into_iter().for_each(|handle| handle.join().unwrap());
+        let mut received: Vec<u64> = receivers
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect();
+
+        received.sort_unstable();
+        assert_eq!(received, (0..3 * PER_SENDER).collect::<Vec<_>>());
+        assert!(receiver.is_empty());
+    }
+
+    #[test]
+    fn test_remaining_items_dropped() {
+        let item = std::sync::Arc::new(());
+        let queue = MpmcQueue::new(4).unwrap();
+        let (sender, receiver) = queue.split();
+
+        for _ in 0..3 {
+            sender.try_push(item.clone()).unwrap();
+        }
+        drop(receiver.try_pop());
+        drop((sender, receiver));
+        assert_eq!(std::sync::Arc::strong_count(&item), 1);
+    }
+}
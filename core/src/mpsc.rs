@@ -0,0 +1,303 @@
+use std::panic::{RefUnwindSafe, UnwindSafe};
+
+use crate::cache_padded::CachePadded;
+use crate::ring_buffer::RingBufferError;
+use crate::slot::{self, readable, writable, Slot};
+use crate::sync::{Arc, AtomicUsize, Ordering};
+
+/// A bounded lock-free multi-producer single-consumer (MPSC) queue
+///
+/// This implementation provides:
+/// - Per-slot sequence stamps, so producers claim a slot with a single CAS and
+///   never wait on each other while writing
+/// - Cache-line padding between the producer and consumer positions
+/// - Power-of-two capacity for efficient mask-based wrapping
+/// - Zero allocations in the hot path
+///
+/// Unlike [`RingBuffer`](crate::ring_buffer::RingBuffer), every slot is usable:
+/// a queue of capacity 8 holds 8 items.
+///
+/// # Thread Safety
+///
+/// [`Producer`] can be cloned and shared freely between threads. There is
+/// exactly one [`Consumer`].
+///
+/// # Example
+///
+/// ```
+/// use ferrite_core::mpsc::MpscQueue;
+///
+/// let queue = MpscQueue::<u32>::new(1024).unwrap();
+/// let (producer, mut consumer) = queue.split();
+///
+/// let handles: Vec<_> = (0..4)
+///     .map(|session| {
+///         let producer = producer.clone();
+///         std::thread::spawn(move || {
+///             for i in 0..10 {
+///                 while producer.push(session * 100 + i).is_err() {
+///                     std::thread::yield_now();
+///                 }
+///             }
+///         })
+///     })
+///     .collect();
+/// handles.into_iter().for_each(|handle| handle.join().unwrap());
+///
+/// let mut received = 0;
+/// while consumer.pop().is_ok() {
+///     received += 1;
+/// }
+/// assert_eq!(received, 40);
+/// ```
+pub struct MpscQueue<T> {
+    slots: Box<[Slot<T>]>,
+}
+
+struct Shared<T> {
+    /// Next position a producer will claim
+    head: CachePadded<AtomicUsize>,
+    /// Next position the consumer will read
+    tail: CachePadded<AtomicUsize>,
+    slots: Box<[Slot<T>]>,
+    mask: usize,
+}
+
+impl<T> MpscQueue<T> {
+    /// Creates a new queue with the specified capacity
+    ///
+    /// # Arguments
+    ///
+    /// * `capacity` - The desired capacity. Must be a power of two and greater than 0.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(MpscQueue<T>)` - A new queue
+    /// * `Err(RingBufferError)` - If capacity is invalid
+    pub fn new(capacity: usize) -> Result<Self, RingBufferError> {
+        if capacity == 0 || !capacity.is_power_of_two() {
+            return Err(RingBufferError::InvalidCapacity(capacity));
+        }
+
+        Ok(MpscQueue {
+            slots: Slot::array(capacity),
+        })
+    }
+
+    /// Returns the capacity of the queue
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Splits the queue into a cloneable producer and the single consumer
+    pub fn split(self) -> (Producer<T>, Consumer<T>) {
+        let mask = self.slots.len() - 1;
+        let shared = Arc::new(Shared {
+            head: CachePadded { value: AtomicUsize::new(0) },
+            tail: CachePadded { value: AtomicUsize::new(0) },
+            slots: self.slots,
+            mask,
+        });
+
+        let producer = Producer {
+            shared: shared.clone(),
+        };
+        let consumer = Consumer { shared, tail: 0 };
+
+        (producer, consumer)
+    }
+}
+
+/// Producer handle of an MPSC queue; clone it to add producers
+pub struct Producer<T> {
+    shared: Arc<Shared<T>>,
+}
+
+/// Consumer half of an MPSC queue
+pub struct Consumer<T> {
+    shared: Arc<Shared<T>>,
+    /// Local copy of the next position to read
+    tail: usize,
+}
+
+unsafe impl<T: Send> Send for Producer<T> {}
+unsafe impl<T: Send> Sync for Producer<T> {}
+unsafe impl<T: Send> Send for Consumer<T> {}
+
+// Slots are only published once fully written and only released once fully
+// read, so a panic cannot leave the queue in a state visible to other handles.
+impl<T: UnwindSafe> UnwindSafe for Producer<T> {}
+impl<T: RefUnwindSafe> RefUnwindSafe for Producer<T> {}
+impl<T: UnwindSafe> UnwindSafe for Consumer<T> {}
+impl<T: RefUnwindSafe> RefUnwindSafe for Consumer<T> {}
+
+impl<T> Clone for Producer<T> {
+    fn clone(&self) -> Self {
+        Producer {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<T> Producer<T> {
+    /// Attempts to push an item into the queue
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - Item was successfully pushed
+    /// * `Err(RingBufferError::BufferFull)` - Queue is full
+    pub fn push(&self, value: T) -> Result<(), RingBufferError> {
+        self.offer(value).map_err(|_| RingBufferError::BufferFull)
+    }
+
+    /// Pushes `value`, handing it back if the queue is full
+    pub(crate) fn offer(&self, value: T) -> Result<(), T> {
+        slot::push(&self.shared.slots, &self.shared.head.value, value)
+    }
+
+    /// Returns the number of items that can be pushed before the queue is full
+    ///
+    /// Other producers may push concurrently, so this is only a snapshot.
+    pub fn remaining_capacity(&self) -> usize {
+        let capacity = self.shared.mask + 1;
+        let tail = self.shared.tail.value.load(Ordering::Acquire);
+        let head = self.shared.head.value.load(Ordering::Relaxed);
+        capacity.saturating_sub(head.wrapping_sub(tail))
+    }
+
+    /// Checks if the queue is full
+    pub fn is_full(&self) -> bool {
+        self.remaining_capacity() == 0
+    }
+}
+
+impl<T> Consumer<T> {
+    /// Attempts to pop an item from the queue
+    ///
+    /// Items pushed by the same producer are popped in push order.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(T)` - Successfully popped an item
+    /// * `Err(RingBufferError::BufferEmpty)` - Queue is empty, or the next
+    ///   claimed slot is still being written
+    pub fn pop(&mut self) -> Result<T, RingBufferError> {
+        let shared = &*self.shared;
+        let tail = self.tail;
+        let slot = &shared.slots[tail & shared.mask];
+
+        if slot.sequence.load(Ordering::Acquire) != readable(tail) {
+            return Err(RingBufferError::BufferEmpty);
+        }
+
+        // Published by the producer's release store and read exactly once
+        let value = slot.value.with_mut(|ptr| unsafe { (*ptr).assume_init_read() });
+        slot.sequence
+            .store(writable(tail.wrapping_add(shared.mask + 1)), Ordering::Release);
+
+        self.tail = tail.wrapping_add(1);
+        shared.tail.value.store(self.tail, Ordering::Release);
+        Ok(value)
+    }
+
+    /// Returns the number of items claimed by producers and not yet popped
+    ///
+    /// This includes items that are still being written.
+    pub fn len(&self) -> usize {
+        let head = self.shared.head.value.load(Ordering::Acquire);
+        head.wrapping_sub(self.tail).min(self.shared.mask + 1)
+    }
+
+    /// Checks if the queue is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<T> Drop for Shared<T> {
+    fn drop(&mut self) {
+        // Every handle is gone, so every claimed slot has been written
+        let head = self.head.value.load(Ordering::Relaxed);
+        let mut tail = self.tail.value.load(Ordering::Relaxed);
+
+        while tail != head {
+            let slot = &self.slots[tail & self.mask];
+            tail = tail.wrapping_add(1);
+            slot.value.with_mut(|ptr| unsafe { (*ptr).assume_init_drop() });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_invalid_capacity() {
+        assert!(matches!(
+            MpscQueue::<u32>::new(0),
+            Err(RingBufferError::InvalidCapacity(0))
+        ));
+        assert!(matches!(
+            MpscQueue::<u32>::new(100),
+            Err(RingBufferError::InvalidCapacity(100))
+        ));
+    }
+
+    #[test]
+    fn test_full_capacity_usable() {
+        let queue = MpscQueue::<u32>::new(4).unwrap();
+        assert_eq!(queue.capacity(), 4);
+        let (producer, mut consumer) = queue.split();
+
+        for i in 0..4 {
+            producer.push(i).unwrap();
+        }
+        assert!(producer.is_full());
+        assert_eq!(producer.push(4), Err(RingBufferError::BufferFull));
+        assert_eq!(consumer.len(), 4);
+
+        assert_eq!(consumer.pop(), Ok(0));
+        assert_eq!(producer.remaining_capacity(), 1);
+        producer.push(4).unwrap();
+
+        for i in 1..5 {
+            assert_eq!(consumer.pop(), Ok(i));
+        }
+        assert_eq!(consumer.pop(), Err(RingBufferError::BufferEmpty));
+        assert!(consumer.is_empty());
+    }
+
+    #[test]
+    fn test_cloned_producers_share_queue() {
+        let queue = MpscQueue::<u32>::new(8).unwrap();
+        let (producer, mut consumer) = queue.split();
+        let other = producer.clone();
+
+        producer.push(1).unwrap();
+        other.push(2).unwrap();
+        drop(producer);
+        other.push(3).unwrap();
+
+        assert_eq!(consumer.pop(), Ok(1));
+        assert_eq!(consumer.pop(), Ok(2));
+        assert_eq!(consumer.pop(), Ok(3));
+    }
+
+    #[test]
+    fn test_remaining_items_dropped() {
+        let item = std::sync::Arc::new(());
+        let queue = MpscQueue::new(8).unwrap();
+        let (producer, mut consumer) = queue.split();
+
+        for _ in 0..5 {
+            producer.push(item.clone()).unwrap();
+        }
+        consumer.pop().unwrap();
+        assert_eq!(std::sync::Arc::strong_count(&item), 5);
+
+        drop(producer);
+        drop(consumer);
+        assert_eq!(std::sync::Arc::strong_count(&item), 1);
+    }
+}
@@ -0,0 +1,469 @@
+use std::cell::RefCell;
+use std::error::Error;
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+
+use crate::ring_buffer::{Consumer, Producer, RingBuffer, RingBufferError};
+use crate::sync::Backoff;
+
+/// Error types for object pool operations
+#[derive(Debug, Clone, PartialEq)]
+pub enum PoolError {
+    /// No object has been returned and the policy is [`ExhaustionPolicy::Fail`]
+    Exhausted,
+}
+
+impl fmt::Display for PoolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PoolError::Exhausted => write!(f, "Object pool is exhausted"),
+        }
+    }
+}
+
+impl Error for PoolError {}
+
+/// What [`PoolSender::acquire`] does when no returned object is available
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExhaustionPolicy {
+    /// Build a new object with the pool's factory
+    ///
+    /// The pool can then hold more objects than its return ring fits; surplus
+    /// objects are dropped when they come back to a full ring.
+    #[default]
+    Allocate,
+    /// Return [`PoolError::Exhausted`]
+    Fail,
+    /// Spin and then yield until the receiver returns an object
+    ///
+    /// Never returns if every object is waiting in the forward ring and the
+    /// receiver is driven by the same thread. Objects only ever come from the
+    /// preallocated set, so [`ObjectPool::split`] rejects this policy when
+    /// none are preallocated.
+    Wait,
+}
+
+/// A pool of reusable objects circulating between a producer and a consumer thread
+///
+/// Built on a pair of SPSC rings: the forward ring carries filled objects to
+/// the [`PoolReceiver`], and the return ring carries them back to the
+/// [`PoolSender`] once the consumer is done. Objects are allocated once and
+/// then recycled, so in steady state neither thread touches the allocator.
+///
+/// Received objects are wrapped in a [`Pooled`] handle that sends the object
+/// back when it is dropped, after resetting it with the optional reset function.
+///
+/// # Example
+///
+/// ```
+/// use ferrite_core::pool::{ExhaustionPolicy, ObjectPool};
+///
+/// let pool = ObjectPool::new(64, || Vec::<u8>::with_capacity(1500))
+///     .unwrap()
+///     .with_preallocated(32)
+///     .with_exhaustion_policy(ExhaustionPolicy::Wait)
+///     .with_reset(Vec::clear);
+/// let (mut sender, receiver) = pool.split();
+///
+/// let handle = std::thread::spawn(move || {
+///     for i in 0..1_000u32 {
+///         let mut message = sender.acquire().unwrap();
+///         message.extend_from_slice(&i.to_le_bytes());
+///         while let Err(rejected) = sender.send(message) {
+///             message = rejected;
+///             std::thread::yield_now();
+///         }
+///     }
+/// });
+///
+/// let mut received = 0;
+/// while received < 1_000 {
+///     match receiver.recv() {
+///         // Returned to the pool, cleared, when `message` goes out of scope
+///         Ok(message) => {
+///             assert_eq!(message.len(), 4);
+///             received += 1;
+///         }
+///         Err(_) => std::thread::yield_now(),
+///     }
+/// }
+/// handle.join().unwrap();
+/// ```
+pub struct ObjectPool<T> {
+    capacity: usize,
+    preallocated: usize,
+    policy: ExhaustionPolicy,
+    factory: Box<dyn FnMut() -> T + Send>,
+    reset: Option<fn(&mut T)>,
+}
+
+impl<T> ObjectPool<T> {
+    /// Creates a new pool
+    ///
+    /// By default the return ring is filled with preallocated objects and the
+    /// pool allocates more when it runs dry.
+    ///
+    /// # Arguments
+    ///
+    /// * `capacity` - Capacity of both rings. Must be a power of two and greater than 0;
+    ///   each ring holds up to `capacity - 1` objects.
+    /// * `factory` - Builds a new object
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(ObjectPool<T>)` - A new pool
+    /// * `Err(RingBufferError)` - If capacity is invalid
+    pub fn new<F>(capacity: usize, factory: F) -> Result<Self, RingBufferError>
+    where
+        F: FnMut() -> T + Send + 'static,
+    {
+        if capacity == 0 || !capacity.is_power_of_two() {
+            return Err(RingBufferError::InvalidCapacity(capacity));
+        }
+
+        Ok(ObjectPool {
+            capacity,
+            preallocated: capacity - 1,
+            policy: ExhaustionPolicy::default(),
+            factory: Box::new(factory),
+            reset: None,
+        })
+    }
+
+    /// Sets how many objects are built up front and placed in the return ring
+    ///
+    /// # Panics
+    ///
+    /// Panics if `count` is not less than the pool's capacity.
+    pub fn with_preallocated(mut self, count: usize) -> Self {
+        assert!(
+            count < self.capacity,
+            "cannot preallocate {} objects in a pool of capacity {}",
+            count,
+            self.capacity
+        );
+        self.preallocated = count;
+        self
+    }
+
+    /// Sets what happens when no returned object is available
+    pub fn with_exhaustion_policy(mut self, policy: ExhaustionPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Sets a function applied to every object on its way back to the pool,
+    /// such as `Vec::clear`
+    pub fn with_reset(mut self, reset: fn(&mut T)) -> Self {
+        self.reset = Some(reset);
+        self
+    }
+
+    /// Returns the capacity of each ring
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Builds the preallocated objects and splits the pool into its two ends
+    ///
+    /// # Panics
+    ///
+    /// Panics if the policy is [`ExhaustionPolicy::Wait`] and no objects are
+    /// preallocated, since `acquire` would then wait forever.
+    pub fn split(mut self) -> (PoolSender<T>, PoolReceiver<T>) {
+        assert!(
+            self.preallocated > 0 || self.policy != ExhaustionPolicy::Wait,
+            "a pool that waits for returned objects needs at least one preallocated object"
+        );
+
+        let (forward, received) = RingBuffer::new(self.capacity)
+            .expect("capacity validated in new")
+            .split();
+        let (mut returns, free) = RingBuffer::new(self.capacity)
+            .expect("capacity validated in new")
+            .split();
+
+        for _ in 0..self.preallocated {
+            // `preallocated` is below the ring's usable capacity
+            let _ = returns.push((self.factory)());
+        }
+
+        let sender = PoolSender {
+            forward,
+            free,
+            factory: self.factory,
+            policy: self.policy,
+        };
+        let receiver = PoolReceiver {
+            received: RefCell::new(received),
+            returns: RefCell::new(returns),
+            reset: self.reset,
+        };
+
+        (sender, receiver)
+    }
+}
+
+/// Producer end of an object pool: takes free objects and sends them filled
+pub struct PoolSender<T> {
+    forward: Producer<T>,
+    free: Consumer<T>,
+    factory: Box<dyn FnMut() -> T + Send>,
+    policy: ExhaustionPolicy,
+}
+
+/// Consumer end of an object pool: receives objects and returns them on drop
+pub struct PoolReceiver<T> {
+    received: RefCell<Consumer<T>>,
+    returns: RefCell<Producer<T>>,
+    reset: Option<fn(&mut T)>,
+}
+
+impl<T> PoolSender<T> {
+    /// Takes a free object, applying the pool's [`ExhaustionPolicy`] if there is none
+    ///
+    /// The object holds whatever the reset function left in it.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(T)` - A free object
+    /// * `Err(PoolError::Exhausted)` - No object is free and the policy is `Fail`
+    pub fn acquire(&mut self) -> Result<T, PoolError> {
+        if let Ok(object) = self.free.pop() {
+            return Ok(object);
+        }
+
+        match self.policy {
+            ExhaustionPolicy::Allocate => Ok((self.factory)()),
+            ExhaustionPolicy::Fail => Err(PoolError::Exhausted),
+            ExhaustionPolicy::Wait => {
+                let mut backoff = Backoff::new();
+                loop {
+                    if let Ok(object) = self.free.pop() {
+                        return Ok(object);
+                    }
+                    backoff.snooze();
+                }
+            }
+        }
+    }
+
+    /// Sends an object to the receiver
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - The object was sent
+    /// * `Err(T)` - The forward ring is full; the object is handed back
+    pub fn send(&mut self, object: T) -> Result<(), T> {
+        if self.forward.is_full() {
+            return Err(object);
+        }
+        // Only this end pushes, so the ring cannot fill up in between
+        let _ = self.forward.push(object);
+        Ok(())
+    }
+
+    /// Returns the number of returned objects ready to be acquired
+    pub fn available(&self) -> usize {
+        self.free.len()
+    }
+}
+
+impl<T> PoolReceiver<T> {
+    /// Attempts to receive the next object
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Pooled<T>)` - The object, returned to the pool when the handle drops
+    /// * `Err(RingBufferError::BufferEmpty)` - Nothing has been sent
+    pub fn recv(&self) -> Result<Pooled<'_, T>, RingBufferError> {
+        let object = self.received.borrow_mut().pop()?;
+        Ok(Pooled {
+            object: Some(object),
+            receiver: self,
+        })
+    }
+
+    /// Returns the number of objects waiting to be received
+    pub fn len(&self) -> usize {
+        self.received.borrow().len()
+    }
+
+    /// Checks if no objects are waiting to be received
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn give_back(&self, mut object: T) {
+        if let Some(reset) = self.reset {
+            reset(&mut object);
+        }
+
+        let mut returns = self.returns.borrow_mut();
+        if !returns.is_full() {
+            // Only this end pushes, so the ring cannot fill up in between
+            let _ = returns.push(object);
+            return;
+        }
+        drop(returns);
+        // Surplus from `ExhaustionPolicy::Allocate`; dropped outside the borrow
+        drop(object);
+    }
+}
+
+/// A received object that goes back to its pool when dropped
+///
+/// Borrows the [`PoolReceiver`], so it stays on the consumer thread.
+pub struct Pooled<'a, T> {
+    /// Always `Some` until taken by `into_inner` or `drop`
+    object: Option<T>,
+    receiver: &'a PoolReceiver<T>,
+}
+
+impl<T> Pooled<'_, T> {
+    /// Takes the object out of the pool for good
+    pub fn into_inner(mut self) -> T {
+        self.object.take().expect("object present until dropped")
+    }
+}
+
+impl<T> Deref for Pooled<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.object.as_ref().expect("object present until dropped")
+    }
+}
+
+impl<T> DerefMut for Pooled<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.object.as_mut().expect("object present until dropped")
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Pooled<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T> Drop for Pooled<'_, T> {
+    fn drop(&mut self) {
+        if let Some(object) = self.object.take() {
+            self.receiver.give_back(object);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_objects_are_recycled() {
+        let pool = ObjectPool::new(4, || Vec::<u8>::with_capacity(64))
+            .unwrap()
+            .with_preallocated(1)
+            .with_exhaustion_policy(ExhaustionPolicy::Fail)
+            .with_reset(Vec::clear);
+        let (mut sender, receiver) = pool.split();
+
+        let mut message = sender.acquire().unwrap();
+        let allocation = message.as_ptr();
+        message.extend_from_slice(b"first");
+        sender.send(message).unwrap();
+        assert_eq!(sender.acquire(), Err(PoolError::Exhausted));
+
+        let received = receiver.recv().unwrap();
+        assert_eq!(&received[..], b"first");
+        drop(received);
+
+        let message = sender.acquire().unwrap();
+        assert_eq!(message.as_ptr(), allocation);
+        assert!(message.is_empty());
+    }
+
+    #[test]
+    fn test_allocate_policy_drops_surplus() {
+        let token = std::sync::Arc::new(());
+        let factory_token = token.clone();
+        let pool = ObjectPool::new(2, move || factory_token.clone())
+            .unwrap()
+            .with_preallocated(0);
+        let (mut sender, receiver) = pool.split();
+        // `token` and the factory's copy
+        let live = |extra: usize| assert_eq!(std::sync::Arc::strong_count(&token), 2 + extra);
+
+        let first = sender.acquire().unwrap();
+        let second = sender.acquire().unwrap();
+        live(2);
+
+        sender.send(first).unwrap();
+        let first = receiver.recv().unwrap();
+        sender.send(second).unwrap();
+        let second = receiver.recv().unwrap();
+
+        // Each ring of capacity 2 holds one object, so the second return is dropped
+        drop(first);
+        drop(second);
+        live(1);
+        assert_eq!(sender.available(), 1);
+    }
+
+    #[test]
+    fn test_into_inner_leaves_pool() {
+        let pool = ObjectPool::new(4, || 0u32).unwrap().with_preallocated(1);
+        let (mut sender, receiver) = pool.split();
+
+        let object = sender.acquire().unwrap();
+        sender.send(object + 7).unwrap();
+        assert_eq!(receiver.len(), 1);
+
+        assert_eq!(receiver.recv().unwrap().into_inner(), 7);
+        assert!(receiver.is_empty());
+        assert_eq!(sender.available(), 0);
+    }
+
+    #[test]
+    fn test_wait_policy_across_threads() {
+        let pool = ObjectPool::new(4, || Box::new(0u64))
+            .unwrap()
+            .with_exhaustion_policy(ExhaustionPolicy::Wait);
+        let (mut sender, receiver) = pool.split();
+
+        let handle = std::thread::spawn(move || {
+            for i in 1..=1_000 {
+                let mut object = sender.acquire().unwrap();
+                *object = i;
+                while let Err(rejected) = sender.send(object) {
+                    object = rejected;
+                    std::thread::yield_now();
+                }
+            }
+        });
+
+        let mut sum = 0;
+        let mut received = 0;
+        while received < 1_000 {
+            match receiver.recv() {
+                Ok(object) => {
+                    sum += **object;
+                    received += 1;
+                }
+                Err(_) => std::thread::yield_now(),
+            }
+        }
+        handle.join().unwrap();
+        assert_eq!(sum, 500_500);
+    }
+
+    #[test]
+    #[should_panic(expected = "at least one preallocated object")]
+    fn test_wait_policy_without_objects_rejected() {
+        let pool = ObjectPool::new(4, || 0u32)
+            .unwrap()
+            .with_exhaustion_policy(ExhaustionPolicy::Wait)
+            .with_preallocated(0);
+        let _ = pool.split();
+    }
+}
@@ -0,0 +1,685 @@
+//! Rate limiters for keeping message rates under a venue's limits
+//!
+//! - [`TokenBucket`] allows bursts of up to `capacity` permits and refills
+//!   them evenly over `period`. It is implemented as the generic cell rate
+//!   algorithm (GCRA), the leaky bucket viewed as a meter, so its whole state
+//!   is a single timestamp.
+//! - [`SlidingWindow`] allows at most `capacity` permits in any window of
+//!   length `period`. It remembers when each of the last `capacity` permits
+//!   was taken, so it is exact but costs one `u64` per permit.
+//!
+//! Both come in two flavours: the plain types take `&mut self` and use no
+//! atomics, for a single hot thread, while [`SharedTokenBucket`] and
+//! [`SharedSlidingWindow`] take `&self` and can be shared across threads
+//! behind an `Arc`. The shared token bucket is lock-free; a successful
+//! acquire on the shared sliding window can wait for other threads, as
+//! described on the type.
+//!
+//! Every limiter reads time from a [`TimeSource`], [`Clock`] by default, so
+//! tests can drive them with a [`ManualClock`](crate::clock::ManualClock).
+//!
+//! # Example
+//!
+//! ```
+//! use ferrite_core::clock::ManualClock;
+//! use ferrite_core::rate_limit::TokenBucket;
+//! use std::time::Duration;
+//!
+//! // 10 orders per second, with the full 10 available as a burst
+//! let clock = ManualClock::new(0);
+//! let mut limiter = TokenBucket::with_clock(10, Duration::from_secs(1), clock.clone()).unwrap();
+//!
+//! assert!(limiter.try_acquire(10));
+//! assert!(!limiter.try_acquire(1));
+//! assert_eq!(limiter.time_until_available(1), Some(Duration::from_millis(100)));
+//!
+//! clock.advance(Duration::from_millis(100));
+//! assert!(limiter.try_acquire(1));
+//! ```
+
+use std::error::Error;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use crate::cache_padded::CachePadded;
+use crate::clock::{Clock, TimeSource};
+use crate::sync::Backoff;
+
+/// Error type for rate limiter construction
+#[derive(Debug, Clone, PartialEq)]
+pub enum RateLimitError {
+    /// Capacity must be greater than 0
+    InvalidCapacity(u64),
+    /// Period must be at least 1ns and fit in 64 bits of nanoseconds
+    InvalidPeriod(Duration),
+}
+
+impl fmt::Display for RateLimitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RateLimitError::InvalidCapacity(capacity) => {
+                write!(f, "Invalid capacity: {}. Must be greater than 0", capacity)
+            }
+            RateLimitError::InvalidPeriod(period) => {
+                write!(f, "Invalid period: {:?}. Must be between 1ns and u64::MAX ns", period)
+            }
+        }
+    }
+}
+
+impl Error for RateLimitError {}
+
+/// Validates a limit and returns its period in nanoseconds
+fn period_nanos(capacity: u64, period: Duration) -> Result<u64, RateLimitError> {
+    if capacity == 0 {
+        return Err(RateLimitError::InvalidCapacity(capacity));
+    }
+    match u64::try_from(period.as_nanos()) {
+        Ok(nanos) if nanos > 0 => Ok(nanos),
+        _ => Err(RateLimitError::InvalidPeriod(period)),
+    }
+}
+
+/// GCRA parameters shared by both token bucket flavours
+#[derive(Debug, Clone, Copy)]
+struct Gcra {
+    capacity: u64,
+    period: Duration,
+    /// Nanoseconds to refill one permit, rounded up so the limit is never exceeded
+    emission: u64,
+    /// How far the theoretical arrival time may run ahead of now
+    tolerance: u64,
+}
+
+impl Gcra {
+    fn new(capacity: u64, period: Duration) -> Result<Self, RateLimitError> {
+        let nanos = period_nanos(capacity, period)?;
+        let emission = nanos.div_ceil(capacity);
+        Ok(Gcra {
+            capacity,
+            period,
+            emission,
+            tolerance: emission.saturating_mul(capacity),
+        })
+    }
+
+    /// Returns the new theoretical arrival time if `n` permits fit at `now`
+    #[inline]
+    fn acquire(&self, tat: u64, now: u64, n: u64) -> Option<u64> {
+        if n > self.capacity {
+            return None;
+        }
+        let next = tat.max(now).saturating_add(n.saturating_mul(self.emission));
+        (next - now <= self.tolerance).then_some(next)
+    }
+
+    fn wait(&self, tat: u64, now: u64, n: u64) -> Option<Duration> {
+        if n > self.capacity {
+            return None;
+        }
+        let ready_at = tat.max(now).saturating_add(n.saturating_mul(self.emission)).saturating_sub(self.tolerance);
+        Some(Duration::from_nanos(ready_at.saturating_sub(now)))
+    }
+}
+
+/// Token bucket for a single thread
+///
+/// Holds up to `capacity` permits and refills them at `capacity` per
+/// `period`; it starts full.
+#[derive(Debug)]
+pub struct TokenBucket<C = Clock> {
+    gcra: Gcra,
+    /// Theoretical arrival time: when the bucket will next be full
+    tat: u64,
+    clock: C,
+}
+
+impl TokenBucket<Clock> {
+    /// Creates a token bucket that reads time from [`Clock::global`]
+    ///
+    /// # Arguments
+    ///
+    /// * `capacity` - Largest burst, and the number of permits refilled per `period`
+    /// * `period` - Time to refill the bucket from empty
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(TokenBucket)` - A full bucket
+    /// * `Err(RateLimitError)` - If `capacity` is 0 or `period` is out of range
+    pub fn new(capacity: u64, period: Duration) -> Result<Self, RateLimitError> {
+        Self::with_clock(capacity, period, *Clock::global())
+    }
+}
+
+impl<C: TimeSource> TokenBucket<C> {
+    /// Creates a token bucket that reads time from `clock`
+    ///
+    /// See [`TokenBucket::new`] for the arguments.
+    pub fn with_clock(capacity: u64, period: Duration, clock: C) -> Result<Self, RateLimitError> {
+        Ok(TokenBucket {
+            gcra: Gcra::new(capacity, period)?,
+            tat: 0,
+            clock,
+        })
+    }
+
+    /// Takes `n` permits if they are all available right now
+    ///
+    /// Returns `false` without taking anything otherwise, including when `n`
+    /// exceeds the capacity.
+    #[inline]
+    pub fn try_acquire(&mut self, n: u64) -> bool {
+        match self.gcra.acquire(self.tat, self.clock.now_nanos(), n) {
+            Some(tat) => {
+                self.tat = tat;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns how long until `n` permits will be available
+    ///
+    /// # Returns
+    ///
+    /// * `Some(Duration::ZERO)` - If `n` permits are available now
+    /// * `Some(wait)` - If `n` permits will be available after `wait`, assuming no other acquisitions
+    /// * `None` - If `n` exceeds the capacity and will never be available
+    pub fn time_until_available(&self, n: u64) -> Option<Duration> {
+        self.gcra.wait(self.tat, self.clock.now_nanos(), n)
+    }
+
+    /// Returns the largest burst this bucket allows
+    pub fn capacity(&self) -> u64 {
+        self.gcra.capacity
+    }
+
+    /// Returns the time to refill the bucket from empty
+    pub fn period(&self) -> Duration {
+        self.gcra.period
+    }
+}
+
+/// Token bucket that can be shared across threads
+///
+/// Behaves like [`TokenBucket`]; acquisitions update its single timestamp
+/// with a compare-and-swap, so they never block.
+#[derive(Debug)]
+pub struct SharedTokenBucket<C = Clock> {
+    tat: CachePadded<AtomicU64>,
+    gcra: Gcra,
+    clock: C,
+}
+
+impl SharedTokenBucket<Clock> {
+    /// Creates a shared token bucket that reads time from [`Clock::global`]
+    ///
+    /// See [`TokenBucket::new`] for the arguments.
+    pub fn new(capacity: u64, period: Duration) -> Result<Self, RateLimitError> {
+        Self::with_clock(capacity, period, *Clock::global())
+    }
+}
+
+impl<C: TimeSource> SharedTokenBucket<C> {
+    /// Creates a shared token bucket that reads time from `clock`
+    ///
+    /// See [`TokenBucket::new`] for the arguments.
+    pub fn with_clock(capacity: u64, period: Duration, clock: C) -> Result<Self, RateLimitError> {
+        Ok(SharedTokenBucket {
+            tat: CachePadded { value: AtomicU64::new(0) },
+            gcra: Gcra::new(capacity, period)?,
+            clock,
+        })
+    }
+
+    /// Takes `n` permits if they are all available right now
+    ///
+    /// See [`TokenBucket::try_acquire`].
+    #[inline]
+    pub fn try_acquire(&self, n: u64) -> bool {
+        let now = self.clock.now_nanos();
+        let mut tat = self.tat.value.load(Ordering::Acquire);
+        loop {
+            let Some(next) = self.gcra.acquire(tat, now, n) else {
+                return false;
+            };
+            match self.tat.value.compare_exchange_weak(tat, next, Ordering::AcqRel, Ordering::Acquire) {
+                Ok(_) => return true,
+                Err(current) => tat = current,
+            }
+        }
+    }
+
+    /// Returns how long until `n` permits will be available
+    ///
+    /// See [`TokenBucket::time_until_available`].
+    pub fn time_until_available(&self, n: u64) -> Option<Duration> {
+        self.gcra.wait(self.tat.value.load(Ordering::Acquire), self.clock.now_nanos(), n)
+    }
+
+    /// Returns the largest burst this bucket allows
+    pub fn capacity(&self) -> u64 {
+        self.gcra.capacity
+    }
+
+    /// Returns the time to refill the bucket from empty
+    pub fn period(&self) -> Duration {
+        self.gcra.period
+    }
+}
+
+/// Sliding-window limiter for a single thread
+///
+/// Allows at most `capacity` permits in any window of length `period`. Each
+/// permit occupies a slot that records when it leaves the window, so memory
+/// grows with the capacity.
+#[derive(Debug)]
+pub struct SlidingWindow<C = Clock> {
+    /// Time each of the last `capacity` permits leaves the window, oldest at `head`
+    expiries: Box<[u64]>,
+    head: usize,
+    period: Duration,
+    period_nanos: u64,
+    clock: C,
+}
+
+impl SlidingWindow<Clock> {
+    /// Creates a sliding-window limiter that reads time from [`Clock::global`]
+    ///
+    /// # Arguments
+    ///
+    /// * `capacity` - Most permits allowed in any window
+    /// * `period` - Length of the window
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(SlidingWindow)` - A limiter with all permits available
+    /// * `Err(RateLimitError)` - If `capacity` is 0 or `period` is out of range
+    pub fn new(capacity: u64, period: Duration) -> Result<Self, RateLimitError> {
+        Self::with_clock(capacity, period, *Clock::global())
+    }
+}
+
+impl<C: TimeSource> SlidingWindow<C> {
+    /// Creates a sliding-window limiter that reads time from `clock`
+    ///
+    /// See [`SlidingWindow::new`] for the arguments.
+    pub fn with_clock(capacity: u64, period: Duration, clock: C) -> Result<Self, RateLimitError> {
+        let period_nanos = period_nanos(capacity, period)?;
+        Ok(SlidingWindow {
+            expiries: vec![0; capacity as usize].into_boxed_slice(),
+            head: 0,
+            period,
+            period_nanos,
+            clock,
+        })
+    }
+
+    /// Takes `n` permits if they are all available right now
+    ///
+    /// Returns `false` without taking anything otherwise, including when `n`
+    /// exceeds the capacity.
+    #[inline]
+    pub fn try_acquire(&mut self, n: u64) -> bool {
+        if n == 0 {
+            return true;
+        }
+        if n > self.capacity() {
+            return false;
+        }
+
+        // Expiries only grow from head onwards, so the n-th oldest permit
+        // leaving the window means the older ones have too
+        let now = self.clock.now_nanos();
+        let len = self.expiries.len();
+        if self.expiries[(self.head + n as usize - 1) % len] > now {
+            return false;
+        }
+        let expiry = now.saturating_add(self.period_nanos);
+        for _ in 0..n {
+            self.expiries[self.head] = expiry;
+            self.head = (self.head + 1) % len;
+        }
+        true
+    }
+
+    /// Returns how long until `n` permits will be available
+    ///
+    /// # Returns
+    ///
+    /// * `Some(Duration::ZERO)` - If `n` permits are available now
+    /// * `Some(wait)` - If `n` permits will be available after `wait`, assuming no other acquisitions
+    /// * `None` - If `n` exceeds the capacity and will never be available
+    pub fn time_until_available(&self, n: u64) -> Option<Duration> {
+        if n == 0 {
+            return Some(Duration::ZERO);
+        }
+        if n > self.capacity() {
+            return None;
+        }
+        let expiry = self.expiries[(self.head + n as usize - 1) % self.expiries.len()];
+        Some(Duration::from_nanos(expiry.saturating_sub(self.clock.now_nanos())))
+    }
+
+    /// Returns the most permits allowed in any window
+    pub fn capacity(&self) -> u64 {
+        self.expiries.len() as u64
+    }
+
+    /// Returns the length of the window
+    pub fn period(&self) -> Duration {
+        self.period
+    }
+}
+
+/// Sliding-window limiter that can be shared across threads
+///
+/// Behaves like [`SlidingWindow`]. Threads claim permits with a
+/// compare-and-swap on a shared counter, then record their expiries and
+/// publish them in claim order. A thread that finds an earlier claim still
+/// unpublished treats those permits as taken, so the limit holds even while
+/// writes are in flight.
+///
+/// # Progress
+///
+/// Claiming is lock-free, but publishing is not. After a successful claim, a
+/// thread spins and then yields until every earlier claim has been
+/// published. A thread preempted between its claim and its publish therefore
+/// stalls every later successful `try_acquire` until it runs again.
+/// Rejections and [`SharedSlidingWindow::time_until_available`] never wait.
+/// Where that matters, use [`SharedTokenBucket`], or give each thread its own
+/// [`SlidingWindow`] with a share of the limit.
+#[derive(Debug)]
+pub struct SharedSlidingWindow<C = Clock> {
+    /// Permits claimed so far
+    claimed: CachePadded<AtomicU64>,
+    /// Permits whose expiries have been written, always at most `claimed`
+    published: CachePadded<AtomicU64>,
+    /// Expiry of permit `p` is in slot `p % capacity`
+    expiries: Box<[AtomicU64]>,
+    period: Duration,
+    period_nanos: u64,
+    clock: C,
+}
+
+impl SharedSlidingWindow<Clock> {
+    /// Creates a shared sliding-window limiter that reads time from [`Clock::global`]
+    ///
+    /// See [`SlidingWindow::new`] for the arguments.
+    pub fn new(capacity: u64, period: Duration) -> Result<Self, RateLimitError> {
+        Self::with_clock(capacity, period, *Clock::global())
+    }
+}
+
+impl<C: TimeSource> SharedSlidingWindow<C> {
+    /// Creates a shared sliding-window limiter that reads time from `clock`
+    ///
+    /// See [`SlidingWindow::new`] for the arguments.
+    pub fn with_clock(capacity: u64, period: Duration, clock: C) -> Result<Self, RateLimitError> {
+        let period_nanos = period_nanos(capacity, period)?;
+        Ok(SharedSlidingWindow {
+            claimed: CachePadded { value: AtomicU64::new(0) },
+            published: CachePadded { value: AtomicU64::new(0) },
+            expiries: (0..capacity).map(|_| AtomicU64::new(0)).collect(),
+            period,
+            period_nanos,
+            clock,
+        })
+    }
+
+    /// Takes `n` permits if they are all available right now
+    ///
+    /// See [`SlidingWindow::try_acquire`].
+    pub fn try_acquire(&self, n: u64) -> bool {
+        if n == 0 {
+            return true;
+        }
+        if n > self.capacity() {
+            return false;
+        }
+
+        let now = self.clock.now_nanos();
+        let mut claimed = self.claimed.value.load(Ordering::Acquire);
+        loop {
+            match self.expiry_before(claimed + n) {
+                Some(expiry) if expiry <= now => {}
+                _ => {
+                    // Only give up if the verdict was about the current claim count
+                    let current = self.claimed.value.load(Ordering::Acquire);
+                    if current == claimed {
+                        return false;
+                    }
+                    claimed = current;
+                    continue;
+                }
+            }
+            match self.claimed.value.compare_exchange_weak(
+                claimed,
+                claimed + n,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => break,
+                Err(current) => claimed = current,
+            }
+        }
+
+        // Publish in claim order so readers never see a gap
+        let mut backoff = Backoff::new();
+        while self.published.value.load(Ordering::Acquire) != claimed {
+            backoff.snooze();
+        }
+
+        // A thread that read the clock earlier may claim later, so never expire
+        // before the permit claimed just ahead: `expiry_before` relies on
+        // expiries rising in claim order
+        let mut expiry = now.saturating_add(self.period_nanos);
+        if let Some(previous) = claimed.checked_sub(1) {
+            expiry = expiry.max(self.slot(previous).load(Ordering::Relaxed));
+        }
+        for permit in claimed..claimed + n {
+            self.slot(permit).store(expiry, Ordering::Relaxed);
+        }
+        self.published.value.store(claimed + n, Ordering::Release);
+        true
+    }
+
+    /// Returns how long until `n` permits will be available
+    ///
+    /// See [`SlidingWindow::time_until_available`]. Permits claimed by another
+    /// thread but not yet recorded count as leaving the window a full period
+    /// from now.
+    pub fn time_until_available(&self, n: u64) -> Option<Duration> {
+        if n == 0 {
+            return Some(Duration::ZERO);
+        }
+        if n > self.capacity() {
+            return None;
+        }
+        let claimed = self.claimed.value.load(Ordering::Acquire);
+        let wait = match self.expiry_before(claimed + n) {
+            Some(expiry) => expiry.saturating_sub(self.clock.now_nanos()),
+            None => self.period_nanos,
+        };
+        Some(Duration::from_nanos(wait))
+    }
+
+    /// Returns the most permits allowed in any window
+    pub fn capacity(&self) -> u64 {
+        self.expiries.len() as u64
+    }
+
+    /// Returns the length of the window
+    pub fn period(&self) -> Duration {
+        self.period
+    }
+
+    /// Returns when the permit `capacity` places before `end` leaves the window
+    ///
+    /// That permit must have left for permit `end - 1` to be taken. Returns
+    /// `Some(0)` if there is no such permit and `None` if it is claimed but
+    /// not yet published.
+    #[inline]
+    fn expiry_before(&self, end: u64) -> Option<u64> {
+        let Some(permit) = end.checked_sub(self.capacity() + 1) else {
+            return Some(0);
+        };
+        if self.published.value.load(Ordering::Acquire) <= permit {
+            return None;
+        }
+        Some(self.slot(permit).load(Ordering::Relaxed))
+    }
+
+    #[inline]
+    fn slot(&self, permit: u64) -> &AtomicU64 {
+        &self.expiries[(permit % self.capacity()) as usize]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::{Arc, Barrier};
+    use std::thread;
+
+    const MS: Duration = Duration::from_millis(1);
+
+    #[test]
+    fn test_token_bucket_refills_evenly() {
+        let clock = ManualClock::new(0);
+        let mut bucket = TokenBucket::with_clock(4, 4 * MS, clock.clone()).unwrap();
+
+        assert!(bucket.try_acquire(3));
+        assert!(!bucket.try_acquire(2));
+        assert_eq!(bucket.time_until_available(2), Some(MS));
+        assert_eq!(bucket.time_until_available(5), None);
+        assert!(!bucket.try_acquire(5));
+
+        clock.advance(MS);
+        assert!(bucket.try_acquire(2));
+        assert!(!bucket.try_acquire(1));
+
+        // Idle time refills up to the capacity and no further
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(bucket.time_until_available(4), Some(Duration::ZERO));
+        assert!(bucket.try_acquire(4));
+        assert!(!bucket.try_acquire(1));
+    }
+
+    #[test]
+    fn test_sliding_window_is_exact() {
+        let clock = ManualClock::new(0);
+        let mut window = SlidingWindow::with_clock(3, 10 * MS, clock.clone()).unwrap();
+
+        assert!(window.try_acquire(1));
+        clock.advance(4 * MS);
+        assert!(window.try_acquire(2));
+        assert!(!window.try_acquire(1));
+        assert_eq!(window.time_until_available(1), Some(6 * MS));
+        assert_eq!(window.time_until_available(2), Some(10 * MS));
+
+        // The first permit leaves the window, the later two are still in it
+        clock.advance(6 * MS);
+        assert!(!window.try_acquire(2));
+        assert!(window.try_acquire(1));
+        assert!(!window.try_acquire(1));
+    }
+
+    #[test]
+    fn test_invalid_limits() {
+        assert_eq!(TokenBucket::new(0, MS).unwrap_err(), RateLimitError::InvalidCapacity(0));
+        assert_eq!(
+            SlidingWindow::new(1, Duration::ZERO).unwrap_err(),
+            RateLimitError::InvalidPeriod(Duration::ZERO)
+        );
+        assert!(SharedTokenBucket::new(1, Duration::MAX).is_err());
+        assert!(SharedSlidingWindow::new(0, MS).is_err());
+    }
+
+    #[test]
+    fn test_shared_limiters_never_exceed_capacity() {
+        let clock = ManualClock::new(0);
+        let bucket = Arc::new(SharedTokenBucket::with_clock(100, MS, clock.clone()).unwrap());
+        let window = Arc::new(SharedSlidingWindow::with_clock(100, MS, clock.clone()).unwrap());
+
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let bucket = Arc::clone(&bucket);
+                let window = Arc::clone(&window);
+                thread::spawn(move || {
+                    let mut granted = (0, 0);
+                    for _ in 0..100 {
+                        granted.0 += bucket.try_acquire(1) as u64;
+                        granted.1 += window.try_acquire(1) as u64;
+                    }
+                    granted
+                })
+            })
+            .collect();
+
+        let (mut from_bucket, mut from_window) = (0, 0);
+        for handle in handles {
+            let (bucket_count, window_count) = handle.join().unwrap();
+            from_bucket += bucket_count;
+            from_window += window_count;
+        }
+        assert_eq!(from_bucket, 100);
+        assert_eq!(from_window, 100);
+        assert_eq!(window.time_until_available(1), Some(MS));
+
+        clock.advance(MS);
+        assert!(bucket.try_acquire(100));
+        assert!(window.try_acquire(100));
+    }
+
+    /// A clock whose first reader stalls after reading, until released
+    struct StallingClock {
+        clock: ManualClock,
+        stall: AtomicBool,
+        read: Barrier,
+        resume: Barrier,
+    }
+
+    impl TimeSource for StallingClock {
+        fn now_nanos(&self) -> u64 {
+            let now = self.clock.now_nanos();
+            if self.stall.swap(false, Ordering::AcqRel) {
+                self.read.wait();
+                self.resume.wait();
+            }
+            now
+        }
+    }
+
+    #[test]
+    fn test_shared_window_late_claim_keeps_expiry_order() {
+        let clock = ManualClock::new(0);
+        let stalling = StallingClock {
+            clock: clock.clone(),
+            stall: AtomicBool::new(true),
+            read: Barrier::new(2),
+            resume: Barrier::new(2),
+        };
+        let window = Arc::new(SharedSlidingWindow::with_clock(2, 10 * MS, stalling).unwrap());
+
+        // Reads the clock at 0 but only claims after the main thread's acquire at 5
+        let late = thread::spawn({
+            let window = Arc::clone(&window);
+            move || window.try_acquire(1)
+        });
+        window.clock.read.wait();
+        clock.advance(5 * MS);
+        assert!(window.try_acquire(1));
+        window.clock.resume.wait();
+        assert!(late.join().unwrap());
+
+        // The permit taken at 5 is still in the window, so two more would breach it
+        clock.advance(7 * MS);
+        assert!(!window.try_acquire(2));
+        clock.advance(3 * MS);
+        assert!(window.try_acquire(2));
+    }
+}
@@ -1,9 +1,25 @@
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
-use std::cell::UnsafeCell;
+use std::cell::{Cell, UnsafeCell};
 use std::mem::MaybeUninit;
 use std::error::Error;
 use std::fmt;
+use std::panic::{RefUnwindSafe, UnwindSafe};
+use std::ptr;
+#[cfg(target_os = "linux")]
+use std::io;
+#[cfg(target_os = "linux")]
+use std::ops::{Deref, DerefMut};
+#[cfg(target_os = "linux")]
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd, RawFd};
+#[cfg(target_os = "linux")]
+use std::sync::atomic::{fence, AtomicBool};
+
+use crate::cache_padded::CachePadded;
+use crate::clock::Clock;
+use crate::histogram::Histogram;
+#[cfg(target_os = "linux")]
+use crate::notify::Notifier;
 
 /// Error types for ring buffer operations
 #[derive(Debug, Clone, PartialEq)]
@@ -49,10 +65,20 @@ impl Error for RingBufferError {}
 /// Designed to achieve ≥20M operations per second on modern hardware.
 /// Uses cache-line alignment and relaxed atomics to minimize contention.
 /// 
+/// # Panic Safety
+/// 
+/// An index is only published after the slots it covers have been written or
+/// moved out, and user code (iterators, closures and `T::drop`) only runs after
+/// the slot it concerns has been taken out of the buffer. A panic in user code
+/// therefore never leaves a half-initialized slot visible or a moved-out slot
+/// behind the consumer, and no item is ever dropped twice. Items still in the
+/// buffer when both halves are gone are dropped with the buffer; if one of those
+/// drops panics, the remaining items are still dropped.
+/// 
 /// # Example
 /// 
 /// ```
-/// use core::ring_buffer::RingBuffer;
+/// use ferrite_core::ring_buffer::RingBuffer;
 /// 
 /// // Create a buffer with capacity 1024
 /// let buffer = RingBuffer::<u32>::new(1024).unwrap();
@@ -84,23 +110,30 @@ pub struct RingBuffer<T> {
     buffer: Box<[UnsafeCell<MaybeUninit<T>>]>,
     /// Capacity minus one, used as a bitmask for wrapping
     mask: usize,
-    /// Shared state between producer and consumer
-    shared: Arc<SharedState>,
+    /// Optional readiness notifier signalled when a parked consumer has work
+    #[cfg(target_os = "linux")]
+    notifier: Option<Notifier>,
+    /// Stamp one push in this many with its enqueue time; 0 disables sampling
+    sample_every: u32,
 }
 
 /// Shared state with cache-line padding to avoid false sharing
 #[repr(C)]
-struct SharedState {
+struct SharedState<T> {
     /// Producer write position
     head: CachePadded<AtomicUsize>,
     /// Consumer read position  
     tail: CachePadded<AtomicUsize>,
-}
-
-/// Cache-line padding wrapper to avoid false sharing
-#[repr(align(64))]
-struct CachePadded<T> {
-    value: T,
+    /// Set by the consumer before it blocks on the notifier
+    #[cfg(target_os = "linux")]
+    parked: CachePadded<AtomicBool>,
+    #[cfg(target_os = "linux")]
+    notifier: Option<Notifier>,
+    /// Slot storage, released once both halves have been dropped
+    buffer: Box<[UnsafeCell<MaybeUninit<T>>]>,
+    /// Enqueue time per slot in [`Clock`] ticks, 0 if not sampled.
+    /// Empty unless latency sampling is enabled.
+    timestamps: Box<[UnsafeCell<u64>]>,
 }
 
 impl<T> RingBuffer<T> {
@@ -118,6 +151,7 @@ impl<T> RingBuffer<T> {
     /// # Example
     /// 
     /// ```
+    /// # use ferrite_core::ring_buffer::RingBuffer;
     /// let buffer = RingBuffer::<u32>::new(1024).unwrap();
     /// ```
     pub fn new(capacity: usize) -> Result<Self, RingBufferError> {
@@ -133,13 +167,86 @@ impl<T> RingBuffer<T> {
         Ok(RingBuffer {
             buffer: buffer.into_boxed_slice(),
             mask: capacity - 1,
-            shared: Arc::new(SharedState {
-                head: CachePadded { value: AtomicUsize::new(0) },
-                tail: CachePadded { value: AtomicUsize::new(0) },
-            }),
+            #[cfg(target_os = "linux")]
+            notifier: None,
+            sample_every: 0,
         })
     }
 
+    /// Enables queue-latency sampling
+    /// 
+    /// Every `sample_every`-th push records its enqueue time in a side array next
+    /// to the slot, and the consumer records how long that item waited into a
+    /// [`Histogram`] of nanoseconds, available from [`Consumer::latency`].
+    /// Unsampled pushes only write a zero, so a large `sample_every` keeps the
+    /// overhead low enough to leave enabled in production.
+    /// 
+    /// Timestamps are TSC reads from [`Clock::global`], which is calibrated
+    /// here if it has not been already, keeping that off the first push.
+    /// 
+    /// # Panics
+    /// 
+    /// Panics if `sample_every` is 0.
+    /// 
+    /// # Example
+    /// 
+    /// ```
+    /// # use ferrite_core::ring_buffer::RingBuffer;
+    /// let buffer = RingBuffer::<u32>::new(1024).unwrap().with_latency_sampling(64);
+    /// let (mut producer, mut consumer) = buffer.split();
+    /// 
+    /// for i in 0..128 {
+    ///     producer.push(i).unwrap();
+    ///     consumer.pop().unwrap();
+    /// }
+    /// 
+    /// let latency = consumer.latency().unwrap();
+    /// assert_eq!(latency.count(), 2);
+    /// println!("p99 dwell time: {} ns", latency.percentile(99.0));
+    /// ```
+    pub fn with_latency_sampling(mut self, sample_every: u32) -> Self {
+        assert!(sample_every > 0, "sample_every must be greater than 0");
+        Clock::global();
+        self.sample_every = sample_every;
+        self
+    }
+
+    /// Attaches an `eventfd` notifier for integration with `epoll` and other reactors
+    /// 
+    /// The returned buffer splits into a [`NotifyingConsumer`], which can park
+    /// itself with [`NotifyingConsumer::arm_notifier`] and implements `AsRawFd`
+    /// and `AsFd` so it can be registered alongside sockets.
+    /// The producer only writes to the eventfd when it publishes into a ring whose
+    /// consumer is parked, so a busy consumer costs the producer no system calls.
+    /// 
+    /// # Returns
+    /// 
+    /// * `Ok(NotifyingRingBuffer<T>)` - The ring buffer with a notifier attached
+    /// * `Err(io::Error)` - If the eventfd could not be created
+    /// 
+    /// # Example
+    /// 
+    /// ```
+    /// # use ferrite_core::ring_buffer::RingBuffer;
+    /// use std::os::fd::AsRawFd;
+    /// 
+    /// let buffer = RingBuffer::<u32>::new(1024).unwrap().with_notifier().unwrap();
+    /// let (mut producer, mut consumer) = buffer.split();
+    /// 
+    /// // Register `consumer.as_raw_fd()` with epoll for EPOLLIN, then:
+    /// let fd = consumer.as_raw_fd();
+    /// if consumer.arm_notifier() {
+    ///     // Nothing to pop: safe to block in epoll_wait until `fd` is readable
+    /// }
+    /// producer.push(7).unwrap(); // `fd` is now readable
+    /// # let _ = fd;
+    /// ```
+    #[cfg(target_os = "linux")]
+    pub fn with_notifier(mut self) -> io::Result<NotifyingRingBuffer<T>> {
+        self.notifier = Some(Notifier::new()?);
+        Ok(NotifyingRingBuffer { ring: self })
+    }
+
     /// Returns the capacity of the ring buffer
     pub fn capacity(&self) -> usize {
         self.buffer.len()
@@ -153,27 +260,47 @@ impl<T> RingBuffer<T> {
     /// # Example
     /// 
     /// ```
+    /// # use ferrite_core::ring_buffer::RingBuffer;
     /// let buffer = RingBuffer::<u32>::new(1024).unwrap();
     /// let (producer, consumer) = buffer.split();
     /// ```
     pub fn split(self) -> (Producer<T>, Consumer<T>) {
-        let buffer_ptr = Box::into_raw(self.buffer) as *mut UnsafeCell<MaybeUninit<T>>;
         let capacity = self.mask + 1;
+        let timestamps = if self.sample_every > 0 {
+            (0..capacity).map(|_| UnsafeCell::new(0)).collect()
+        } else {
+            Box::default()
+        };
+
+        let shared = Arc::new(SharedState {
+            head: CachePadded { value: AtomicUsize::new(0) },
+            tail: CachePadded { value: AtomicUsize::new(0) },
+            #[cfg(target_os = "linux")]
+            parked: CachePadded { value: AtomicBool::new(false) },
+            #[cfg(target_os = "linux")]
+            notifier: self.notifier,
+            buffer: self.buffer,
+            timestamps,
+        });
+        let buffer_ptr = shared.buffer.as_ptr() as *mut UnsafeCell<MaybeUninit<T>>;
         
         let producer = Producer {
             buffer: buffer_ptr,
             mask: self.mask,
             capacity,
-            shared: self.shared.clone(),
+            shared: shared.clone(),
             cached_tail: 0,
+            sample_every: self.sample_every,
+            sample_countdown: self.sample_every,
         };
 
         let consumer = Consumer {
             buffer: buffer_ptr,
             mask: self.mask,
             capacity,
-            shared: self.shared,
+            shared,
             cached_head: 0,
+            latency: (self.sample_every > 0).then(Histogram::new),
         };
 
         (producer, consumer)
@@ -185,8 +312,11 @@ pub struct Producer<T> {
     buffer: *mut UnsafeCell<MaybeUninit<T>>,
     mask: usize,
     capacity: usize,
-    shared: Arc<SharedState>,
+    shared: Arc<SharedState<T>>,
     cached_tail: usize,
+    sample_every: u32,
+    /// Pushes left until the next sampled one
+    sample_countdown: u32,
 }
 
 /// Consumer half of the ring buffer
@@ -194,13 +324,23 @@ pub struct Consumer<T> {
     buffer: *mut UnsafeCell<MaybeUninit<T>>,
     mask: usize,
     capacity: usize,
-    shared: Arc<SharedState>,
+    shared: Arc<SharedState<T>>,
     cached_head: usize,
+    /// Dwell times of sampled items, present when latency sampling is enabled
+    latency: Option<Histogram>,
 }
 
 unsafe impl<T: Send> Send for Producer<T> {}
 unsafe impl<T: Send> Send for Consumer<T> {}
 
+// The raw slot pointer and `UnsafeCell`s opt the halves out of the unwind-safety
+// auto traits. Neither half can be observed in a broken state after a panic (see
+// "Panic Safety" on `RingBuffer`), so they are as unwind safe as the items.
+impl<T: UnwindSafe> UnwindSafe for Producer<T> {}
+impl<T: RefUnwindSafe> RefUnwindSafe for Producer<T> {}
+impl<T: UnwindSafe> UnwindSafe for Consumer<T> {}
+impl<T: RefUnwindSafe> RefUnwindSafe for Consumer<T> {}
+
 impl<T> Producer<T> {
     /// Attempts to push an item into the buffer
     /// 
@@ -219,15 +359,110 @@ impl<T> Producer<T> {
             }
         }
 
+        // The slot at `head` is outside the published range, so the consumer
+        // cannot touch it until the store below.
         unsafe {
             let slot = &mut *(*self.buffer.add(head)).get();
             slot.write(value);
         }
 
-        self.shared.head.value.store(next_head, Ordering::Release);
+        if self.sample_every != 0 {
+            self.stamp_slot(head);
+        }
+
+        self.publish(next_head);
         Ok(())
     }
 
+    /// Pushes items from an iterator until it is exhausted or the buffer is full
+    /// 
+    /// The items are published to the consumer with a single index store. The
+    /// iterator is only advanced while there is room, so an item is never pulled
+    /// and then lost to a full buffer; pass `iter.by_ref()` to keep the rest.
+    /// 
+    /// If the iterator panics, every item it produced before the panic is
+    /// published and the buffer stays consistent.
+    /// 
+    /// # Returns
+    /// 
+    /// The number of items pushed
+    /// 
+    /// # Example
+    /// 
+    /// ```
+    /// # use ferrite_core::ring_buffer::RingBuffer;
+    /// let buffer = RingBuffer::<u32>::new(4).unwrap();
+    /// let (mut producer, consumer) = buffer.split();
+    /// 
+    /// let mut items = 0..10;
+    /// assert_eq!(producer.push_iter(items.by_ref()), 3);
+    /// assert_eq!(items.next(), Some(3));
+    /// assert_eq!(consumer.len(), 3);
+    /// ```
+    pub fn push_iter<I: IntoIterator<Item = T>>(&mut self, iter: I) -> usize {
+        let head = self.shared.head.value.load(Ordering::Relaxed);
+        self.cached_tail = self.shared.tail.value.load(Ordering::Acquire);
+        let free = self.cached_tail.wrapping_sub(head).wrapping_sub(1) & self.mask;
+
+        let mut batch = PublishGuard {
+            producer: self,
+            head,
+            written: 0,
+        };
+
+        for value in iter.into_iter().take(free) {
+            let index = (batch.head + batch.written) & batch.producer.mask;
+            // `index` is one of the `free` slots the consumer cannot reach yet
+            unsafe {
+                let slot = &mut *(*batch.producer.buffer.add(index)).get();
+                slot.write(value);
+            }
+            if batch.producer.sample_every != 0 {
+                batch.producer.stamp_slot(index);
+            }
+            batch.written += 1;
+        }
+
+        batch.written
+    }
+
+    /// Makes every slot before `next_head` visible to the consumer
+    #[inline]
+    fn publish(&self, next_head: usize) {
+        self.shared.head.value.store(next_head, Ordering::Release);
+
+        #[cfg(target_os = "linux")]
+        self.wake_consumer();
+    }
+
+    /// Writes the enqueue time for sampled pushes and clears it for the rest
+    #[inline]
+    fn stamp_slot(&mut self, index: usize) {
+        self.sample_countdown -= 1;
+        let stamp = if self.sample_countdown == 0 {
+            self.sample_countdown = self.sample_every;
+            Clock::global().ticks().max(1)
+        } else {
+            0
+        };
+        unsafe { *self.shared.timestamps[index].get() = stamp };
+    }
+
+    /// Signals the notifier if the consumer parked itself before this publish
+    #[cfg(target_os = "linux")]
+    #[inline]
+    fn wake_consumer(&self) {
+        if let Some(notifier) = &self.shared.notifier {
+            // Pairs with the fence in `NotifyingConsumer::arm_notifier`: either the consumer
+            // observes the new head, or we observe its parked flag.
+            fence(Ordering::SeqCst);
+            let parked = &self.shared.parked.value;
+            if parked.load(Ordering::Relaxed) && parked.swap(false, Ordering::Relaxed) {
+                let _ = notifier.notify();
+            }
+        }
+    }
+
     /// Returns the number of items that can be pushed without blocking
     pub fn remaining_capacity(&self) -> usize {
         let head = self.shared.head.value.load(Ordering::Relaxed);
@@ -263,17 +498,74 @@ impl<T> Consumer<T> {
             }
         }
 
+        // `tail` is inside the published range and is moved out exactly once:
+        // nothing runs between the read and the store that releases the slot.
         let value = unsafe {
             let slot = &mut *(*self.buffer.add(tail)).get();
             slot.assume_init_read()
         };
 
+        if self.latency.is_some() {
+            self.record_dwell(tail);
+        }
+
         let next_tail = (tail + 1) & self.mask;
         self.shared.tail.value.store(next_tail, Ordering::Release);
 
         Ok(value)
     }
 
+    /// Pops up to `max` items, handing each to `f` in order
+    /// 
+    /// The consumed slots are released to the producer with a single index store
+    /// once the batch is done, instead of one store per item.
+    /// 
+    /// If `f` panics, the item it was given and every item before it count as
+    /// consumed; the rest stay in the buffer for the next pop.
+    /// 
+    /// # Returns
+    /// 
+    /// The number of items handed to `f`
+    /// 
+    /// # Example
+    /// 
+    /// ```
+    /// # use ferrite_core::ring_buffer::RingBuffer;
+    /// let buffer = RingBuffer::<u32>::new(8).unwrap();
+    /// let (mut producer, mut consumer) = buffer.split();
+    /// producer.push_iter(1..=5);
+    /// 
+    /// let mut sum = 0;
+    /// assert_eq!(consumer.pop_batch(3, |value| sum += value), 3);
+    /// assert_eq!(sum, 6);
+    /// assert_eq!(consumer.len(), 2);
+    /// ```
+    pub fn pop_batch<F: FnMut(T)>(&mut self, max: usize, mut f: F) -> usize {
+        let tail = self.shared.tail.value.load(Ordering::Relaxed);
+        self.cached_head = self.shared.head.value.load(Ordering::Acquire);
+        let count = (self.cached_head.wrapping_sub(tail) & self.mask).min(max);
+
+        let mut batch = ReleaseGuard {
+            consumer: self,
+            tail,
+            read: 0,
+        };
+
+        while batch.read < count {
+            let index = (tail + batch.read) & batch.consumer.mask;
+            // Published and not yet moved out; `read` is bumped before `f` runs
+            // so the guard releases this slot even if `f` panics.
+            let value = unsafe { (*(*batch.consumer.buffer.add(index)).get()).assume_init_read() };
+            if batch.consumer.latency.is_some() {
+                batch.consumer.record_dwell(index);
+            }
+            batch.read += 1;
+            f(value);
+        }
+
+        count
+    }
+
     /// Returns the number of items available to pop
     pub fn len(&self) -> usize {
         let head = self.shared.head.value.load(Ordering::Acquire);
@@ -290,21 +582,343 @@ impl<T> Consumer<T> {
     pub fn is_empty(&self) -> bool {
         self.len() == 0
     }
+
+    /// Starts a transactional read over the buffered items
+    /// 
+    /// Items popped through the returned [`Transaction`] are borrowed in place and
+    /// the consumer position is not published until [`Transaction::commit`]. If the
+    /// transaction is aborted or dropped, the same items are seen again by the next
+    /// `pop` or transaction, which makes it safe to give up half way through a batch.
+    /// 
+    /// While a transaction is open the producer cannot reuse the slots it has
+    /// popped, so long-running transactions reduce the usable capacity.
+    /// 
+    /// # Example
+    /// 
+    /// ```
+    /// # use ferrite_core::ring_buffer::RingBuffer;
+    /// let buffer = RingBuffer::<String>::new(8).unwrap();
+    /// let (mut producer, mut consumer) = buffer.split();
+    /// producer.push("header".to_string()).unwrap();
+    /// producer.push("bad body".to_string()).unwrap();
+    /// 
+    /// let txn = consumer.begin();
+    /// let header = txn.pop().unwrap();
+    /// let body = txn.pop().unwrap();
+    /// if body.starts_with("bad") {
+    ///     txn.abort(); // both items stay in the buffer
+    /// } else {
+    ///     let _ = (header, body);
+    ///     txn.commit();
+    /// }
+    /// 
+    /// assert_eq!(consumer.pop().unwrap(), "header");
+    /// ```
+    pub fn begin(&mut self) -> Transaction<'_, T> {
+        let tail = self.shared.tail.value.load(Ordering::Relaxed);
+        let cached_head = self.cached_head;
+
+        Transaction {
+            consumer: self,
+            start: tail,
+            cursor: Cell::new(tail),
+            cached_head: Cell::new(cached_head),
+        }
+    }
+
+    /// Returns the dwell-time histogram in nanoseconds
+    /// 
+    /// Returns `None` unless the ring buffer was created with
+    /// [`RingBuffer::with_latency_sampling`].
+    pub fn latency(&self) -> Option<&Histogram> {
+        self.latency.as_ref()
+    }
+
+    /// Clears the dwell-time histogram, e.g. after exporting a reporting interval
+    pub fn reset_latency(&mut self) {
+        if let Some(latency) = &mut self.latency {
+            latency.reset();
+        }
+    }
+
+    /// Records how long the item in `index` waited, if its push was sampled
+    #[inline]
+    fn record_dwell(&mut self, index: usize) {
+        let stamp = unsafe { *self.shared.timestamps[index].get() };
+        if stamp != 0 {
+            let clock = Clock::global();
+            let dwell = clock.ticks_to_nanos(clock.ticks().saturating_sub(stamp));
+            if let Some(latency) = &mut self.latency {
+                latency.record(dwell);
+            }
+        }
+    }
+
+}
+
+/// A [`RingBuffer`] with an `eventfd` notifier, created by [`RingBuffer::with_notifier`]
+#[cfg(target_os = "linux")]
+pub struct NotifyingRingBuffer<T> {
+    ring: RingBuffer<T>,
+}
+
+#[cfg(target_os = "linux")]
+impl<T> NotifyingRingBuffer<T> {
+    /// Returns the capacity of the ring buffer
+    pub fn capacity(&self) -> usize {
+        self.ring.capacity()
+    }
+
+    /// Splits the ring buffer into a producer and a notifier-bearing consumer
+    pub fn split(self) -> (Producer<T>, NotifyingConsumer<T>) {
+        let (producer, consumer) = self.ring.split();
+        (producer, NotifyingConsumer { consumer })
+    }
+}
+
+/// Consumer half of a ring buffer with a notifier
+/// 
+/// Dereferences to [`Consumer`] for popping, and exposes the notifier's eventfd
+/// through `AsRawFd` and `AsFd` for registration with `epoll` or any reactor.
+#[cfg(target_os = "linux")]
+pub struct NotifyingConsumer<T> {
+    consumer: Consumer<T>,
+}
+
+#[cfg(target_os = "linux")]
+impl<T> NotifyingConsumer<T> {
+    fn notifier(&self) -> &Notifier {
+        match &self.consumer.shared.notifier {
+            Some(notifier) => notifier,
+            None => unreachable!("NotifyingRingBuffer always attaches a notifier"),
+        }
+    }
+
+    /// Parks the consumer so the next push signals the notifier
+    /// 
+    /// Call this when [`Consumer::pop`] reports an empty buffer and the thread is
+    /// about to block on the notifier's file descriptor. Any stale notification is
+    /// cleared first, so the descriptor only becomes readable for pushes that happen
+    /// after this call. The consumer is unparked by the producer's next push.
+    /// 
+    /// # Returns
+    /// 
+    /// * `true` - The buffer is empty and the caller may block on the file descriptor
+    /// * `false` - Items are already available; pop them instead of blocking
+    pub fn arm_notifier(&mut self) -> bool {
+        let _ = self.notifier().drain();
+
+        let consumer = &mut self.consumer;
+        let parked = &consumer.shared.parked.value;
+        parked.store(true, Ordering::Relaxed);
+        // Pairs with the fence in `Producer::wake_consumer`
+        fence(Ordering::SeqCst);
+
+        let tail = consumer.shared.tail.value.load(Ordering::Relaxed);
+        consumer.cached_head = consumer.shared.head.value.load(Ordering::Acquire);
+        if tail != consumer.cached_head {
+            parked.store(false, Ordering::Relaxed);
+            return false;
+        }
+        true
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl<T> Deref for NotifyingConsumer<T> {
+    type Target = Consumer<T>;
+
+    fn deref(&self) -> &Consumer<T> {
+        &self.consumer
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl<T> DerefMut for NotifyingConsumer<T> {
+    fn deref_mut(&mut self) -> &mut Consumer<T> {
+        &mut self.consumer
+    }
+}
+
+/// Exposes the notifier's eventfd for registration with `epoll` or any reactor
+#[cfg(target_os = "linux")]
+impl<T> AsFd for NotifyingConsumer<T> {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.notifier().as_fd()
+    }
 }
 
-impl<T> Drop for Producer<T> {
+#[cfg(target_os = "linux")]
+impl<T> AsRawFd for NotifyingConsumer<T> {
+    fn as_raw_fd(&self) -> RawFd {
+        self.notifier().as_raw_fd()
+    }
+}
+
+
+/// Transactional read guard created by [`Consumer::begin`]
+/// 
+/// Popped items are only borrowed; they are released to the producer when the
+/// transaction is committed. Dropping the guard without committing is the same
+/// as calling [`Transaction::abort`].
+pub struct Transaction<'a, T> {
+    consumer: &'a mut Consumer<T>,
+    /// Consumer position when the transaction began
+    start: usize,
+    /// Position of the next item to pop within the transaction
+    cursor: Cell<usize>,
+    cached_head: Cell<usize>,
+}
+
+impl<'a, T> Transaction<'a, T> {
+    /// Borrows the next item without releasing it to the producer
+    /// 
+    /// References returned by earlier calls remain valid for the lifetime of
+    /// the transaction, so a whole batch can be inspected before deciding.
+    /// 
+    /// # Returns
+    /// 
+    /// * `Ok(&T)` - The next item in the buffer
+    /// * `Err(RingBufferError::BufferEmpty)` - No more items are available
+    pub fn pop(&self) -> Result<&T, RingBufferError> {
+        let cursor = self.cursor.get();
+
+        if cursor == self.cached_head.get() {
+            let head = self.consumer.shared.head.value.load(Ordering::Acquire);
+            self.cached_head.set(head);
+            if cursor == head {
+                return Err(RingBufferError::BufferEmpty);
+            }
+        }
+
+        // The slot stays initialized until the tail moves past it, which only
+        // `commit_with` does, and it consumes the transaction.
+        let value = unsafe { (*(*self.consumer.buffer.add(cursor)).get()).assume_init_ref() };
+
+        self.cursor.set((cursor + 1) & self.consumer.mask);
+        Ok(value)
+    }
+
+    /// Returns the number of items popped so far in this transaction
+    pub fn popped(&self) -> usize {
+        self.cursor.get().wrapping_sub(self.start) & self.consumer.mask
+    }
+
+    /// Drops the popped items and releases their slots to the producer
+    pub fn commit(self) {
+        self.commit_with(drop);
+    }
+
+    /// Moves the popped items out in order and releases their slots to the producer
+    /// 
+    /// Use this when the items need to outlive the transaction.
+    /// 
+    /// # Example
+    /// 
+    /// ```
+    /// # use ferrite_core::ring_buffer::RingBuffer;
+    /// let buffer = RingBuffer::<Vec<u8>>::new(8).unwrap();
+    /// let (mut producer, mut consumer) = buffer.split();
+    /// producer.push(vec![1, 2]).unwrap();
+    /// 
+    /// let txn = consumer.begin();
+    /// assert_eq!(txn.pop().unwrap().len(), 2);
+    /// 
+    /// let mut batch = Vec::new();
+    /// txn.commit_with(|item| batch.push(item));
+    /// assert_eq!(batch, vec![vec![1, 2]]);
+    /// ```
+    /// 
+    /// If `f` (or `T::drop`, for [`Transaction::commit`]) panics, the item it was
+    /// given and every item before it count as consumed; the rest stay in the buffer.
+    pub fn commit_with<F: FnMut(T)>(self, mut f: F) {
+        let count = self.popped();
+        self.consumer.cached_head = self.cached_head.get();
+
+        let mut batch = ReleaseGuard {
+            consumer: &mut *self.consumer,
+            tail: self.start,
+            read: 0,
+        };
+
+        while batch.read < count {
+            let index = (self.start + batch.read) & batch.consumer.mask;
+            // Popped by this transaction, so published and still initialized
+            let value = unsafe { (*(*batch.consumer.buffer.add(index)).get()).assume_init_read() };
+            if batch.consumer.latency.is_some() {
+                batch.consumer.record_dwell(index);
+            }
+            batch.read += 1;
+            f(value);
+        }
+    }
+
+    /// Abandons the transaction, leaving every popped item in the buffer
+    pub fn abort(self) {}
+}
+
+/// Publishes the slots written by a batch push, including when the source panics
+struct PublishGuard<'a, T> {
+    producer: &'a mut Producer<T>,
+    head: usize,
+    written: usize,
+}
+
+impl<T> Drop for PublishGuard<'_, T> {
     fn drop(&mut self) {
-        // Producer is responsible for cleaning up the buffer
+        if self.written > 0 {
+            let next_head = (self.head + self.written) & self.producer.mask;
+            self.producer.publish(next_head);
+        }
+    }
+}
+
+/// Releases the slots moved out by a batch pop, including when a callback panics
+struct ReleaseGuard<'a, T> {
+    consumer: &'a mut Consumer<T>,
+    tail: usize,
+    read: usize,
+}
+
+impl<T> Drop for ReleaseGuard<'_, T> {
+    fn drop(&mut self) {
+        if self.read > 0 {
+            let next_tail = (self.tail + self.read) & self.consumer.mask;
+            self.consumer.shared.tail.value.store(next_tail, Ordering::Release);
+        }
+    }
+}
+
+/// Drops the initialized items in a run of slots
+struct DropSlots<'a, T>(&'a mut [UnsafeCell<MaybeUninit<T>>]);
+
+impl<T> Drop for DropSlots<'_, T> {
+    fn drop(&mut self) {
+        // `UnsafeCell<MaybeUninit<T>>` has the same layout as `T`. Dropping a
+        // slice keeps dropping the remaining items if one of them panics.
         unsafe {
-            let buffer = std::slice::from_raw_parts_mut(self.buffer, self.capacity);
-            let _ = Box::from_raw(buffer);
+            let items = ptr::slice_from_raw_parts_mut(self.0.as_mut_ptr() as *mut T, self.0.len());
+            ptr::drop_in_place(items);
         }
     }
 }
 
-impl<T> Drop for Consumer<T> {
+impl<T> Drop for SharedState<T> {
     fn drop(&mut self) {
-        // Consumer doesn't own the buffer, so nothing to do
+        // Both halves are gone, so the published range is final
+        let head = *self.head.value.get_mut();
+        let tail = *self.tail.value.get_mut();
+
+        let (front, back) = if tail <= head {
+            (&mut self.buffer[tail..head], &mut [][..])
+        } else {
+            let (wrapped, front) = self.buffer.split_at_mut(tail);
+            (front, &mut wrapped[..head])
+        };
+
+        // Declared first so it still runs if dropping the front run panics
+        let _back = DropSlots(back);
+        drop(DropSlots(front));
     }
 }
 
@@ -403,4 +1017,196 @@ mod tests {
             }
         }
     }
-}
\ No newline at end of file
+
+    #[cfg(target_os = "linux")]
+    fn fd_readable(consumer: &NotifyingConsumer<u32>) -> bool {
+        let fd = consumer.as_raw_fd();
+        let mut pfd = libc::pollfd { fd, events: libc::POLLIN, revents: 0 };
+        unsafe { libc::poll(&mut pfd, 1, 0) == 1 }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_notifier_wakes_parked_consumer() {
+        let buffer = RingBuffer::<u32>::new(16).unwrap().with_notifier().unwrap();
+        let (mut producer, mut consumer) = buffer.split();
+
+        assert!(consumer.arm_notifier());
+        assert!(!fd_readable(&consumer));
+
+        producer.push(1).unwrap();
+        assert!(fd_readable(&consumer));
+        assert_eq!(consumer.pop(), Ok(1));
+
+        // The first push unparked the consumer, so later pushes stay silent
+        assert!(consumer.arm_notifier());
+        producer.push(2).unwrap();
+        producer.push(3).unwrap();
+        assert!(fd_readable(&consumer));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_notifier_suppressed_while_busy() {
+        let buffer = RingBuffer::<u32>::new(16).unwrap().with_notifier().unwrap();
+        let (mut producer, mut consumer) = buffer.split();
+
+        producer.push(1).unwrap();
+        assert!(!fd_readable(&consumer));
+
+        // Items are pending, so the consumer must not park
+        assert!(!consumer.arm_notifier());
+        producer.push(2).unwrap();
+        assert!(!fd_readable(&consumer));
+        assert_eq!(consumer.pop(), Ok(1));
+        assert_eq!(consumer.pop(), Ok(2));
+    }
+
+    #[test]
+    fn test_transaction_commit() {
+        let buffer = RingBuffer::<u32>::new(8).unwrap();
+        let (mut producer, mut consumer) = buffer.split();
+        for i in 0..5 {
+            producer.push(i).unwrap();
+        }
+
+        let txn = consumer.begin();
+        let first = txn.pop().unwrap();
+        let second = txn.pop().unwrap();
+        assert_eq!((*first, *second), (0, 1));
+        assert_eq!(txn.popped(), 2);
+        txn.commit();
+
+        assert_eq!(consumer.len(), 3);
+        assert_eq!(producer.remaining_capacity(), 4);
+        assert_eq!(consumer.pop(), Ok(2));
+    }
+
+    #[test]
+    fn test_transaction_abort_and_drop_restore() {
+        let buffer = RingBuffer::<u32>::new(8).unwrap();
+        let (mut producer, mut consumer) = buffer.split();
+        producer.push(1).unwrap();
+        producer.push(2).unwrap();
+
+        let txn = consumer.begin();
+        assert_eq!(txn.pop(), Ok(&1));
+        assert_eq!(txn.pop(), Ok(&2));
+        assert_eq!(txn.pop(), Err(RingBufferError::BufferEmpty));
+        txn.abort();
+        assert_eq!(consumer.len(), 2);
+
+        {
+            let txn = consumer.begin();
+            assert_eq!(txn.pop(), Ok(&1));
+        }
+        assert_eq!(consumer.pop(), Ok(1));
+        assert_eq!(consumer.pop(), Ok(2));
+    }
+
+    #[test]
+    fn test_transaction_sees_items_pushed_while_open() {
+        let buffer = RingBuffer::<u32>::new(4).unwrap();
+        let (mut producer, mut consumer) = buffer.split();
+        producer.push(1).unwrap();
+
+        let txn = consumer.begin();
+        assert_eq!(txn.pop(), Ok(&1));
+        assert_eq!(txn.pop(), Err(RingBufferError::BufferEmpty));
+
+        producer.push(2).unwrap();
+        producer.push(3).unwrap();
+        // Slots held by the open transaction are not reusable yet
+        assert_eq!(producer.push(4), Err(RingBufferError::BufferFull));
+        assert_eq!(txn.pop(), Ok(&2));
+        txn.commit();
+
+        assert_eq!(producer.push(4), Ok(()));
+        assert_eq!(consumer.pop(), Ok(3));
+        assert_eq!(consumer.pop(), Ok(4));
+    }
+
+    #[test]
+    fn test_transaction_commit_with_moves_out_across_wrap() {
+        let buffer = RingBuffer::<String>::new(4).unwrap();
+        let (mut producer, mut consumer) = buffer.split();
+
+        for round in 0..5 {
+            for i in 0..3 {
+                producer.push(format!("{}-{}", round, i)).unwrap();
+            }
+            let txn = consumer.begin();
+            while txn.pop().is_ok() {}
+            let mut moved = Vec::new();
+            txn.commit_with(|item| moved.push(item));
+            assert_eq!(moved, vec![format!("{}-0", round), format!("{}-1", round), format!("{}-2", round)]);
+            assert!(consumer.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_latency_sampling_disabled_by_default() {
+        let buffer = RingBuffer::<u32>::new(16).unwrap();
+        let (mut producer, mut consumer) = buffer.split();
+        producer.push(1).unwrap();
+        consumer.pop().unwrap();
+        assert!(consumer.latency().is_none());
+    }
+
+    #[test]
+    fn test_latency_sampling_rate() {
+        let buffer = RingBuffer::<u32>::new(16).unwrap().with_latency_sampling(4);
+        let (mut producer, mut consumer) = buffer.split();
+
+        for round in 0..10 {
+            for i in 0..10 {
+                producer.push(round * 10 + i).unwrap();
+            }
+            for i in 0..10 {
+                assert_eq!(consumer.pop(), Ok(round * 10 + i));
+            }
+        }
+        assert_eq!(consumer.latency().unwrap().count(), 25);
+
+        consumer.reset_latency();
+        assert_eq!(consumer.latency().unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_latency_sampling_measures_dwell_time() {
+        let buffer = RingBuffer::<u32>::new(16).unwrap().with_latency_sampling(1);
+        let (mut producer, mut consumer) = buffer.split();
+
+        producer.push(1).unwrap();
+        producer.push(2).unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        consumer.pop().unwrap();
+
+        let txn = consumer.begin();
+        txn.pop().unwrap();
+        txn.commit();
+
+        let latency = consumer.latency().unwrap();
+        assert_eq!(latency.count(), 2);
+        assert!(latency.min() >= 2_000_000, "min dwell {} ns", latency.min());
+    }
+
+    #[test]
+    fn test_push_iter_stops_when_full() {
+        let buffer = RingBuffer::<u32>::new(8).unwrap();
+        let (mut producer, mut consumer) = buffer.split();
+
+        let mut items = 0..20;
+        assert_eq!(producer.push_iter(items.by_ref()), 7);
+        assert_eq!(producer.push_iter(items.by_ref()), 0);
+        assert_eq!(items.next(), Some(7));
+
+        assert_eq!(consumer.pop_batch(3, drop), 3);
+        assert_eq!(producer.push_iter(100..), 3);
+
+        let mut popped = Vec::new();
+        assert_eq!(consumer.pop_batch(usize::MAX, |value| popped.push(value)), 7);
+        assert_eq!(popped, vec![3, 4, 5, 6, 100, 101, 102]);
+        assert_eq!(consumer.pop_batch(4, drop), 0);
+    }
+}
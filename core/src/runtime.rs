@@ -0,0 +1,655 @@
+//! Pinned-thread runner for pipelines of stages connected by rings
+//!
+//! Each [`Stage`] runs on a dedicated thread, optionally pinned to a CPU and
+//! raised to real-time priority. Stages are chained in the order they are
+//! spawned, which is also the order the pipeline drains in on shutdown:
+//!
+//! - The first stage stops at its next poll once [`Runtime::shutdown`] is called
+//! - Every later stage stops once all stages before it have stopped and it has
+//!   no work left, so nothing queued between stages is lost
+//!
+//! [`Relay`] and [`Sink`] connect stages through ring buffers.
+//!
+//! # Example
+//!
+//! ```
+//! use std::sync::atomic::{AtomicU64, Ordering};
+//! use std::sync::Arc;
+//! use ferrite_core::ring_buffer::RingBuffer;
+//! use ferrite_core::runtime::{Relay, Runtime, Sink, StageConfig, Work};
+//!
+//! let (mut numbers, raw) = RingBuffer::new(64).unwrap().split();
+//! let (doubled, output) = RingBuffer::new(64).unwrap().split();
+//!
+//! let total = Arc::new(AtomicU64::new(0));
+//! let sum = total.clone();
+//! let mut next = 0u64;
+//!
+//! let mut runtime = Runtime::new();
+//! runtime
+//!     .spawn(StageConfig::new("source"), move || {
+//!         if next == 100 {
+//!             return Work::Done;
+//!         }
+//!         if numbers.push(next).is_ok() {
+//!             next += 1;
+//!         }
+//!         Work::Progress
+//!     })
+//!     .unwrap();
+//! runtime.spawn(StageConfig::new("double"), Relay::new(raw, doubled, |n| n * 2)).unwrap();
+//! runtime
+//!     .spawn(StageConfig::new("sum"), Sink::new(output, move |n| {
+//!         sum.fetch_add(n, Ordering::Relaxed);
+//!     }))
+//!     .unwrap();
+//!
+//! runtime.join().unwrap();
+//! assert_eq!(total.load(Ordering::Relaxed), 9900);
+//! ```
+
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::channel::bounded_spsc;
+use crate::ring_buffer::{Consumer, Producer};
+
+/// Outcome of a single [`Stage::poll`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Work {
+    /// The stage did some work and should be polled again straight away
+    Progress,
+    /// The stage found nothing to do; the runner waits per its [`IdleStrategy`]
+    Idle,
+    /// The stage has finished for good and its thread exits
+    Done,
+}
+
+/// Unit of work driven by a [`Runtime`] thread
+///
+/// Implemented for any `FnMut() -> Work` closure.
+pub trait Stage: Send {
+    /// Does one bounded piece of work
+    fn poll(&mut self) -> Work;
+
+    /// Checks if the stage holds no work of its own, such as a value it could not pass on yet
+    ///
+    /// A stage is only stopped while this returns `true`. Defaults to `true`.
+    fn is_drained(&self) -> bool {
+        true
+    }
+
+    /// Called once on the stage's thread after its last poll
+    fn on_stop(&mut self) {}
+}
+
+impl<F: FnMut() -> Work + Send> Stage for F {
+    fn poll(&mut self) -> Work {
+        self()
+    }
+}
+
+/// How a stage thread waits after a poll that found no work
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IdleStrategy {
+    /// Spin between polls; lowest latency, but keeps a core busy
+    BusySpin,
+    /// Spin briefly, then yield the thread between polls
+    #[default]
+    Yielding,
+    /// Spin and yield briefly, then sleep for the given duration between polls
+    Sleeping(Duration),
+}
+
+impl IdleStrategy {
+    /// Idle polls that spin before `Yielding` and `Sleeping` back off
+    const SPIN_TRIES: u32 = 100;
+
+    /// Idle polls that yield before `Sleeping` starts to sleep
+    const YIELD_TRIES: u32 = 100;
+
+    /// Waits once; `attempt` counts consecutive idle polls
+    pub(crate) fn idle(&self, attempt: &mut u32) {
+        *attempt = attempt.saturating_add(1);
+
+        match *self {
+            IdleStrategy::BusySpin => std::hint::spin_loop(),
+            _ if *attempt <= Self::SPIN_TRIES => std::hint::spin_loop(),
+            IdleStrategy::Sleeping(duration) if *attempt > Self::SPIN_TRIES + Self::YIELD_TRIES => {
+                thread::sleep(duration)
+            }
+            _ => thread::yield_now(),
+        }
+    }
+}
+
+/// Thread settings for one stage
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StageConfig {
+    name: String,
+    cpu: Option<usize>,
+    priority: Option<i32>,
+    idle_strategy: IdleStrategy,
+}
+
+impl StageConfig {
+    /// Creates a config for an unpinned, normal-priority thread with the given name
+    pub fn new(name: impl Into<String>) -> Self {
+        StageConfig {
+            name: name.into(),
+            cpu: None,
+            priority: None,
+            idle_strategy: IdleStrategy::default(),
+        }
+    }
+
+    /// Pins the stage thread to the given CPU with `sched_setaffinity`
+    pub fn with_cpu(mut self, cpu: usize) -> Self {
+        self.cpu = Some(cpu);
+        self
+    }
+
+    /// Runs the stage under `SCHED_FIFO` at the given priority (1-99) where permitted
+    ///
+    /// Without the privilege to do so the stage keeps its normal priority; see
+    /// [`Runtime::is_realtime`]. A real-time stage that never idles can starve
+    /// every other thread on its CPU, so pair this with [`StageConfig::with_cpu`].
+    pub fn with_realtime_priority(mut self, priority: i32) -> Self {
+        self.priority = Some(priority);
+        self
+    }
+
+    /// Sets how the stage waits between idle polls; defaults to [`IdleStrategy::Yielding`]
+    pub fn with_idle_strategy(mut self, idle_strategy: IdleStrategy) -> Self {
+        self.idle_strategy = idle_strategy;
+        self
+    }
+}
+
+/// Identifies a stage started with [`Runtime::spawn`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct StageId(usize);
+
+struct StageThread {
+    handle: Option<JoinHandle<()>>,
+    finished: Arc<AtomicBool>,
+    realtime: bool,
+}
+
+/// Runs stages on dedicated threads and drains them in order on shutdown
+///
+/// Dropping the runtime shuts it down and joins every stage, ignoring panics.
+pub struct Runtime {
+    stages: Vec<StageThread>,
+    shutdown: Arc<AtomicBool>,
+}
+
+impl Runtime {
+    /// Creates a runtime with no stages
+    pub fn new() -> Self {
+        Runtime {
+            stages: Vec::new(),
+            shutdown: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Starts a stage on a new thread, downstream of every stage spawned before it
+    ///
+    /// Returns once the thread has applied its CPU affinity and priority.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - Thread name, affinity, priority and idle strategy
+    /// * `stage` - The stage to run
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(StageId)` - The stage is running
+    /// * `Err(io::Error)` - If the thread could not be spawned or pinned, or the priority is invalid
+    pub fn spawn(&mut self, config: StageConfig, stage: impl Stage + 'static) -> io::Result<StageId> {
+        let (ready_tx, ready_rx) = bounded_spsc::<io::Result<bool>>(2).expect("2 is a valid capacity");
+        let finished = Arc::new(AtomicBool::new(false));
+        let upstream = Upstream {
+            stages: self.stages.iter().map(|stage| stage.finished.clone()).collect(),
+            shutdown: self.shutdown.clone(),
+        };
+
+        let mut stage = stage;
+        let guard = FinishGuard(finished.clone());
+        let StageConfig {
+            name,
+            cpu,
+            priority,
+            idle_strategy,
+        } = config;
+
+        let handle = thread::Builder::new().name(name).spawn(move || {
+            let _guard = guard;
+            let setup = configure_thread(cpu, priority);
+            let ok = setup.is_ok();
+            let _ = ready_tx.send(setup);
+            if ok {
+                run_stage(&mut stage, idle_strategy, &upstream);
+            }
+        })?;
+
+        let realtime = match ready_rx.recv() {
+            Ok(Ok(realtime)) => realtime,
+            Ok(Err(err)) => {
+                let _ = handle.join();
+                return Err(err);
+            }
+            Err(_) => {
+                let _ = handle.join();
+                return Err(io::Error::other("stage thread exited during setup"));
+            }
+        };
+
+        self.stages.push(StageThread {
+            handle: Some(handle),
+            finished,
+            realtime,
+        });
+        Ok(StageId(self.stages.len() - 1))
+    }
+
+    /// Asks the first stage to stop, starting an ordered drain of the pipeline
+    ///
+    /// Returns immediately; use [`Runtime::join`] to wait for the drain to finish.
+    pub fn shutdown(&self) {
+        self.shutdown.store(true, Ordering::Release);
+    }
+
+    /// Waits for every stage to stop
+    ///
+    /// Without a prior [`Runtime::shutdown`] this only returns once the first
+    /// stage reports [`Work::Done`] and the rest drain behind it.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - Every stage stopped normally
+    /// * `Err(payload)` - The panic payload of the first stage that panicked
+    pub fn join(mut self) -> thread::Result<()> {
+        self.join_all()
+    }
+
+    /// Checks if a stage has stopped
+    pub fn is_finished(&self, id: StageId) -> bool {
+        self.stages[id.0].finished.load(Ordering::Acquire)
+    }
+
+    /// Checks if a stage was granted the real-time priority it asked for
+    pub fn is_realtime(&self, id: StageId) -> bool {
+        self.stages[id.0].realtime
+    }
+
+    /// Returns the number of stages spawned
+    pub fn len(&self) -> usize {
+        self.stages.len()
+    }
+
+    /// Checks if no stages have been spawned
+    pub fn is_empty(&self) -> bool {
+        self.stages.is_empty()
+    }
+
+    fn join_all(&mut self) -> thread::Result<()> {
+        let mut result = Ok(());
+        for stage in &mut self.stages {
+            if let Some(handle) = stage.handle.take() {
+                if let Err(payload) = handle.join() {
+                    if result.is_ok() {
+                        result = Err(payload);
+                    }
+                }
+            }
+        }
+        result
+    }
+}
+
+impl Default for Runtime {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for Runtime {
+    fn drop(&mut self) {
+        self.shutdown();
+        let _ = self.join_all();
+    }
+}
+
+/// What a stage waits on before it may stop
+struct Upstream {
+    stages: Vec<Arc<AtomicBool>>,
+    shutdown: Arc<AtomicBool>,
+}
+
+impl Upstream {
+    fn is_first(&self) -> bool {
+        self.stages.is_empty()
+    }
+
+    fn is_finished(&self) -> bool {
+        if self.is_first() {
+            self.shutdown.load(Ordering::Acquire)
+        } else {
+            self.stages.iter().all(|finished| finished.load(Ordering::Acquire))
+        }
+    }
+}
+
+/// Marks a stage finished when its thread exits, even by panicking, so downstream stages still drain
+struct FinishGuard(Arc<AtomicBool>);
+
+impl Drop for FinishGuard {
+    fn drop(&mut self) {
+        // Release publishes everything the stage pushed before it stopped
+        self.0.store(true, Ordering::Release);
+    }
+}
+
+fn run_stage(stage: &mut impl Stage, idle_strategy: IdleStrategy, upstream: &Upstream) {
+    let mut attempt = 0;
+    loop {
+        // Loaded before polling: an item upstream pushes just before finishing
+        // is then either seen by this poll or keeps the stage running for another
+        let upstream_finished = upstream.is_finished();
+        let work = stage.poll();
+        match work {
+            Work::Done => break,
+            Work::Progress => attempt = 0,
+            Work::Idle => {}
+        }
+
+        // Later stages only stop once a poll after upstream finished came back empty
+        let may_stop = upstream.is_first() || work == Work::Idle;
+        if may_stop && upstream_finished && stage.is_drained() {
+            break;
+        }
+
+        if work == Work::Idle {
+            idle_strategy.idle(&mut attempt);
+        }
+    }
+    stage.on_stop();
+}
+
+/// Applies affinity and priority to the calling thread
+///
+/// # Returns
+///
+/// * `Ok(true)` - Real-time priority was granted
+/// * `Ok(false)` - No priority was asked for, or it was not permitted
+/// * `Err(io::Error)` - If pinning failed or the priority is out of range
+#[cfg(target_os = "linux")]
+fn configure_thread(cpu: Option<usize>, priority: Option<i32>) -> io::Result<bool> {
+    if let Some(cpu) = cpu {
+        if cpu >= libc::CPU_SETSIZE as usize {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "CPU index out of range"));
+        }
+
+        let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+        unsafe { libc::CPU_SET(cpu, &mut set) };
+        let result = unsafe { libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) };
+        if result != 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+
+    let Some(priority) = priority else {
+        return Ok(false);
+    };
+
+    let param = libc::sched_param {
+        sched_priority: priority,
+    };
+    match unsafe { libc::pthread_setschedparam(libc::pthread_self(), libc::SCHED_FIFO, &param) } {
+        0 => Ok(true),
+        libc::EPERM => Ok(false),
+        errno => Err(io::Error::from_raw_os_error(errno)),
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn configure_thread(cpu: Option<usize>, priority: Option<i32>) -> io::Result<bool> {
+    if cpu.is_some() {
+        return Err(io::Error::new(io::ErrorKind::Unsupported, "CPU pinning requires Linux"));
+    }
+    let _ = priority;
+    Ok(false)
+}
+
+/// Stage that moves values from one ring to another through a function
+///
+/// A value the output ring has no room for is held until it does, and the
+/// stage does not stop while it holds one.
+pub struct Relay<I, O, F> {
+    input: Consumer<I>,
+    output: Producer<O>,
+    f: F,
+    pending: Option<O>,
+}
+
+impl<I, O, F: FnMut(I) -> O> Relay<I, O, F> {
+    /// Creates a relay that pushes `f(value)` to `output` for every value popped from `input`
+    pub fn new(input: Consumer<I>, output: Producer<O>, f: F) -> Self {
+        Relay {
+            input,
+            output,
+            f,
+            pending: None,
+        }
+    }
+}
+
+impl<I: Send, O: Send, F: FnMut(I) -> O + Send> Stage for Relay<I, O, F> {
+    fn poll(&mut self) -> Work {
+        if let Some(value) = self.pending.take() {
+            if self.output.is_full() {
+                self.pending = Some(value);
+                return Work::Idle;
+            }
+            let _ = self.output.push(value);
+        }
+
+        match self.input.pop() {
+            Ok(value) => {
+                let value = (self.f)(value);
+                if self.output.is_full() {
+                    self.pending = Some(value);
+                } else {
+                    let _ = self.output.push(value);
+                }
+                Work::Progress
+            }
+            Err(_) => Work::Idle,
+        }
+    }
+
+    fn is_drained(&self) -> bool {
+        self.pending.is_none()
+    }
+}
+
+/// Stage that hands every value popped from a ring to a function
+pub struct Sink<T, F> {
+    input: Consumer<T>,
+    f: F,
+}
+
+impl<T, F: FnMut(T)> Sink<T, F> {
+    /// Creates a sink that calls `f` with every value popped from `input`
+    pub fn new(input: Consumer<T>, f: F) -> Self {
+        Sink { input, f }
+    }
+}
+
+impl<T: Send, F: FnMut(T) + Send> Stage for Sink<T, F> {
+    fn poll(&mut self) -> Work {
+        match self.input.pop() {
+            Ok(value) => {
+                (self.f)(value);
+                Work::Progress
+            }
+            Err(_) => Work::Idle,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ring_buffer::RingBuffer;
+    use std::sync::atomic::AtomicU64;
+
+    #[test]
+    fn test_shutdown_drains_pipeline() {
+        let (mut input, raw) = RingBuffer::new(8).unwrap().split();
+        let (relayed, output) = RingBuffer::new(2).unwrap().split();
+        let seen = Arc::new(AtomicU64::new(0));
+        let count = seen.clone();
+        let pushed = Arc::new(AtomicU64::new(0));
+        let pushed_by_source = pushed.clone();
+        let mut next = 0u64;
+
+        let mut runtime = Runtime::new();
+        runtime
+            .spawn(StageConfig::new("source"), move || {
+                if input.push(next).is_ok() {
+                    next += 1;
+                    pushed_by_source.store(next, Ordering::Relaxed);
+                    Work::Progress
+                } else {
+                    Work::Idle
+                }
+            })
+            .unwrap();
+        runtime
+            .spawn(StageConfig::new("relay"), Relay::new(raw, relayed, |n| n + 1))
+            .unwrap();
+        let sink = runtime
+            .spawn(StageConfig::new("sink"), Sink::new(output, move |n| {
+                // The relay adds one, so the sink sees exactly 1, 2, 3, ...
+                let expected = count.load(Ordering::Relaxed) + 1;
+                assert_eq!(n, expected);
+                count.store(expected, Ordering::Relaxed);
+            }))
+            .unwrap();
+
+        while seen.load(Ordering::Relaxed) < 100 {
+            thread::yield_now();
+        }
+        runtime.shutdown();
+        while !runtime.is_finished(sink) {
+            thread::yield_now();
+        }
+
+        // Everything the source pushed before stopping made it through the relay
+        runtime.join().unwrap();
+        assert!(seen.load(Ordering::Relaxed) >= 100);
+        assert_eq!(seen.load(Ordering::Relaxed), pushed.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn test_done_source_drains_without_shutdown() {
+        let (mut input, output) = RingBuffer::new(4).unwrap().split();
+        let total = Arc::new(AtomicU64::new(0));
+        let sum = total.clone();
+        let mut values = 1..=50u64;
+        let mut pending = None;
+
+        let mut runtime = Runtime::new();
+        runtime
+            .spawn(
+                StageConfig::new("source").with_idle_strategy(IdleStrategy::BusySpin),
+                move || {
+                    let Some(value) = pending.take().or_else(|| values.next()) else {
+                        return Work::Done;
+                    };
+                    if input.push(value).is_err() {
+                        pending = Some(value);
+                    }
+                    Work::Progress
+                },
+            )
+            .unwrap();
+        runtime
+            .spawn(
+                StageConfig::new("sink").with_idle_strategy(IdleStrategy::Sleeping(Duration::from_micros(10))),
+                Sink::new(output, move |n| {
+                    sum.fetch_add(n, Ordering::Relaxed);
+                }),
+            )
+            .unwrap();
+
+        runtime.join().unwrap();
+        assert_eq!(total.load(Ordering::Relaxed), 1275);
+    }
+
+    /// Returns a CPU the current thread may run on, which need not be CPU 0 under a cpuset
+    #[cfg(target_os = "linux")]
+    fn allowed_cpu() -> usize {
+        let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+        let result = unsafe { libc::sched_getaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &mut set) };
+        assert_eq!(result, 0);
+        (0..libc::CPU_SETSIZE as usize)
+            .find(|&cpu| unsafe { libc::CPU_ISSET(cpu, &set) })
+            .expect("thread may run on no CPU")
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_affinity_and_priority() {
+        let cpu = allowed_cpu();
+        let mut runtime = Runtime::new();
+        let err = runtime
+            .spawn(StageConfig::new("bad").with_cpu(usize::MAX), || Work::Done)
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert!(runtime.is_empty());
+
+        let pinned = runtime.spawn(StageConfig::new("pinned").with_cpu(cpu), || Work::Done).unwrap();
+        assert!(!runtime.is_realtime(pinned));
+
+        // Granted or not depending on privileges, but never an error
+        runtime
+            .spawn(
+                StageConfig::new("realtime").with_cpu(cpu).with_realtime_priority(1),
+                || Work::Done,
+            )
+            .unwrap();
+        assert!(runtime
+            .spawn(StageConfig::new("invalid").with_realtime_priority(1000), || Work::Done)
+            .is_err());
+        runtime.join().unwrap();
+    }
+
+    #[test]
+    fn test_panicking_stage_still_drains_downstream() {
+        let (mut input, output) = RingBuffer::new(4).unwrap().split();
+        let received = Arc::new(AtomicU64::new(0));
+        let count = received.clone();
+
+        let mut runtime = Runtime::new();
+        runtime
+            .spawn(StageConfig::new("source"), move || {
+                input.push(1u64).unwrap();
+                panic!("source failed");
+            })
+            .unwrap();
+        runtime
+            .spawn(StageConfig::new("sink"), Sink::new(output, move |_| {
+                count.fetch_add(1, Ordering::Relaxed);
+            }))
+            .unwrap();
+
+        assert!(runtime.join().is_err());
+        assert_eq!(received.load(Ordering::Relaxed), 1);
+    }
+}
@@ -0,0 +1,318 @@
+use std::panic::{RefUnwindSafe, UnwindSafe};
+
+use crate::cache_padded::CachePadded;
+use crate::sync::{fence, Arc, AtomicUsize, Backoff, Ordering};
+
+/// A sequence lock sharing the latest value from one writer with many readers
+///
+/// The writer bumps a version counter to an odd number, writes the value in
+/// place and bumps the counter to the next even number. Readers copy the
+/// value optimistically and keep the copy only if the counter was even and
+/// unchanged around it; otherwise they retry. Readers never write to shared
+/// memory, so any number of them can poll without slowing the writer, and
+/// the writer never waits for anyone.
+///
+/// This implementation provides:
+/// - A cache-padded version counter, kept off the readers' other hot data
+/// - Wait-free writes and lock-free reads
+/// - No allocation after construction
+///
+/// Only the latest value is kept. Use a queue when every update must be seen.
+///
+/// # Memory Ordering
+///
+/// The writer stores the odd version with `Relaxed`, issues a `Release`
+/// fence, writes the value and stores the even version with `Release`.
+/// A reader loads the version with `Acquire`, copies the value, issues an
+/// `Acquire` fence and reloads the version with `Relaxed`. If the copy
+/// observed any part of a write, the fence pair guarantees the reload
+/// observes at least that write's odd version, so the copy is discarded.
+///
+/// # Example
+///
+/// ```
+/// use ferrite_core::seqlock::SeqLock;
+///
+/// #[derive(Clone, Copy, Debug, PartialEq)]
+/// struct TopOfBook {
+///     bid: u64,
+///     ask: u64,
+/// }
+///
+/// let (mut writer, reader) = SeqLock::new(TopOfBook { bid: 0, ask: 0 }).split();
+///
+/// let snapshot = reader.clone();
+/// let handle = std::thread::spawn(move || {
+///     let book = snapshot.read();
+///     assert!(book.bid == 0 || book.ask == book.bid + 1);
+/// });
+///
+/// writer.write(&TopOfBook { bid: 100, ask: 101 });
+/// handle.join().unwrap();
+///
+/// assert_eq!(reader.read(), TopOfBook { bid: 100, ask: 101 });
+/// assert_eq!(reader.version(), 1);
+/// ```
+pub struct SeqLock<T: Copy> {
+    value: T,
+}
+
+struct Shared<T> {
+    /// Twice the number of completed writes, plus one while a write is in progress
+    sequence: CachePadded<AtomicUsize>,
+    data: Storage<T>,
+}
+
+impl<T: Copy> SeqLock<T> {
+    /// Creates a new seqlock holding `value`
+    pub fn new(value: T) -> Self {
+        SeqLock { value }
+    }
+
+    /// Splits the seqlock into the single writer and a cloneable reader
+    pub fn split(self) -> (Writer<T>, Reader<T>) {
+        let shared = Arc::new(Shared {
+            sequence: CachePadded { value: AtomicUsize::new(0) },
+            data: Storage::new(self.value),
+        });
+
+        let writer = Writer {
+            shared: shared.clone(),
+            sequence: 0,
+        };
+        let reader = Reader { shared };
+
+        (writer, reader)
+    }
+}
+
+/// Writing half of a seqlock; there is exactly one
+pub struct Writer<T> {
+    shared: Arc<Shared<T>>,
+    /// Local copy of the version counter
+    sequence: usize,
+}
+
+/// Reading handle of a seqlock; clone it to add readers
+pub struct Reader<T> {
+    shared: Arc<Shared<T>>,
+}
+
+unsafe impl<T: Copy + Send> Send for Writer<T> {}
+unsafe impl<T: Copy + Send> Send for Reader<T> {}
+unsafe impl<T: Copy + Send> Sync for Reader<T> {}
+
+// A write interrupted by a panic cannot happen: copying a `T: Copy` never
+// panics, and readers discard any copy taken while the version is odd.
+impl<T: UnwindSafe> UnwindSafe for Writer<T> {}
+impl<T: RefUnwindSafe> RefUnwindSafe for Writer<T> {}
+impl<T: UnwindSafe> UnwindSafe for Reader<T> {}
+impl<T: RefUnwindSafe> RefUnwindSafe for Reader<T> {}
+
+impl<T> Clone for Reader<T> {
+    fn clone(&self) -> Self {
+        Reader {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<T: Copy> Writer<T> {
+    /// Replaces the shared value
+    ///
+    /// Never waits: readers that overlap the write retry on their side.
+    pub fn write(&mut self, value: &T) {
+        let shared = &*self.shared;
+        let sequence = self.sequence;
+
+        shared.sequence.value.store(sequence.wrapping_add(1), Ordering::Relaxed);
+        // Keeps the data writes below from becoming visible before the odd version
+        fence(Ordering::Release);
+
+        // This is the only writer, and readers validate every copy they take
+        unsafe { shared.data.write(value) };
+
+        self.sequence = sequence.wrapping_add(2);
+        shared.sequence.value.store(self.sequence, Ordering::Release);
+    }
+
+    /// Returns the number of writes made so far
+    pub fn version(&self) -> u64 {
+        (self.sequence >> 1) as u64
+    }
+}
+
+impl<T: Copy> Reader<T> {
+    /// Returns a consistent copy of the latest value
+    ///
+    /// Retries, spinning and then yielding, while a write overlaps the copy.
+    pub fn read(&self) -> T {
+        let mut backoff = Backoff::new();
+        loop {
+            if let Some(value) = self.try_read() {
+                return value;
+            }
+            backoff.snooze();
+        }
+    }
+
+    /// Makes a single attempt to copy the latest value
+    ///
+    /// # Returns
+    ///
+    /// * `Some(T)` - A consistent copy of the value
+    /// * `None` - A write was in progress or overlapped the copy
+    pub fn try_read(&self) -> Option<T> {
+        let shared = &*self.shared;
+
+        let before = shared.sequence.value.load(Ordering::Acquire);
+        if before & 1 != 0 {
+            return None;
+        }
+
+        // May race with the writer; the copy is thrown away if it did
+        let value = unsafe { shared.data.read() };
+
+        // Keeps the data reads above from being satisfied after the reload below
+        fence(Ordering::Acquire);
+        let after = shared.sequence.value.load(Ordering::Relaxed);
+
+        if before == after {
+            Some(value)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the number of completed writes
+    ///
+    /// A change in version means there is a newer value to read.
+    pub fn version(&self) -> u64 {
+        (self.shared.sequence.value.load(Ordering::Acquire) >> 1) as u64
+    }
+}
+
+/// The shared value, copied with volatile accesses that may race with the writer
+#[cfg(not(loom))]
+struct Storage<T>(std::cell::UnsafeCell<T>);
+
+#[cfg(not(loom))]
+impl<T: Copy> Storage<T> {
+    fn new(value: T) -> Self {
+        Storage(std::cell::UnsafeCell::new(value))
+    }
+
+    /// # Safety
+    ///
+    /// The result must be discarded if a write overlapped it.
+    unsafe fn read(&self) -> T {
+        std::ptr::read_volatile(self.0.get())
+    }
+
+    /// # Safety
+    ///
+    /// Must only be called by the single writer.
+    unsafe fn write(&self, value: &T) {
+        std::ptr::write_volatile(self.0.get(), *value)
+    }
+}
+
+/// The shared value, split into `Relaxed` atomic words so loom can observe torn reads
+///
+/// Copies `T` byte for byte through `usize` words, so loom tests must use
+/// types without padding bytes.
+#[cfg(loom)]
+struct Storage<T> {
+    words: Box<[AtomicUsize]>,
+    _marker: std::marker::PhantomData<T>,
+}
+
+#[cfg(loom)]
+impl<T: Copy> Storage<T> {
+    const WORDS: usize = std::mem::size_of::<T>().div_ceil(std::mem::size_of::<usize>());
+
+    fn new(value: T) -> Self {
+        let storage = Storage {
+            words: (0..Self::WORDS).map(|_| AtomicUsize::new(0)).collect(),
+            _marker: std::marker::PhantomData,
+        };
+        unsafe { storage.write(&value) };
+        storage
+    }
+
+    unsafe fn read(&self) -> T {
+        let words: Vec<usize> = self.words.iter().map(|word| word.load(Ordering::Relaxed)).collect();
+        std::ptr::read_unaligned(words.as_ptr() as *const T)
+    }
+
+    unsafe fn write(&self, value: &T) {
+        let mut words = vec![0usize; Self::WORDS];
+        std::ptr::copy_nonoverlapping(
+            value as *const T as *const u8,
+            words.as_mut_ptr() as *mut u8,
+            std::mem::size_of::<T>(),
+        );
+        for (word, bits) in self.words.iter().zip(words) {
+            word.store(bits, Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_latest_value() {
+        let (mut writer, reader) = SeqLock::new((1u32, 2u64)).split();
+        assert_eq!(reader.read(), (1, 2));
+        assert_eq!(reader.version(), 0);
+
+        writer.write(&(3, 4));
+        writer.write(&(5, 6));
+        assert_eq!(reader.try_read(), Some((5, 6)));
+        assert_eq!(reader.version(), 2);
+        assert_eq!(writer.version(), 2);
+    }
+
+    #[test]
+    fn test_try_read_rejects_write_in_progress() {
+        let (writer, reader) = SeqLock::new(7u64).split();
+        writer.shared.sequence.value.store(1, Ordering::Relaxed);
+        assert_eq!(reader.try_read(), None);
+
+        writer.shared.sequence.value.store(2, Ordering::Relaxed);
+        assert_eq!(reader.try_read(), Some(7));
+    }
+
+    #[test]
+    fn test_no_torn_reads_across_threads() {
+        const WRITES: u64 = 20_000;
+        let (mut writer, reader) = SeqLock::new([0u64; 8]).split();
+
+        let readers: Vec<_> = (0..2)
+            .map(|_| {
+                let reader = reader.clone();
+                std::thread::spawn(move || {
+                    let mut last = 0;
+                    while last < WRITES {
+                        let value = reader.read();
+                        assert!(value.iter().all(|&word| word == value[0]), "torn read {:?}", value);
+                        assert!(value[0] >= last);
+                        last = value[0];
+                        std::thread::yield_now();
+                    }
+                })
+            })
+            .collect();
+
+        for i in 1..=WRITES {
+            writer.write(&[i; 8]);
+            if i % 64 == 0 {
+                std::thread::yield_now();
+            }
+        }
+
+        readers.into_iter().for_each(|handle| handle.join().unwrap());
+    }
+}
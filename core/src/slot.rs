@@ -0,0 +1,84 @@
+use std::mem::MaybeUninit;
+
+use crate::sync::{AtomicUsize, Ordering, UnsafeCell};
+
+/// A queue slot tagged with the position it is ready for
+///
+/// Shared by the Vyukov-style bounded queues. A slot accepts a write for
+/// position `p` when its sequence is `writable(p)`, holds the value for `p`
+/// when its sequence is `readable(p)`, and is released for the next lap by
+/// setting its sequence to `writable(p + capacity)`. Doubling the position
+/// keeps "holds `p`" and "accepts `p + 1`" apart even for a capacity of 1.
+pub(crate) struct Slot<T> {
+    pub(crate) sequence: AtomicUsize,
+    pub(crate) value: UnsafeCell<MaybeUninit<T>>,
+}
+
+impl<T> Slot<T> {
+    /// Allocates `capacity` empty slots, each ready for its first-lap position
+    pub(crate) fn array(capacity: usize) -> Box<[Slot<T>]> {
+        (0..capacity)
+            .map(|index| Slot {
+                sequence: AtomicUsize::new(writable(index)),
+                value: UnsafeCell::new(MaybeUninit::uninit()),
+            })
+            .collect()
+    }
+}
+
+/// Sequence of a slot that accepts a write for `position`
+#[inline]
+pub(crate) fn writable(position: usize) -> usize {
+    position << 1
+}
+
+/// Sequence of a slot that holds the value for `position`
+#[inline]
+pub(crate) fn readable(position: usize) -> usize {
+    (position << 1) | 1
+}
+
+/// Pushes `value` for the next free position of a queue with many producers
+///
+/// `slots` must have a power-of-two length, and `head` is the position the
+/// next push claims. A producer claims its position with a CAS on `head`,
+/// writes the slot it now owns and publishes it with the slot's sequence.
+///
+/// # Returns
+///
+/// * `Ok(())` - The value was pushed
+/// * `Err(T)` - The queue is full; the value is handed back
+pub(crate) fn push<T>(slots: &[Slot<T>], head: &AtomicUsize, value: T) -> Result<(), T> {
+    let mask = slots.len() - 1;
+    let mut position = head.load(Ordering::Relaxed);
+
+    loop {
+        let slot = &slots[position & mask];
+        let sequence = slot.sequence.load(Ordering::Acquire);
+        let lag = sequence.wrapping_sub(writable(position)) as isize;
+
+        if lag == 0 {
+            match head.compare_exchange_weak(
+                position,
+                position.wrapping_add(1),
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => {
+                    // The CAS gave this producer exclusive ownership of the
+                    // slot until the sequence store below publishes it.
+                    slot.value.with_mut(|ptr| unsafe { (*ptr).write(value) });
+                    slot.sequence.store(readable(position), Ordering::Release);
+                    return Ok(());
+                }
+                Err(current) => position = current,
+            }
+        } else if lag < 0 {
+            // The slot still holds an item from the previous lap
+            return Err(value);
+        } else {
+            // Another producer claimed this position first
+            position = head.load(Ordering::Relaxed);
+        }
+    }
+}
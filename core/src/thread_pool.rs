@@ -0,0 +1,430 @@
+//! Work-stealing thread pool built on the Chase-Lev [`Deque`]
+//!
+//! Every worker thread owns a deque. Tasks spawned from inside a task go onto
+//! the spawning worker's deque, where it pops them newest first; tasks spawned
+//! from outside the pool go into a shared MPMC injector queue. A worker that
+//! runs out of work takes from the injector and then steals the oldest tasks
+//! from the other workers.
+//!
+//! # Example
+//!
+//! ```
+//! use std::sync::atomic::{AtomicU64, Ordering};
+//! use std::sync::Arc;
+//! use ferrite_core::thread_pool::ThreadPool;
+//!
+//! let pool = Arc::new(ThreadPool::new(4).unwrap());
+//! let total = Arc::new(AtomicU64::new(0));
+//!
+//! for chunk in 0..10u64 {
+//!     let (spawner, total) = (pool.clone(), total.clone());
+//!     pool.spawn(move || {
+//!         // Nested tasks land on this worker's deque for others to steal
+//!         for i in chunk * 10..(chunk + 1) * 10 {
+//!             let total = total.clone();
+//!             spawner.spawn(move || {
+//!                 total.fetch_add(i, Ordering::Relaxed);
+//!             });
+//!         }
+//!     });
+//! }
+//!
+//! pool.wait().unwrap();
+//! assert_eq!(total.load(Ordering::Relaxed), 4950);
+//! ```
+
+use std::any::Any;
+use std::cell::RefCell;
+use std::io;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::deque::{Deque, Steal, Stealer, Worker};
+use crate::mpmc::{self, MpmcQueue};
+use crate::runtime::IdleStrategy;
+
+type Task = Box<dyn FnOnce() + Send>;
+
+/// Configures a [`ThreadPool`] before its threads start
+#[derive(Debug, Clone)]
+pub struct ThreadPoolBuilder {
+    threads: usize,
+    deque_capacity: usize,
+    injector_capacity: usize,
+    idle_strategy: IdleStrategy,
+}
+
+impl ThreadPoolBuilder {
+    /// Creates a builder for a pool of `threads` workers
+    ///
+    /// Defaults to deques of 1024 tasks, an injector of 4096 tasks and workers
+    /// that sleep 50µs at a time once they have spun and yielded for a while.
+    pub fn new(threads: usize) -> Self {
+        ThreadPoolBuilder {
+            threads,
+            deque_capacity: 1024,
+            injector_capacity: 4096,
+            idle_strategy: IdleStrategy::Sleeping(Duration::from_micros(50)),
+        }
+    }
+
+    /// Sets the capacity of each worker's deque; must be a power of two
+    pub fn with_deque_capacity(mut self, capacity: usize) -> Self {
+        self.deque_capacity = capacity;
+        self
+    }
+
+    /// Sets the capacity of the queue for tasks spawned outside the pool; must be a power of two
+    pub fn with_injector_capacity(mut self, capacity: usize) -> Self {
+        self.injector_capacity = capacity;
+        self
+    }
+
+    /// Sets how workers wait when there is nothing to run or steal
+    pub fn with_idle_strategy(mut self, idle_strategy: IdleStrategy) -> Self {
+        self.idle_strategy = idle_strategy;
+        self
+    }
+
+    /// Starts the worker threads
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(ThreadPool)` - The pool is running
+    /// * `Err(io::Error)` - If there are no threads, a capacity is invalid, or a
+    ///   thread could not be spawned
+    pub fn build(self) -> io::Result<ThreadPool> {
+        let invalid = |message| io::Error::new(io::ErrorKind::InvalidInput, message);
+        if self.threads == 0 {
+            return Err(invalid("a thread pool needs at least one thread"));
+        }
+
+        let (injector_tx, injector_rx) = MpmcQueue::new(self.injector_capacity)
+            .map_err(|_| invalid("injector capacity must be a power of two"))?
+            .split();
+        let mut workers = Vec::with_capacity(self.threads);
+        let mut stealers = Vec::with_capacity(self.threads);
+        for _ in 0..self.threads {
+            let (worker, stealer) = Deque::new(self.deque_capacity)
+                .map_err(|_| invalid("deque capacity must be a power of two"))?
+                .split();
+            workers.push(worker);
+            stealers.push(stealer);
+        }
+
+        let shared = Arc::new(PoolShared {
+            injector_tx,
+            injector_rx,
+            stealers: stealers.into(),
+            pending: AtomicUsize::new(0),
+            shutdown: AtomicBool::new(false),
+            panic: Mutex::new(None),
+            idle_strategy: self.idle_strategy,
+        });
+
+        let mut pool = ThreadPool {
+            shared: shared.clone(),
+            threads: Vec::with_capacity(self.threads),
+        };
+        for (index, worker) in workers.into_iter().enumerate() {
+            let shared = shared.clone();
+            // On failure, dropping `pool` stops the threads already started
+            let handle = thread::Builder::new()
+                .name(format!("ferrite-pool-{}", index))
+                .spawn(move || run_worker(&shared, index, worker))?;
+            pool.threads.push(handle);
+        }
+
+        Ok(pool)
+    }
+}
+
+/// Work-stealing pool of worker threads
+///
+/// Dropping the pool runs every task already spawned, then joins the threads.
+pub struct ThreadPool {
+    shared: Arc<PoolShared>,
+    threads: Vec<JoinHandle<()>>,
+}
+
+struct PoolShared {
+    injector_tx: mpmc::Sender<Task>,
+    injector_rx: mpmc::Receiver<Task>,
+    stealers: Box<[Stealer<Task>]>,
+    /// Tasks spawned but not yet finished
+    pending: AtomicUsize,
+    shutdown: AtomicBool,
+    /// First panic payload since the last `wait`
+    panic: Mutex<Option<Box<dyn Any + Send>>>,
+    idle_strategy: IdleStrategy,
+}
+
+/// The calling worker's deque, set for the lifetime of each pool thread
+struct Local {
+    /// Address of the pool the worker belongs to
+    pool: *const PoolShared,
+    index: usize,
+    worker: Worker<Task>,
+}
+
+thread_local! {
+    static LOCAL: RefCell<Option<Local>> = const { RefCell::new(None) };
+}
+
+impl ThreadPool {
+    /// Starts a pool of `threads` workers with default settings
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(ThreadPool)` - The pool is running
+    /// * `Err(io::Error)` - If `threads` is 0 or a thread could not be spawned
+    pub fn new(threads: usize) -> io::Result<Self> {
+        ThreadPoolBuilder::new(threads).build()
+    }
+
+    /// Returns a builder for a pool of `threads` workers
+    pub fn builder(threads: usize) -> ThreadPoolBuilder {
+        ThreadPoolBuilder::new(threads)
+    }
+
+    /// Returns the number of worker threads
+    pub fn threads(&self) -> usize {
+        self.threads.len()
+    }
+
+    /// Runs `task` on the pool
+    ///
+    /// Called from one of the pool's own tasks, the task goes onto that
+    /// worker's deque; otherwise it goes into the injector. If that queue is
+    /// full the task runs on the calling thread instead, so spawning never blocks.
+    pub fn spawn(&self, task: impl FnOnce() + Send + 'static) {
+        let shared = &*self.shared;
+        shared.pending.fetch_add(1, Ordering::Relaxed);
+
+        let task: Task = Box::new(task);
+        let task = match shared.push_local(task) {
+            Ok(()) => return,
+            Err(task) => task,
+        };
+        if let Err(task) = shared.injector_tx.offer(task) {
+            shared.run(task);
+        }
+    }
+
+    /// Waits until every spawned task has finished, helping to run them meanwhile
+    ///
+    /// Must not be called from inside a task, since that task counts as unfinished.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - Every task finished normally
+    /// * `Err(payload)` - The payload of the first task to panic since the last `wait`
+    pub fn wait(&self) -> thread::Result<()> {
+        let shared = &*self.shared;
+        let mut attempt = 0;
+        while shared.pending.load(Ordering::Acquire) != 0 {
+            match shared.find_task() {
+                Some(task) => {
+                    shared.run(task);
+                    attempt = 0;
+                }
+                None => shared.idle_strategy.idle(&mut attempt),
+            }
+        }
+
+        match shared.panic.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).take() {
+            Some(payload) => Err(payload),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for ThreadPool {
+    fn drop(&mut self) {
+        self.shared.shutdown.store(true, Ordering::Release);
+        let current = thread::current().id();
+        for handle in self.threads.drain(..) {
+            // A task holding the last handle to the pool cannot join its own thread
+            if handle.thread().id() != current {
+                let _ = handle.join();
+            }
+        }
+    }
+}
+
+impl PoolShared {
+    /// Pushes onto the calling worker's deque, if the caller is one of this pool's workers
+    fn push_local(&self, task: Task) -> Result<(), Task> {
+        LOCAL.with(|local| match local.borrow_mut().as_mut() {
+            Some(local) if std::ptr::eq(local.pool, self) => local.worker.push(task),
+            _ => Err(task),
+        })
+    }
+
+    /// Takes a task from the caller's deque, the injector or another worker
+    fn find_task(&self) -> Option<Task> {
+        let (own, local_task) = LOCAL.with(|local| match local.borrow_mut().as_mut() {
+            Some(local) if std::ptr::eq(local.pool, self) => (Some(local.index), local.worker.pop()),
+            _ => (None, None),
+        });
+        if local_task.is_some() {
+            return local_task;
+        }
+
+        if let Ok(task) = self.injector_rx.try_pop() {
+            return Some(task);
+        }
+
+        // Start after our own deque so thieves spread out over their victims
+        let start = own.map_or(0, |index| index + 1);
+        let count = self.stealers.len();
+        for offset in 0..count {
+            let victim = (start + offset) % count;
+            if Some(victim) == own {
+                continue;
+            }
+            loop {
+                match self.stealers[victim].steal() {
+                    Steal::Success(task) => return Some(task),
+                    Steal::Empty => break,
+                    Steal::Retry => {}
+                }
+            }
+        }
+        None
+    }
+
+    fn run(&self, task: Task) {
+        if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(task)) {
+            let mut panic = self.panic.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            panic.get_or_insert(payload);
+        }
+        self.pending.fetch_sub(1, Ordering::Release);
+    }
+}
+
+fn run_worker(shared: &PoolShared, index: usize, worker: Worker<Task>) {
+    LOCAL.with(|local| {
+        *local.borrow_mut() = Some(Local {
+            pool: shared as *const PoolShared,
+            index,
+            worker,
+        });
+    });
+
+    let mut attempt = 0;
+    loop {
+        if let Some(task) = shared.find_task() {
+            shared.run(task);
+            attempt = 0;
+            continue;
+        }
+
+        // Nothing is left to run or steal. Tasks still running spawn onto
+        // their own worker's deque or the injector, and that worker runs them
+        // before it stops, so this one need not wait for them.
+        if shared.shutdown.load(Ordering::Acquire) {
+            break;
+        }
+        shared.idle_strategy.idle(&mut attempt);
+    }
+
+    LOCAL.with(|local| local.borrow_mut().take());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicU64;
+
+    fn sum_range(pool: &Arc<ThreadPool>, total: &Arc<AtomicU64>, start: u64, end: u64) {
+        if end - start <= 8 {
+            total.fetch_add((start..end).sum(), Ordering::Relaxed);
+            return;
+        }
+
+        let mid = start + (end - start) / 2;
+        for (start, end) in [(start, mid), (mid, end)] {
+            let (spawner, total) = (pool.clone(), total.clone());
+            pool.spawn(move || sum_range(&spawner, &total, start, end));
+        }
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        assert_eq!(ThreadPool::new(0).err().unwrap().kind(), io::ErrorKind::InvalidInput);
+        let err = ThreadPool::builder(1).with_deque_capacity(3).build().err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_nested_spawns_are_stolen_and_finish() {
+        let pool = Arc::new(ThreadPool::builder(3).with_deque_capacity(16).build().unwrap());
+        let total = Arc::new(AtomicU64::new(0));
+
+        sum_range(&pool, &total, 0, 10_000);
+        pool.wait().unwrap();
+        assert_eq!(total.load(Ordering::Relaxed), 49_995_000);
+        assert_eq!(pool.threads(), 3);
+    }
+
+    #[test]
+    fn test_panics_are_reported_and_workers_survive() {
+        let pool = ThreadPool::new(2).unwrap();
+        let ran = Arc::new(AtomicU64::new(0));
+
+        pool.spawn(|| panic!("task failed"));
+        for _ in 0..10 {
+            let ran = ran.clone();
+            pool.spawn(move || {
+                ran.fetch_add(1, Ordering::Relaxed);
+            });
+        }
+
+        assert!(pool.wait().is_err());
+        assert_eq!(ran.load(Ordering::Relaxed), 10);
+        assert!(pool.wait().is_ok());
+    }
+
+    #[test]
+    fn test_last_handle_dropped_inside_task() {
+        let pool = Arc::new(ThreadPool::new(2).unwrap());
+        let released = Arc::new(AtomicBool::new(false));
+        let (done_tx, done_rx) = std::sync::mpsc::channel();
+
+        let (handle, flag) = (pool.clone(), released.clone());
+        pool.spawn(move || {
+            while !flag.load(Ordering::Acquire) {
+                thread::yield_now();
+            }
+            // The pool is dropped here, on one of its own workers
+            drop(handle);
+            done_tx.send(()).unwrap();
+        });
+        drop(pool);
+        released.store(true, Ordering::Release);
+
+        done_rx
+            .recv_timeout(Duration::from_secs(10))
+            .expect("dropping the last handle inside a task deadlocked");
+    }
+
+    #[test]
+    fn test_drop_runs_pending_tasks() {
+        let ran = Arc::new(AtomicU64::new(0));
+        let pool = ThreadPool::builder(1).with_injector_capacity(2).build().unwrap();
+
+        // More tasks than the injector holds; the overflow runs inline
+        for _ in 0..100 {
+            let ran = ran.clone();
+            pool.spawn(move || {
+                ran.fetch_add(1, Ordering::Relaxed);
+            });
+        }
+        drop(pool);
+        assert_eq!(ran.load(Ordering::Relaxed), 100);
+    }
+}
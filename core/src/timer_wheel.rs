@@ -0,0 +1,521 @@
+use crate::ring_buffer::Consumer;
+
+/// Bits of a deadline consumed by each level
+const SLOT_BITS: u32 = 6;
+
+/// Slots per level
+const SLOTS: usize = 1 << SLOT_BITS;
+
+/// Number of levels, enough to place any `u64` deadline directly
+const LEVELS: usize = (u64::BITS as usize).div_ceil(SLOT_BITS as usize);
+
+/// Slab index marking the end of a list
+const NIL: u32 = u32::MAX;
+
+/// Bucket of the list of timers whose deadline had passed when inserted
+const OVERDUE: usize = LEVELS * SLOTS;
+
+/// Identifies a scheduled timer for [`TimerWheel::cancel`]
+///
+/// Handles carry a generation, so a handle to a timer that has fired or been
+/// cancelled never matches a later timer reusing the same storage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TimerHandle {
+    index: u32,
+    generation: u32,
+}
+
+/// A scheduling request sent to the thread that owns a [`TimerWheel`]
+#[derive(Debug)]
+pub enum TimerCommand<T> {
+    /// Schedule `payload` to expire at `deadline`
+    Schedule { deadline: u64, payload: T },
+    /// Cancel a timer scheduled earlier
+    Cancel(TimerHandle),
+}
+
+/// A hashed hierarchical timer wheel for a single-threaded event loop
+///
+/// Time is measured in caller-defined ticks, e.g. milliseconds since start.
+/// Each level has 64 slots; level `n` slots span 64^n ticks, and eleven
+/// levels cover the whole `u64` range.
+/// A timer is placed in the lowest level whose span covers its deadline and
+/// moves down a level each time the wheel reaches its slot, until it expires
+/// from level 0 on exactly its deadline tick.
+///
+/// This implementation provides:
+/// - O(1) insert of future deadlines and O(1) cancel by [`TimerHandle`]
+/// - Storage for a fixed number of timers, allocated once in `new`
+/// - Intrusive per-slot lists, so firing and cascading never allocate
+///
+/// # Cross-thread scheduling
+///
+/// The wheel itself is not shared. Other threads send [`TimerCommand`]s through
+/// a [`RingBuffer`](crate::ring_buffer::RingBuffer) and the owning thread
+/// applies them with [`TimerWheel::drain_commands`].
+///
+/// # Example
+///
+/// ```
+/// use ferrite_core::timer_wheel::TimerWheel;
+///
+/// let mut wheel = TimerWheel::new(1024);
+/// let heartbeat = wheel.insert(100, "heartbeat").unwrap();
+/// let timeout = wheel.insert(5_000, "order timeout").unwrap();
+///
+/// assert_eq!(wheel.cancel(timeout), Some("order timeout"));
+///
+/// let mut fired = Vec::new();
+/// wheel.tick(150, |deadline, payload| fired.push((deadline, payload)));
+/// assert_eq!(fired, vec![(100, "heartbeat")]);
+/// assert_eq!(wheel.cancel(heartbeat), None);
+/// ```
+pub struct TimerWheel<T> {
+    entries: Box<[Entry<T>]>,
+    /// First entry of each slot's list, level by level
+    slots: Box<[[u32; SLOTS]; LEVELS]>,
+    /// Bit `n` of a level is set while its slot `n` is non-empty
+    occupied: [u64; LEVELS],
+    /// First entry of the overdue list, kept in deadline order
+    overdue: u32,
+    /// Head of the list of unused entries
+    free: u32,
+    /// Last tick processed
+    now: u64,
+    len: usize,
+}
+
+struct Entry<T> {
+    deadline: u64,
+    payload: Option<T>,
+    generation: u32,
+    /// Where the entry is linked, as `level * SLOTS + slot` or [`OVERDUE`]
+    bucket: usize,
+    prev: u32,
+    next: u32,
+}
+
+impl<T> TimerWheel<T> {
+    /// Creates an empty wheel at tick 0 with room for `capacity` timers
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` does not fit in a `u32`.
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity < NIL as usize, "timer wheel capacity {} is too large", capacity);
+
+        let entries = (0..capacity)
+            .map(|index| Entry {
+                deadline: 0,
+                payload: None,
+                generation: 0,
+                bucket: 0,
+                prev: NIL,
+                next: if index + 1 < capacity { index as u32 + 1 } else { NIL },
+            })
+            .collect();
+
+        TimerWheel {
+            entries,
+            slots: Box::new([[NIL; SLOTS]; LEVELS]),
+            occupied: [0; LEVELS],
+            overdue: NIL,
+            free: if capacity > 0 { 0 } else { NIL },
+            now: 0,
+            len: 0,
+        }
+    }
+
+    /// Schedules `payload` to expire at `deadline`
+    ///
+    /// A deadline at or before the current tick goes on an overdue list that
+    /// the next [`TimerWheel::tick`] fires first, even if `now` is unchanged.
+    /// Inserting it walks the overdue timers still pending, to keep them in
+    /// deadline order.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(TimerHandle)` - The timer was scheduled
+    /// * `Err(T)` - Every entry is in use; the payload is handed back
+    pub fn insert(&mut self, deadline: u64, payload: T) -> Result<TimerHandle, T> {
+        if self.free == NIL {
+            return Err(payload);
+        }
+
+        let index = self.free;
+        let entry = &mut self.entries[index as usize];
+        self.free = entry.next;
+        entry.deadline = deadline;
+        entry.payload = Some(payload);
+        let generation = entry.generation;
+
+        if deadline <= self.now {
+            self.link_overdue(index);
+        } else {
+            self.link(index, self.bucket_for(deadline, self.now + 1));
+        }
+        self.len += 1;
+
+        Ok(TimerHandle { index, generation })
+    }
+
+    /// Cancels a timer that has not fired yet
+    ///
+    /// # Returns
+    ///
+    /// * `Some(T)` - The timer's payload
+    /// * `None` - The timer already fired or was cancelled
+    pub fn cancel(&mut self, handle: TimerHandle) -> Option<T> {
+        let entry = self.entries.get(handle.index as usize)?;
+        if entry.generation != handle.generation || entry.payload.is_none() {
+            return None;
+        }
+
+        self.unlink(handle.index);
+        Some(self.release(handle.index))
+    }
+
+    /// Advances the wheel to `now`, passing every expired timer to `f`
+    ///
+    /// Timers inserted with a deadline that had already passed fire first.
+    /// The rest fire tick by tick, each with the deadline it was scheduled for.
+    /// Empty stretches are skipped using per-level occupancy bitmaps, so a
+    /// large jump costs no more than the slots it actually visits. Ticks must
+    /// not go backwards; an earlier `now` is ignored.
+    ///
+    /// # Returns
+    ///
+    /// The number of timers that fired
+    pub fn tick<F: FnMut(u64, T)>(&mut self, now: u64, mut f: F) -> usize {
+        let mut fired = 0;
+
+        while let Some(index) = self.first(OVERDUE) {
+            self.unlink(index);
+            let deadline = self.entries[index as usize].deadline;
+            let payload = self.release(index);
+            fired += 1;
+            f(deadline, payload);
+        }
+
+        while self.now < now {
+            let tick = match self.next_event() {
+                Some(tick) if tick <= now => tick,
+                _ => {
+                    self.now = now;
+                    break;
+                }
+            };
+
+            // Bring down every higher-level slot that starts at this tick
+            for level in 1..LEVELS {
+                let shift = SLOT_BITS * level as u32;
+                if tick & ((1 << shift) - 1) != 0 {
+                    break;
+                }
+                self.cascade(level * SLOTS + ((tick >> shift) as usize & (SLOTS - 1)), tick);
+            }
+
+            let bucket = tick as usize & (SLOTS - 1);
+            while let Some(index) = self.first(bucket) {
+                self.unlink(index);
+                let deadline = self.entries[index as usize].deadline;
+                let payload = self.release(index);
+                fired += 1;
+                f(deadline, payload);
+            }
+            self.now = tick;
+        }
+
+        fired
+    }
+
+    /// Applies scheduling requests received from other threads
+    ///
+    /// Cancels are always applied, since they free entries. A `Schedule` that
+    /// finds the wheel full stops the drain, leaving it and the commands after
+    /// it in the ring. Handles of timers scheduled this way are not reported;
+    /// senders that need to cancel should have the owning thread hand them out.
+    ///
+    /// # Returns
+    ///
+    /// The number of commands applied
+    pub fn drain_commands(&mut self, commands: &mut Consumer<TimerCommand<T>>) -> usize {
+        let mut applied = 0;
+        loop {
+            // Look before taking, so a blocked command stays in the ring
+            let txn = commands.begin();
+            match txn.pop() {
+                Ok(TimerCommand::Schedule { .. }) if self.is_full() => break,
+                Ok(_) => {}
+                Err(_) => break,
+            }
+
+            txn.commit_with(|command| match command {
+                TimerCommand::Schedule { deadline, payload } => {
+                    // The wheel has a free entry, so this cannot fail
+                    let _ = self.insert(deadline, payload);
+                }
+                TimerCommand::Cancel(handle) => {
+                    self.cancel(handle);
+                }
+            });
+            applied += 1;
+        }
+        applied
+    }
+
+    /// Returns the last tick processed
+    pub fn now(&self) -> u64 {
+        self.now
+    }
+
+    /// Returns the number of scheduled timers
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Checks if no timers are scheduled
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Checks if every entry is in use
+    pub fn is_full(&self) -> bool {
+        self.free == NIL
+    }
+
+    /// Returns the maximum number of scheduled timers
+    pub fn capacity(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Picks the bucket for a deadline, given the next tick to be processed
+    ///
+    /// The highest bit where the deadline differs from `base` picks the level,
+    /// so every timer sits in a slot the wheel has not yet reached on its level.
+    fn bucket_for(&self, deadline: u64, base: u64) -> usize {
+        let deadline = deadline.max(base);
+        let masked = (deadline ^ base) | (SLOTS as u64 - 1);
+        let level = ((63 - masked.leading_zeros()) / SLOT_BITS) as usize;
+        let slot = (deadline >> (SLOT_BITS * level as u32)) as usize & (SLOTS - 1);
+        level * SLOTS + slot
+    }
+
+    /// Returns the next tick that expires a timer or cascades a slot
+    fn next_event(&self) -> Option<u64> {
+        let base = self.now + 1;
+
+        (0..LEVELS)
+            .filter(|&level| self.occupied[level] != 0)
+            .map(|level| {
+                let shift = SLOT_BITS * level as u32;
+                let current = (base >> shift) as u32 & (SLOTS as u32 - 1);
+
+                // Timers only sit in slots at or after the one the wheel is on
+                let slot = (self.occupied[level] & (u64::MAX << current)).trailing_zeros();
+
+                // The top level's span exceeds `u64`, so mask in `u128`
+                let rotation = 1u128 << (shift + SLOT_BITS);
+                let start = base as u128 & !(rotation - 1);
+                (start | (slot as u128) << shift) as u64
+            })
+            .min()
+    }
+
+    /// Re-places every timer in a higher-level bucket relative to `tick`
+    fn cascade(&mut self, bucket: usize, tick: u64) {
+        let mut index = self.take(bucket);
+        while index != NIL {
+            let next = self.entries[index as usize].next;
+            let deadline = self.entries[index as usize].deadline;
+            self.link(index, self.bucket_for(deadline, tick));
+            index = next;
+        }
+    }
+
+    fn first(&self, bucket: usize) -> Option<u32> {
+        let head = match bucket {
+            OVERDUE => self.overdue,
+            _ => self.slots[bucket / SLOTS][bucket % SLOTS],
+        };
+        (head != NIL).then_some(head)
+    }
+
+    fn head_mut(&mut self, bucket: usize) -> &mut u32 {
+        match bucket {
+            OVERDUE => &mut self.overdue,
+            _ => &mut self.slots[bucket / SLOTS][bucket % SLOTS],
+        }
+    }
+
+    /// Detaches a bucket's whole list and returns its first entry
+    fn take(&mut self, bucket: usize) -> u32 {
+        self.occupied[bucket / SLOTS] &= !(1 << (bucket % SLOTS));
+        std::mem::replace(&mut self.slots[bucket / SLOTS][bucket % SLOTS], NIL)
+    }
+
+    fn link(&mut self, index: u32, bucket: usize) {
+        let head = &mut self.slots[bucket / SLOTS][bucket % SLOTS];
+        let next = std::mem::replace(head, index);
+
+        self.occupied[bucket / SLOTS] |= 1 << (bucket % SLOTS);
+
+        let entry = &mut self.entries[index as usize];
+        entry.bucket = bucket;
+        entry.prev = NIL;
+        entry.next = next;
+        if next != NIL {
+            self.entries[next as usize].prev = index;
+        }
+    }
+
+    /// Links an entry into the overdue list after every timer due no later
+    fn link_overdue(&mut self, index: u32) {
+        let deadline = self.entries[index as usize].deadline;
+        let mut prev = NIL;
+        let mut next = self.overdue;
+        while next != NIL && self.entries[next as usize].deadline <= deadline {
+            prev = next;
+            next = self.entries[next as usize].next;
+        }
+
+        let entry = &mut self.entries[index as usize];
+        entry.bucket = OVERDUE;
+        entry.prev = prev;
+        entry.next = next;
+        if prev == NIL {
+            self.overdue = index;
+        } else {
+            self.entries[prev as usize].next = index;
+        }
+        if next != NIL {
+            self.entries[next as usize].prev = index;
+        }
+    }
+
+    fn unlink(&mut self, index: u32) {
+        let entry = &self.entries[index as usize];
+        let (bucket, prev, next) = (entry.bucket, entry.prev, entry.next);
+
+        if prev == NIL {
+            *self.head_mut(bucket) = next;
+            if next == NIL && bucket != OVERDUE {
+                self.occupied[bucket / SLOTS] &= !(1 << (bucket % SLOTS));
+            }
+        } else {
+            self.entries[prev as usize].next = next;
+        }
+        if next != NIL {
+            self.entries[next as usize].prev = prev;
+        }
+    }
+
+    /// Returns an unlinked entry to the free list and takes its payload
+    fn release(&mut self, index: u32) -> T {
+        let entry = &mut self.entries[index as usize];
+        entry.generation = entry.generation.wrapping_add(1);
+        entry.next = self.free;
+        self.free = index;
+        self.len -= 1;
+        entry.payload.take().expect("scheduled entry holds a payload")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ring_buffer::RingBuffer;
+
+    fn fire_all(wheel: &mut TimerWheel<u64>, now: u64) -> Vec<(u64, u64)> {
+        let mut fired = Vec::new();
+        wheel.tick(now, |deadline, payload| fired.push((deadline, payload)));
+        fired
+    }
+
+    #[test]
+    fn test_fires_on_exact_tick_across_levels() {
+        let deadlines = [1u64, 63, 64, 65, 4_095, 4_096, 300_000, 1 << 37, u64::MAX];
+        let mut wheel = TimerWheel::new(16);
+        for &deadline in &deadlines {
+            wheel.insert(deadline, deadline).unwrap();
+        }
+
+        for &deadline in &deadlines {
+            assert!(fire_all(&mut wheel, deadline - 1).is_empty());
+            assert_eq!(fire_all(&mut wheel, deadline), vec![(deadline, deadline)]);
+        }
+        assert!(wheel.is_empty());
+    }
+
+    #[test]
+    fn test_cancel_and_stale_handles() {
+        let mut wheel = TimerWheel::new(2);
+        let first = wheel.insert(10, 1).unwrap();
+        let second = wheel.insert(10, 2).unwrap();
+        assert_eq!(wheel.insert(10, 3), Err(3));
+
+        assert_eq!(wheel.cancel(first), Some(1));
+        assert_eq!(wheel.cancel(first), None);
+
+        // The freed entry is reused with a new generation
+        let third = wheel.insert(20, 3).unwrap();
+        assert_ne!(third, first);
+        assert_eq!(wheel.cancel(first), None);
+
+        assert_eq!(fire_all(&mut wheel, 25), vec![(10, 2), (20, 3)]);
+        assert_eq!(wheel.cancel(second), None);
+        assert_eq!(wheel.len(), 0);
+    }
+
+    #[test]
+    fn test_past_deadline_fires_on_next_tick() {
+        let mut wheel = TimerWheel::new(4);
+        fire_all(&mut wheel, 100);
+        wheel.insert(50, 7).unwrap();
+        wheel.insert(100, 8).unwrap();
+        let cancelled = wheel.insert(60, 9).unwrap();
+        wheel.insert(20, 10).unwrap();
+        assert_eq!(wheel.cancel(cancelled), Some(9));
+
+        // Fired with `now` unchanged, in deadline order
+        assert_eq!(fire_all(&mut wheel, 100), vec![(20, 10), (50, 7), (100, 8)]);
+        assert!(wheel.is_empty());
+        assert_eq!(fire_all(&mut wheel, 101), vec![]);
+    }
+
+    #[test]
+    fn test_commands_from_ring() {
+        let mut wheel = TimerWheel::new(1);
+        let (mut producer, mut consumer) = RingBuffer::new(4).unwrap().split();
+
+        producer.push(TimerCommand::Schedule { deadline: 5, payload: 1 }).unwrap();
+        producer.push(TimerCommand::Schedule { deadline: 6, payload: 2 }).unwrap();
+
+        assert_eq!(wheel.drain_commands(&mut consumer), 1);
+        assert_eq!(consumer.len(), 1);
+        assert_eq!(fire_all(&mut wheel, 5), vec![(5, 1)]);
+
+        assert_eq!(wheel.drain_commands(&mut consumer), 1);
+        assert_eq!(fire_all(&mut wheel, 10), vec![(6, 2)]);
+    }
+
+    #[test]
+    fn test_cancel_commands_applied_when_full() {
+        let mut wheel = TimerWheel::new(1);
+        let (mut producer, mut consumer) = RingBuffer::new(4).unwrap().split();
+        let handle = wheel.insert(5, 1).unwrap();
+        assert!(wheel.is_full());
+
+        producer.push(TimerCommand::Cancel(handle)).unwrap();
+        producer.push(TimerCommand::Schedule { deadline: 6, payload: 2 }).unwrap();
+        producer.push(TimerCommand::Schedule { deadline: 7, payload: 3 }).unwrap();
+
+        // The cancel frees the entry the first schedule then takes
+        assert_eq!(wheel.drain_commands(&mut consumer), 2);
+        assert_eq!(consumer.len(), 1);
+        assert_eq!(fire_all(&mut wheel, 10), vec![(6, 2)]);
+
+        assert_eq!(wheel.drain_commands(&mut consumer), 1);
+        assert_eq!(fire_all(&mut wheel, 10), vec![(7, 3)]);
+    }
+}
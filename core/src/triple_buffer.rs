@@ -0,0 +1,235 @@
+use std::panic::{RefUnwindSafe, UnwindSafe};
+
+use crate::cache_padded::CachePadded;
+use crate::sync::{Arc, AtomicUsize, Ordering, UnsafeCell};
+
+/// Bits of the shared back-buffer word holding a buffer index
+const INDEX_MASK: usize = 0b11;
+
+/// Set in the shared back-buffer word when it holds a value the reader has not seen
+const DIRTY: usize = 0b100;
+
+/// A triple buffer handing the newest complete value from one writer to one reader
+///
+/// Three buffers rotate between the writer (the back buffer it fills), the
+/// reader (the front buffer it reads) and a shared middle slot. Publishing
+/// swaps the writer's buffer into the middle slot and reading swaps the middle
+/// slot out again, each with a single atomic exchange of a buffer index. Values
+/// are never copied, only the index moves, so `T` can be arbitrarily large.
+///
+/// This implementation provides:
+/// - Wait-free writes and reads: neither side ever retries or blocks
+/// - The reader always sees the most recently published value, skipping any
+///   it was too slow to see
+/// - No allocation after construction
+///
+/// # Thread Safety
+///
+/// Like [`RingBuffer`](crate::ring_buffer::RingBuffer), the buffer is split into
+/// exactly one [`Writer`] and one [`Reader`], which can live on different threads.
+///
+/// # Example
+///
+/// ```
+/// use ferrite_core::triple_buffer::TripleBuffer;
+///
+/// let (mut writer, mut reader) = TripleBuffer::new(vec![0u64; 1024]).split();
+///
+/// let handle = std::thread::spawn(move || {
+///     for version in 1..=10 {
+///         let state = writer.input_buffer();
+///         state.iter_mut().for_each(|entry| *entry = version);
+///         writer.publish();
+///     }
+/// });
+/// handle.join().unwrap();
+///
+/// assert!(reader.has_update());
+/// assert!(reader.read().iter().all(|&entry| entry == 10));
+/// assert!(!reader.has_update());
+/// ```
+pub struct TripleBuffer<T> {
+    buffers: [T; 3],
+}
+
+struct Shared<T> {
+    /// Index of the middle buffer, plus `DIRTY` when it is newer than the reader's
+    back: CachePadded<AtomicUsize>,
+    buffers: [UnsafeCell<T>; 3],
+}
+
+impl<T: Clone> TripleBuffer<T> {
+    /// Creates a new triple buffer with every buffer set to `initial`
+    ///
+    /// This is the only place `T` is cloned.
+    pub fn new(initial: T) -> Self {
+        TripleBuffer::from_buffers([initial.clone(), initial.clone(), initial])
+    }
+}
+
+impl<T> TripleBuffer<T> {
+    /// Creates a new triple buffer from three initial buffers
+    ///
+    /// The reader starts on the first buffer, the writer on the second. The
+    /// third is what the writer's next [`Writer::input_buffer`] call after the
+    /// first publish returns, so it should be a valid value too.
+    pub fn from_buffers(buffers: [T; 3]) -> Self {
+        TripleBuffer { buffers }
+    }
+
+    /// Splits the triple buffer into its writer and reader halves
+    pub fn split(self) -> (Writer<T>, Reader<T>) {
+        let [front, input, middle] = self.buffers;
+        let shared = Arc::new(Shared {
+            back: CachePadded { value: AtomicUsize::new(2) },
+            buffers: [UnsafeCell::new(front), UnsafeCell::new(input), UnsafeCell::new(middle)],
+        });
+
+        let writer = Writer {
+            shared: shared.clone(),
+            index: 1,
+        };
+        let reader = Reader { shared, index: 0 };
+
+        (writer, reader)
+    }
+}
+
+/// Writing half of a triple buffer
+pub struct Writer<T> {
+    shared: Arc<Shared<T>>,
+    /// Buffer this half owns exclusively
+    index: usize,
+}
+
+/// Reading half of a triple buffer
+pub struct Reader<T> {
+    shared: Arc<Shared<T>>,
+    /// Buffer this half owns exclusively
+    index: usize,
+}
+
+unsafe impl<T: Send> Send for Writer<T> {}
+unsafe impl<T: Send> Send for Reader<T> {}
+
+// Each half only ever touches the buffer it owns, and ownership moves with a
+// single atomic exchange, so a panic cannot leave a buffer shared.
+impl<T: UnwindSafe> UnwindSafe for Writer<T> {}
+impl<T: RefUnwindSafe> RefUnwindSafe for Writer<T> {}
+impl<T: UnwindSafe> UnwindSafe for Reader<T> {}
+impl<T: RefUnwindSafe> RefUnwindSafe for Reader<T> {}
+
+impl<T> Writer<T> {
+    /// Returns the back buffer for the next value to be built in place
+    ///
+    /// The buffer holds an older value, usually the one published two calls to
+    /// [`Writer::publish`] ago, so every field must be overwritten or updated.
+    pub fn input_buffer(&mut self) -> &mut T {
+        // Owned by this half until the next publish
+        self.shared.buffers[self.index].with_mut(|ptr| unsafe { &mut *ptr })
+    }
+
+    /// Makes the back buffer the newest value and takes a free buffer to write into next
+    pub fn publish(&mut self) {
+        // Release publishes the buffer's contents; Acquire takes over the
+        // returned buffer only after the reader has finished with it.
+        let previous = self.shared.back.value.swap(self.index | DIRTY, Ordering::AcqRel);
+        self.index = previous & INDEX_MASK;
+    }
+
+    /// Moves `value` into the back buffer and publishes it
+    pub fn write(&mut self, value: T) {
+        *self.input_buffer() = value;
+        self.publish();
+    }
+
+    /// Checks whether the last published value has not been picked up by the reader yet
+    pub fn is_unread(&self) -> bool {
+        self.shared.back.value.load(Ordering::Relaxed) & DIRTY != 0
+    }
+}
+
+impl<T> Reader<T> {
+    /// Returns the newest published value
+    ///
+    /// Takes over the newest buffer if one was published since the last call,
+    /// otherwise returns the same value as before.
+    pub fn read(&mut self) -> &T {
+        if self.has_update() {
+            // Release hands the old front buffer back; Acquire pairs with the
+            // writer's publish so the new buffer's contents are visible.
+            let previous = self.shared.back.value.swap(self.index, Ordering::AcqRel);
+            self.index = previous & INDEX_MASK;
+        }
+        self.shared.buffers[self.index].with(|ptr| unsafe { &*ptr })
+    }
+
+    /// Checks whether a value newer than the last one read has been published
+    pub fn has_update(&self) -> bool {
+        self.shared.back.value.load(Ordering::Relaxed) & DIRTY != 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reader_sees_newest_value() {
+        let (mut writer, mut reader) = TripleBuffer::new(0u32).split();
+        assert!(!reader.has_update());
+        assert_eq!(*reader.read(), 0);
+
+        writer.write(1);
+        writer.write(2);
+        assert!(writer.is_unread());
+        assert_eq!(*reader.read(), 2);
+        assert!(!writer.is_unread());
+        assert_eq!(*reader.read(), 2);
+
+        writer.write(3);
+        assert_eq!(*reader.read(), 3);
+    }
+
+    #[test]
+    fn test_buffers_rotate_without_copying() {
+        let buffers = [vec![0u8; 64], vec![1u8; 64], vec![2u8; 64]];
+        let addresses: Vec<_> = buffers.iter().map(|buffer| buffer.as_ptr()).collect();
+        let (mut writer, mut reader) = TripleBuffer::from_buffers(buffers).split();
+
+        for round in 0..10u8 {
+            let input = writer.input_buffer();
+            assert!(addresses.contains(&input.as_ptr()));
+            input.fill(round);
+            writer.publish();
+
+            let output = reader.read();
+            assert!(addresses.contains(&output.as_ptr()));
+            assert!(output.iter().all(|&byte| byte == round));
+        }
+    }
+
+    #[test]
+    fn test_concurrent_values_are_complete() {
+        const VERSIONS: u64 = 10_000;
+        let (mut writer, mut reader) = TripleBuffer::new([0u64; 16]).split();
+
+        let handle = std::thread::spawn(move || {
+            for version in 1..=VERSIONS {
+                writer.input_buffer().fill(version);
+                writer.publish();
+            }
+        });
+
+        let mut last = 0;
+        while last < VERSIONS {
+            let value = reader.read();
+            assert!(value.iter().all(|&entry| entry == value[0]));
+            assert!(value[0] >= last);
+            last = value[0];
+            std::thread::yield_now();
+        }
+
+        handle.join().unwrap();
+    }
+}
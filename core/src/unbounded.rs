@@ -0,0 +1,397 @@
+use std::cell::UnsafeCell;
+use std::marker::PhantomData;
+use std::mem::MaybeUninit;
+use std::panic::{RefUnwindSafe, UnwindSafe};
+use std::ptr;
+use std::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use crate::cache_padded::CachePadded;
+use crate::ring_buffer::{self, RingBuffer, RingBufferError};
+
+/// Capacity of the ring that hands drained blocks back to the producer;
+/// it keeps up to one less than this many blocks for reuse
+const BLOCK_CACHE: usize = 4;
+
+/// An unbounded lock-free single-producer single-consumer (SPSC) queue
+///
+/// Items are stored in a linked list of fixed-size blocks. Within a block,
+/// slots are addressed with the same power-of-two masking as
+/// [`RingBuffer`]. When the producer fills a block it links a new one; when
+/// the consumer drains a block it hands it back through a small ring, so a
+/// queue that stays within a few blocks stops allocating once warmed up.
+///
+/// This implementation provides:
+/// - Pushes that never fail
+/// - Cache-line padding between the producer and consumer positions
+/// - One allocation per block, and none while drained blocks can be reused
+///
+/// # Thread Safety
+///
+/// Like [`RingBuffer`], the queue is split into exactly one [`Producer`] and
+/// one [`Consumer`], which can live on different threads.
+///
+/// # Example
+///
+/// ```
+/// use ferrite_core::unbounded::UnboundedQueue;
+///
+/// let queue = UnboundedQueue::<u32>::new(64).unwrap();
+/// let (mut producer, mut consumer) = queue.split();
+///
+/// std::thread::spawn(move || {
+///     for i in 0..1_000 {
+///         producer.push(i);
+///     }
+/// })
+/// .join()
+/// .unwrap();
+///
+/// assert_eq!(consumer.len(), 1_000);
+/// for i in 0..1_000 {
+///     assert_eq!(consumer.pop(), Ok(i));
+/// }
+/// ```
+pub struct UnboundedQueue<T> {
+    block_size: usize,
+    _marker: PhantomData<T>,
+}
+
+/// A fixed-size run of slots linked to the block filled after it
+struct Block<T> {
+    slots: Box<[UnsafeCell<MaybeUninit<T>>]>,
+    next: AtomicPtr<Block<T>>,
+}
+
+impl<T> Block<T> {
+    fn new(block_size: usize) -> Box<Self> {
+        Box::new(Block {
+            slots: (0..block_size).map(|_| UnsafeCell::new(MaybeUninit::uninit())).collect(),
+            next: AtomicPtr::new(ptr::null_mut()),
+        })
+    }
+}
+
+struct Shared<T> {
+    /// Number of items pushed
+    head: CachePadded<AtomicUsize>,
+    /// Number of items popped
+    tail: CachePadded<AtomicUsize>,
+    /// The consumer's current block, the start of the chain on teardown
+    front: AtomicPtr<Block<T>>,
+    mask: usize,
+}
+
+impl<T> UnboundedQueue<T> {
+    /// Creates a new queue that grows in blocks of the specified size
+    ///
+    /// # Arguments
+    ///
+    /// * `block_size` - Slots per block. Must be a power of two and greater than 0.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(UnboundedQueue<T>)` - A new queue
+    /// * `Err(RingBufferError)` - If the block size is invalid
+    pub fn new(block_size: usize) -> Result<Self, RingBufferError> {
+        if block_size == 0 || !block_size.is_power_of_two() {
+            return Err(RingBufferError::InvalidCapacity(block_size));
+        }
+
+        Ok(UnboundedQueue {
+            block_size,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Returns the number of slots per block
+    pub fn block_size(&self) -> usize {
+        self.block_size
+    }
+
+    /// Allocates the first block and splits the queue into producer and consumer halves
+    pub fn split(self) -> (Producer<T>, Consumer<T>) {
+        let first = Box::into_raw(Block::new(self.block_size));
+        let (recycle, reuse) = RingBuffer::new(BLOCK_CACHE)
+            .expect("block cache capacity is a power of two")
+            .split();
+
+        let shared = Arc::new(Shared {
+            head: CachePadded { value: AtomicUsize::new(0) },
+            tail: CachePadded { value: AtomicUsize::new(0) },
+            front: AtomicPtr::new(first),
+            mask: self.block_size - 1,
+        });
+
+        let producer = Producer {
+            shared: shared.clone(),
+            block: first,
+            head: 0,
+            reuse,
+        };
+        let consumer = Consumer {
+            shared,
+            block: first,
+            tail: 0,
+            recycle,
+        };
+
+        (producer, consumer)
+    }
+}
+
+/// Producer half of an unbounded queue
+pub struct Producer<T> {
+    shared: Arc<Shared<T>>,
+    /// Block holding the next position to write
+    block: *mut Block<T>,
+    /// Local copy of the next position to write
+    head: usize,
+    /// Drained blocks handed back by the consumer
+    reuse: ring_buffer::Consumer<Box<Block<T>>>,
+}
+
+/// Consumer half of an unbounded queue
+pub struct Consumer<T> {
+    shared: Arc<Shared<T>>,
+    /// Block holding the next position to read
+    block: *mut Block<T>,
+    /// Local copy of the next position to read
+    tail: usize,
+    /// Hands drained blocks back to the producer
+    recycle: ring_buffer::Producer<Box<Block<T>>>,
+}
+
+unsafe impl<T: Send> Send for Producer<T> {}
+unsafe impl<T: Send> Send for Consumer<T> {}
+
+// Items are published only once written and positions only advance past
+// slots that have been moved out, as in `RingBuffer`.
+impl<T: UnwindSafe> UnwindSafe for Producer<T> {}
+impl<T: RefUnwindSafe> RefUnwindSafe for Producer<T> {}
+impl<T: UnwindSafe> UnwindSafe for Consumer<T> {}
+impl<T: RefUnwindSafe> RefUnwindSafe for Consumer<T> {}
+
+impl<T> Producer<T> {
+    /// Pushes an item into the queue
+    ///
+    /// Links a new block first if the current one is full, reusing a drained
+    /// block when the consumer has handed one back.
+    pub fn push(&mut self, value: T) {
+        let head = self.head;
+        let index = head & self.shared.mask;
+
+        if index == 0 && head != 0 {
+            let block = match self.reuse.pop() {
+                Ok(block) => {
+                    block.next.store(ptr::null_mut(), Ordering::Relaxed);
+                    Box::into_raw(block)
+                }
+                Err(_) => Box::into_raw(Block::new(self.shared.mask + 1)),
+            };
+            // Linked before the item is published, so the consumer always finds it
+            unsafe { (*self.block).next.store(block, Ordering::Release) };
+            self.block = block;
+        }
+
+        // Outside the published range, so the consumer cannot touch it until
+        // the store below.
+        unsafe { (*(*self.block).slots[index].get()).write(value) };
+
+        self.head = head.wrapping_add(1);
+        self.shared.head.value.store(self.head, Ordering::Release);
+    }
+
+    /// Returns the number of items pushed and not yet popped
+    pub fn len(&self) -> usize {
+        self.head.wrapping_sub(self.shared.tail.value.load(Ordering::Acquire))
+    }
+
+    /// Checks if the queue is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<T> Consumer<T> {
+    /// Attempts to pop an item from the queue
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(T)` - Successfully popped an item
+    /// * `Err(RingBufferError::BufferEmpty)` - Queue is empty
+    pub fn pop(&mut self) -> Result<T, RingBufferError> {
+        let tail = self.tail;
+        if tail == self.shared.head.value.load(Ordering::Acquire) {
+            return Err(RingBufferError::BufferEmpty);
+        }
+
+        let index = tail & self.shared.mask;
+        if index == 0 && tail != 0 {
+            self.advance_block();
+        }
+
+        // Published by the producer's release store and read exactly once
+        let value = unsafe { (*(*self.block).slots[index].get()).assume_init_read() };
+
+        self.tail = tail.wrapping_add(1);
+        self.shared.tail.value.store(self.tail, Ordering::Release);
+        Ok(value)
+    }
+
+    /// Returns the number of items pushed and not yet popped
+    pub fn len(&self) -> usize {
+        self.shared.head.value.load(Ordering::Acquire).wrapping_sub(self.tail)
+    }
+
+    /// Checks if the queue is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Moves to the next block and hands the drained one back to the producer
+    fn advance_block(&mut self) {
+        let drained = self.block;
+        // The producer linked it before publishing the item being popped
+        let next = unsafe { (*drained).next.load(Ordering::Acquire) };
+        self.block = next;
+        self.shared.front.store(next, Ordering::Relaxed);
+
+        // Every slot has been moved out and the producer has moved on
+        let drained = unsafe { Box::from_raw(drained) };
+        if !self.recycle.is_full() {
+            let _ = self.recycle.push(drained);
+        }
+    }
+}
+
+impl<T> Drop for Shared<T> {
+    fn drop(&mut self) {
+        // Both halves are gone, so the chain and both positions are final
+        let head = *self.head.value.get_mut();
+        let tail = *self.tail.value.get_mut();
+        let mut block = *self.front.get_mut();
+
+        // The consumer only moves to the next block when it pops from it, so
+        // after draining a block exactly it is still on the drained one.
+        let mut start = if tail == 0 { 0 } else { tail.wrapping_sub(1) & !self.mask };
+
+        while !block.is_null() {
+            let mut current = unsafe { Box::from_raw(block) };
+            block = *current.next.get_mut();
+
+            for (index, slot) in current.slots.iter_mut().enumerate() {
+                let position = start.wrapping_add(index);
+                if position.wrapping_sub(tail) < head.wrapping_sub(tail) {
+                    unsafe { slot.get_mut().assume_init_drop() };
+                }
+            }
+            start = start.wrapping_add(self.mask + 1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_invalid_block_size() {
+        assert!(matches!(
+            UnboundedQueue::<u32>::new(0),
+            Err(RingBufferError::InvalidCapacity(0))
+        ));
+        assert!(matches!(
+            UnboundedQueue::<u32>::new(3),
+            Err(RingBufferError::InvalidCapacity(3))
+        ));
+    }
+
+    #[test]
+    fn test_grows_across_blocks() {
+        let queue = UnboundedQueue::<u32>::new(4).unwrap();
+        let (mut producer, mut consumer) = queue.split();
+        assert_eq!(consumer.pop(), Err(RingBufferError::BufferEmpty));
+
+        for i in 0..100 {
+            producer.push(i);
+        }
+        assert_eq!(producer.len(), 100);
+
+        for i in 0..100 {
+            assert_eq!(consumer.pop(), Ok(i));
+        }
+        assert!(consumer.is_empty());
+        assert_eq!(consumer.pop(), Err(RingBufferError::BufferEmpty));
+    }
+
+    #[test]
+    fn test_drained_blocks_are_reused() {
+        let queue = UnboundedQueue::<u32>::new(2).unwrap();
+        let (mut producer, mut consumer) = queue.split();
+
+        producer.push(0);
+        producer.push(1);
+        producer.push(2);
+        let second = producer.block;
+        assert_eq!(consumer.pop(), Ok(0));
+        assert_eq!(consumer.pop(), Ok(1));
+        let first = consumer.block;
+        assert_eq!(consumer.pop(), Ok(2));
+        assert_eq!(consumer.block, second);
+
+        producer.push(3);
+        producer.push(4);
+        assert_eq!(producer.block, first);
+        assert_eq!(consumer.pop(), Ok(3));
+        assert_eq!(consumer.pop(), Ok(4));
+    }
+
+    #[test]
+    fn test_remaining_items_dropped() {
+        let item = Arc::new(());
+
+        // Covers a consumer stopping mid-block and exactly at a block boundary
+        for popped in 0..=11 {
+            let queue = UnboundedQueue::new(4).unwrap();
+            let (mut producer, mut consumer) = queue.split();
+
+            for _ in 0..11 {
+                producer.push(item.clone());
+            }
+            for _ in 0..popped {
+                consumer.pop().unwrap();
+            }
+            assert_eq!(Arc::strong_count(&item), 12 - popped);
+
+            drop(producer);
+            drop(consumer);
+            assert_eq!(Arc::strong_count(&item), 1);
+        }
+    }
+
+    #[test]
+    fn test_concurrent_push_pop() {
+        const ITEMS: u64 = 50_000;
+        let queue = UnboundedQueue::<u64>::new(32).unwrap();
+        let (mut producer, mut consumer) = queue.split();
+
+        let handle = std::thread::spawn(move || {
+            for i in 0..ITEMS {
+                producer.push(i);
+            }
+        });
+
+        let mut expected = 0;
+        while expected < ITEMS {
+            match consumer.pop() {
+                Ok(value) => {
+                    assert_eq!(value, expected);
+                    expected += 1;
+                }
+                Err(_) => std::thread::yield_now(),
+            }
+        }
+        handle.join().unwrap();
+    }
+}
@@ -1,19 +1,20 @@
-use ::core::ring_buffer::{RingBuffer, RingBufferError};
+use ferrite_core::ring_buffer::{RingBuffer, RingBufferError};
 use std::thread;
 use std::time::Duration;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::panic::{self, AssertUnwindSafe, RefUnwindSafe, UnwindSafe};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
 
 #[test]
 fn test_single_element_buffer() {
     // Smallest possible buffer
     let buffer = RingBuffer::<u32>::new(1).unwrap();
-    let (mut producer, mut consumer) = buffer.split();
+    let (mut producer, consumer) = buffer.split();
     
     // Should be empty initially
     assert!(consumer.is_empty());
     assert_eq!(consumer.len(), 0);
-    assert!(!producer.is_full());
+    assert!(producer.is_full());
     
     // Can't push even one item (capacity - 1 = 0)
     assert_eq!(producer.push(42), Err(RingBufferError::BufferFull));
@@ -48,8 +49,8 @@ fn test_large_buffer() {
         producer.push(i).unwrap();
     }
     
-    assert_eq!(consumer.len(), half);
-    assert_eq!(producer.remaining_capacity(), (1 << 20) - 1 - half);
+    assert_eq!(consumer.len(), half as usize);
+    assert_eq!(producer.remaining_capacity(), (1 << 20) - 1 - half as usize);
     
     // Consume all
     for i in 0..half {
@@ -78,8 +79,6 @@ fn test_zero_sized_type() {
 
 #[test]
 fn test_drop_semantics() {
-    use std::sync::atomic::{AtomicUsize, Ordering};
-    
     static DROP_COUNT: AtomicUsize = AtomicUsize::new(0);
     
     struct DropCounter;
@@ -107,9 +106,7 @@ fn test_drop_semantics() {
         // Remaining 2 items should be dropped when buffer is dropped
     }
     
-    // Note: In our implementation, items aren't dropped until consumed
-    // This is different from some implementations that drop on buffer drop
-    assert_eq!(DROP_COUNT.load(Ordering::Relaxed), 1);
+    assert_eq!(DROP_COUNT.load(Ordering::Relaxed), 3);
 }
 
 #[test]
@@ -248,4 +245,201 @@ fn test_memory_barriers() {
     
     producer_handle.join().unwrap();
     consumer_handle.join().unwrap();
-}
\ No newline at end of file
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_notifier_no_lost_wakeups() {
+    use std::os::fd::AsRawFd;
+
+    let buffer = RingBuffer::<u32>::new(8).unwrap().with_notifier().unwrap();
+    let (mut producer, mut consumer) = buffer.split();
+
+    let producer_handle = thread::spawn(move || {
+        for i in 0..10_000 {
+            while producer.push(i).is_err() {
+                thread::yield_now();
+            }
+            if i % 1000 == 0 {
+                thread::sleep(Duration::from_micros(200));
+            }
+        }
+    });
+
+    let consumer_handle = thread::spawn(move || {
+        let mut expected = 0;
+        while expected < 10_000 {
+            match consumer.pop() {
+                Ok(val) => {
+                    assert_eq!(val, expected);
+                    expected += 1;
+                }
+                Err(_) => {
+                    if consumer.arm_notifier() {
+                        // Block like an epoll loop would; a lost wakeup hangs here
+                        let mut pfd = libc::pollfd {
+                            fd: consumer.as_raw_fd(),
+                            events: libc::POLLIN,
+                            revents: 0,
+                        };
+                        let ready = unsafe { libc::poll(&mut pfd, 1, 5_000) };
+                        assert_eq!(ready, 1, "consumer was never woken");
+                    }
+                }
+            }
+        }
+    });
+
+    producer_handle.join().unwrap();
+    consumer_handle.join().unwrap();
+}
+
+/// Counts drops per id and panics when dropping the configured id
+struct PanicOnDrop<'a> {
+    id: usize,
+    panic_id: usize,
+    drops: &'a [AtomicUsize],
+}
+
+impl Drop for PanicOnDrop<'_> {
+    fn drop(&mut self) {
+        self.drops[self.id].fetch_add(1, Ordering::SeqCst);
+        if self.id == self.panic_id {
+            panic!("drop of item {} panicked", self.id);
+        }
+    }
+}
+
+fn drop_counters(n: usize) -> Vec<AtomicUsize> {
+    (0..n).map(|_| AtomicUsize::new(0)).collect()
+}
+
+#[test]
+fn test_panicking_drop_during_teardown() {
+    let drops = drop_counters(6);
+    let buffer = RingBuffer::<PanicOnDrop>::new(8).unwrap();
+    let (mut producer, mut consumer) = buffer.split();
+
+    // Wrap the live range around the end of the buffer
+    for id in 0..6 {
+        producer.push(PanicOnDrop { id, panic_id: usize::MAX, drops: &drops }).unwrap();
+        std::mem::forget(consumer.pop().unwrap());
+    }
+    drops.iter().for_each(|count| count.store(0, Ordering::SeqCst));
+    for id in 0..6 {
+        producer.push(PanicOnDrop { id, panic_id: 1, drops: &drops }).unwrap();
+    }
+
+    let result = panic::catch_unwind(AssertUnwindSafe(move || {
+        drop(producer);
+        drop(consumer);
+    }));
+
+    assert!(result.is_err());
+    // Every item was dropped exactly once, including those after the panic
+    for count in &drops {
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+    }
+}
+
+#[test]
+fn test_push_iter_panicking_adapter() {
+    let buffer = RingBuffer::<u32>::new(16).unwrap();
+    let (mut producer, mut consumer) = buffer.split();
+
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        producer.push_iter((0..10).map(|i| if i == 5 { panic!("adapter panicked") } else { i }));
+    }));
+    assert!(result.is_err());
+
+    // Items produced before the panic were published, nothing after it
+    assert_eq!(consumer.len(), 5);
+    for i in 0..5 {
+        assert_eq!(consumer.pop(), Ok(i));
+    }
+    assert!(consumer.is_empty());
+
+    producer.push(42).unwrap();
+    assert_eq!(consumer.pop(), Ok(42));
+}
+
+#[test]
+fn test_pop_batch_panicking_closure() {
+    let drops = drop_counters(6);
+    let buffer = RingBuffer::<PanicOnDrop>::new(8).unwrap();
+    let (mut producer, mut consumer) = buffer.split();
+    for id in 0..6 {
+        producer.push(PanicOnDrop { id, panic_id: usize::MAX, drops: &drops }).unwrap();
+    }
+
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        consumer.pop_batch(6, |item| {
+            if item.id == 2 {
+                panic!("handler panicked");
+            }
+        });
+    }));
+    assert!(result.is_err());
+
+    // The item the closure was handed was dropped during unwinding, once
+    assert_eq!(consumer.len(), 3);
+    for (id, count) in drops.iter().enumerate() {
+        assert_eq!(count.load(Ordering::SeqCst), usize::from(id <= 2));
+    }
+
+    let remaining: Vec<usize> = std::iter::from_fn(|| consumer.pop().ok()).map(|item| item.id).collect();
+    assert_eq!(remaining, vec![3, 4, 5]);
+    for count in &drops {
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+    }
+}
+
+#[test]
+fn test_transaction_commit_with_panicking_drop() {
+    let drops = drop_counters(4);
+    let buffer = RingBuffer::<PanicOnDrop>::new(8).unwrap();
+    let (mut producer, mut consumer) = buffer.split();
+    for id in 0..4 {
+        producer.push(PanicOnDrop { id, panic_id: 1, drops: &drops }).unwrap();
+    }
+
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        let txn = consumer.begin();
+        for _ in 0..3 {
+            txn.pop().unwrap();
+        }
+        txn.commit();
+    }));
+    assert!(result.is_err());
+
+    // Items 0 and 1 are consumed; 2 was popped in the transaction but not yet
+    // released when the drop of 1 panicked, so it is still in the buffer
+    assert_eq!(consumer.len(), 2);
+    assert_eq!(consumer.pop().map(|item| item.id).ok(), Some(2));
+    assert_eq!(drops[0].load(Ordering::SeqCst), 1);
+    assert_eq!(drops[1].load(Ordering::SeqCst), 1);
+    assert_eq!(drops[2].load(Ordering::SeqCst), 1);
+    assert_eq!(drops[3].load(Ordering::SeqCst), 0);
+}
+
+#[test]
+fn test_halves_are_unwind_safe() {
+    fn assert_unwind_safe<T: UnwindSafe + RefUnwindSafe>() {}
+    assert_unwind_safe::<ferrite_core::ring_buffer::Producer<String>>();
+    assert_unwind_safe::<ferrite_core::ring_buffer::Consumer<String>>();
+
+    let buffer = RingBuffer::<String>::new(4).unwrap();
+    let (mut producer, consumer) = buffer.split();
+    producer.push("before".to_string()).unwrap();
+
+    // The consumer can be moved into `catch_unwind` without `AssertUnwindSafe`
+    let mut consumer = panic::catch_unwind(move || {
+        let mut consumer = consumer;
+        assert_eq!(consumer.pop().unwrap(), "before");
+        consumer
+    })
+    .unwrap();
+
+    producer.push("after".to_string()).unwrap();
+    assert_eq!(consumer.pop().unwrap(), "after");
+}
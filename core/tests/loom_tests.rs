@@ -1,7 +1,13 @@
 #![cfg(loom)]
 
 use loom::thread;
-use ::core::ring_buffer::RingBuffer;
+use ferrite_core::deque::{Deque, Steal};
+use ferrite_core::disruptor::Disruptor;
+use ferrite_core::mpmc::MpmcQueue;
+use ferrite_core::mpsc::MpscQueue;
+use ferrite_core::ring_buffer::{RingBuffer, RingBufferError};
+use ferrite_core::seqlock::SeqLock;
+use ferrite_core::triple_buffer::TripleBuffer;
 
 #[test]
 fn loom_spsc_basic() {
@@ -168,4 +174,312 @@ fn loom_wrap_around() {
         
         assert_eq!(values, vec![0, 1, 2, 3, 4]);
     });
-}
\ No newline at end of file
+}
+
+#[test]
+fn loom_mpsc_two_producers() {
+    let mut config = loom::model::Builder::new();
+    config.preemption_bound = Some(3);
+    
+    config.check(|| {
+        let queue = MpscQueue::<u32>::new(4).unwrap();
+        let (producer, mut consumer) = queue.split();
+        let producer2 = producer.clone();
+        
+        let first = thread::spawn(move || {
+            producer.push(1).unwrap();
+            producer.push(2).unwrap();
+        });
+        
+        let second = thread::spawn(move || {
+            producer2.push(10).unwrap();
+        });
+        
+        let mut values = Vec::new();
+        while values.len() < 3 {
+            match consumer.pop() {
+                Ok(val) => values.push(val),
+                Err(_) => thread::yield_now(),
+            }
+        }
+        
+        first.join().unwrap();
+        second.join().unwrap();
+        
+        // Items from one producer keep their order
+        let ones: Vec<_> = values.iter().filter(|&&v| v < 10).copied().collect();
+        assert_eq!(ones, vec![1, 2]);
+        assert!(values.contains(&10));
+        assert_eq!(consumer.pop(), Err(RingBufferError::BufferEmpty));
+    });
+}
+
+#[test]
+fn loom_mpsc_full_queue() {
+    let mut config = loom::model::Builder::new();
+    config.preemption_bound = Some(3);
+    
+    config.check(|| {
+        let queue = MpscQueue::<u32>::new(2).unwrap();
+        let (producer, mut consumer) = queue.split();
+        let producer2 = producer.clone();
+        
+        let first = thread::spawn(move || {
+            (0..2).filter(|&i| producer.push(i).is_ok()).count()
+        });
+        
+        let second = thread::spawn(move || {
+            (10..12).filter(|&i| producer2.push(i).is_ok()).count()
+        });
+        
+        let pushed = first.join().unwrap() + second.join().unwrap();
+        
+        // Nothing was popped, so exactly the capacity fits
+        assert_eq!(pushed, 2);
+        let mut popped = 0;
+        while consumer.pop().is_ok() {
+            popped += 1;
+        }
+        assert_eq!(popped, 2);
+    });
+}
+
+#[test]
+fn loom_mpsc_memory_ordering() {
+    let mut config = loom::model::Builder::new();
+    config.preemption_bound = Some(3);
+    
+    config.check(|| {
+        let queue = MpscQueue::<Box<u32>>::new(2).unwrap();
+        let (producer, mut consumer) = queue.split();
+        let producer2 = producer.clone();
+        
+        let first = thread::spawn(move || {
+            while producer.push(Box::new(100)).is_err() {
+                thread::yield_now();
+            }
+        });
+        
+        let second = thread::spawn(move || {
+            while producer2.push(Box::new(200)).is_err() {
+                thread::yield_now();
+            }
+        });
+        
+        let mut sum = 0;
+        for _ in 0..2 {
+            loop {
+                match consumer.pop() {
+                    Ok(boxed) => {
+                        sum += *boxed;
+                        break;
+                    }
+                    Err(_) => thread::yield_now(),
+                }
+            }
+        }
+        
+        first.join().unwrap();
+        second.join().unwrap();
+        assert_eq!(sum, 300);
+    });
+}
+
+#[test]
+fn loom_mpmc_two_consumers() {
+    let mut config = loom::model::Builder::new();
+    config.preemption_bound = Some(3);
+    
+    config.check(|| {
+        let queue = MpmcQueue::<u32>::new(2).unwrap();
+        let (sender, receiver) = queue.split();
+        let receiver2 = receiver.clone();
+        
+        let producer = thread::spawn(move || {
+            sender.try_push(1).unwrap();
+            sender.try_push(2).unwrap();
+        });
+        
+        let consumer = thread::spawn(move || receiver2.try_pop().ok());
+        let first = receiver.try_pop().ok();
+        
+        producer.join().unwrap();
+        let second = consumer.join().unwrap();
+        
+        // Every item is received exactly once
+        let mut values: Vec<_> = first.into_iter().chain(second).collect();
+        while let Ok(val) = receiver.try_pop() {
+            values.push(val);
+        }
+        values.sort_unstable();
+        assert_eq!(values, vec![1, 2]);
+    });
+}
+
+#[test]
+fn loom_disruptor_dependency_barrier() {
+    let mut config = loom::model::Builder::new();
+    config.preemption_bound = Some(3);
+    
+    config.check(|| {
+        let seen = loom::sync::Arc::new(loom::sync::atomic::AtomicUsize::new(0));
+        let mut disruptor = Disruptor::new(2, || 0usize).unwrap();
+        let first = disruptor.add_handler(&[], |event: &usize, _: u64, _: bool| {
+            assert_ne!(*event, 0);
+        });
+        let counter = seen.clone();
+        disruptor.add_handler(&[first], move |event: &usize, _: u64, _: bool| {
+            counter.fetch_add(*event, loom::sync::atomic::Ordering::Relaxed);
+        });
+        let (mut publisher, mut processors) = disruptor.split();
+        let mut second = processors.pop().unwrap();
+        let mut first = processors.pop().unwrap();
+        
+        let publisher_handle = thread::spawn(move || {
+            publisher.try_publish_with(|event, _| *event = 1).unwrap();
+            publisher.try_publish_with(|event, _| *event = 2).unwrap();
+            // Only reusable once both handlers are done with the first event
+            publisher.try_publish_with(|event, _| *event = 4).is_ok()
+        });
+        
+        let first_handle = thread::spawn(move || {
+            first.poll();
+            first
+        });
+        second.poll();
+        
+        let third_published = publisher_handle.join().unwrap();
+        let mut first = first_handle.join().unwrap();
+        while first.poll() > 0 {}
+        while second.poll() > 0 {}
+        
+        let expected = if third_published { 7 } else { 3 };
+        assert_eq!(seen.load(loom::sync::atomic::Ordering::Relaxed), expected);
+    });
+}
+
+#[test]
+fn loom_seqlock_no_torn_reads() {
+    let mut config = loom::model::Builder::new();
+    config.preemption_bound = Some(3);
+    
+    config.check(|| {
+        let (mut writer, reader) = SeqLock::new([0usize; 2]).split();
+        
+        let writer_handle = thread::spawn(move || {
+            writer.write(&[1, 1]);
+            writer.write(&[2, 2]);
+        });
+        
+        // Every successful read is a value the writer actually wrote
+        for _ in 0..2 {
+            if let Some(value) = reader.try_read() {
+                assert_eq!(value[0], value[1]);
+            }
+        }
+        
+        writer_handle.join().unwrap();
+        assert_eq!(reader.try_read(), Some([2, 2]));
+    });
+}
+
+#[test]
+fn loom_seqlock_readers_see_monotonic_values() {
+    let mut config = loom::model::Builder::new();
+    config.preemption_bound = Some(2);
+    
+    config.check(|| {
+        let (mut writer, reader) = SeqLock::new([0usize; 2]).split();
+        let other = reader.clone();
+        
+        let writer_handle = thread::spawn(move || {
+            writer.write(&[1, 1]);
+            writer.write(&[2, 2]);
+        });
+        
+        let reader_handle = thread::spawn(move || {
+            let first = other.try_read();
+            let second = other.try_read();
+            if let (Some(first), Some(second)) = (first, second) {
+                assert!(second[0] >= first[0]);
+            }
+            first.into_iter().chain(second).for_each(|value| assert_eq!(value[0], value[1]));
+        });
+        
+        if let Some(value) = reader.try_read() {
+            assert_eq!(value[0], value[1]);
+        }
+        
+        writer_handle.join().unwrap();
+        reader_handle.join().unwrap();
+    });
+}
+
+#[test]
+fn loom_triple_buffer_handoff() {
+    let mut config = loom::model::Builder::new();
+    config.preemption_bound = Some(3);
+    
+    config.check(|| {
+        let (mut writer, mut reader) = TripleBuffer::new(0u32).split();
+        
+        let writer_handle = thread::spawn(move || {
+            for value in 1..=3 {
+                writer.write(value);
+            }
+        });
+        
+        let first = *reader.read();
+        let second = *reader.read();
+        assert!(second >= first);
+        
+        writer_handle.join().unwrap();
+        assert_eq!(*reader.read(), 3);
+    });
+}
+
+#[test]
+fn loom_deque_pop_races_steal() {
+    let mut config = loom::model::Builder::new();
+    config.preemption_bound = Some(3);
+    
+    config.check(|| {
+        let (mut worker, stealer) = Deque::<u32>::new(4).unwrap().split();
+        worker.push(7).unwrap();
+        
+        let stealer_handle = thread::spawn(move || stealer.steal());
+        
+        let popped = worker.pop();
+        let stolen = stealer_handle.join().unwrap();
+        
+        // The last item goes to exactly one side
+        match (popped, stolen) {
+            (Some(7), Steal::Empty) | (Some(7), Steal::Retry) | (None, Steal::Success(7)) => {}
+            other => panic!("unexpected outcome {:?}", other),
+        }
+        assert!(worker.is_empty());
+    });
+}
+
+#[test]
+fn loom_deque_push_steal_conserves_items() {
+    let mut config = loom::model::Builder::new();
+    config.preemption_bound = Some(3);
+    
+    config.check(|| {
+        let (mut worker, stealer) = Deque::<u32>::new(2).unwrap().split();
+        
+        let stealer_handle = thread::spawn(move || stealer.steal().success());
+        
+        worker.push(1).unwrap();
+        worker.push(2).unwrap();
+        let stolen = stealer_handle.join().unwrap();
+        
+        let mut seen: Vec<u32> = stolen.into_iter().collect();
+        while let Some(value) = worker.pop() {
+            seen.push(value);
+        }
+        seen.sort_unstable();
+        assert_eq!(seen, vec![1, 2]);
+    });
+}
@@ -1,5 +1,10 @@
 use proptest::prelude::*;
-use ::core::ring_buffer::{RingBuffer, RingBufferError};
+use ferrite_core::deque::{Deque, Steal};
+use ferrite_core::mpsc::MpscQueue;
+use ferrite_core::ring_buffer::{RingBuffer, RingBufferError};
+use ferrite_core::timer_wheel::TimerWheel;
+use ferrite_core::unbounded::UnboundedQueue;
+use std::collections::VecDeque;
 use std::thread;
 use std::sync::mpsc;
 
@@ -19,7 +24,7 @@ proptest! {
 
     #[test]
     fn prop_push_pop_consistency(
-        capacity in (1usize..=10).map(|n| 1 << n), // Powers of 2: 2, 4, 8, ..., 1024
+        capacity in (1usize..=10).prop_map(|n| 1 << n), // Powers of 2: 2, 4, 8, ..., 1024
         operations in prop::collection::vec(0u32..1000, 0..100)
     ) {
         let buffer = RingBuffer::<u32>::new(capacity).unwrap();
@@ -57,7 +62,7 @@ proptest! {
 
     #[test]
     fn prop_len_consistency(
-        capacity in (1usize..=8).map(|n| 1 << n),
+        capacity in (1usize..=8).prop_map(|n| 1 << n),
         push_count in 0usize..20,
         pop_count in 0usize..20
     ) {
@@ -91,7 +96,7 @@ proptest! {
 
     #[test]
     fn prop_concurrent_consistency(
-        capacity in (2usize..=8).map(|n| 1 << n),
+        capacity in (2usize..=8).prop_map(|n| 1 << n),
         values in prop::collection::vec(0u32..1000, 10..100)
     ) {
         let buffer = RingBuffer::<u32>::new(capacity).unwrap();
@@ -134,4 +139,184 @@ proptest! {
         
         prop_assert_eq!(values, received);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn prop_mpsc_matches_model(
+        capacity in (0usize..=6).prop_map(|n| 1 << n),
+        operations in prop::collection::vec(prop::option::of(0u32..1000), 0..200)
+    ) {
+        let queue = MpscQueue::<u32>::new(capacity).unwrap();
+        let (producer, mut consumer) = queue.split();
+        let mut model = VecDeque::new();
+        
+        // `Some` pushes a value, `None` pops one
+        for operation in operations {
+            match operation {
+                Some(value) => {
+                    if model.len() < capacity {
+                        prop_assert_eq!(producer.push(value), Ok(()));
+                        model.push_back(value);
+                    } else {
+                        prop_assert_eq!(producer.push(value), Err(RingBufferError::BufferFull));
+                    }
+                }
+                None => match model.pop_front() {
+                    Some(expected) => prop_assert_eq!(consumer.pop(), Ok(expected)),
+                    None => prop_assert_eq!(consumer.pop(), Err(RingBufferError::BufferEmpty)),
+                },
+            }
+            prop_assert_eq!(consumer.len(), model.len());
+            prop_assert_eq!(producer.remaining_capacity(), capacity - model.len());
+        }
+    }
+
+    #[test]
+    fn prop_unbounded_matches_model(
+        block_size in (0usize..=4).prop_map(|n| 1 << n),
+        operations in prop::collection::vec(prop::option::of(0u32..1000), 0..300)
+    ) {
+        let queue = UnboundedQueue::<u32>::new(block_size).unwrap();
+        let (mut producer, mut consumer) = queue.split();
+        let mut model = VecDeque::new();
+        
+        // `Some` pushes a value, `None` pops one
+        for operation in operations {
+            match operation {
+                Some(value) => {
+                    producer.push(value);
+                    model.push_back(value);
+                }
+                None => match model.pop_front() {
+                    Some(expected) => prop_assert_eq!(consumer.pop(), Ok(expected)),
+                    None => prop_assert_eq!(consumer.pop(), Err(RingBufferError::BufferEmpty)),
+                },
+            }
+            prop_assert_eq!(consumer.len(), model.len());
+        }
+    }
+
+    #[test]
+    fn prop_deque_matches_model(
+        capacity in (0usize..=4).prop_map(|n| 1 << n),
+        operations in prop::collection::vec((0u8..3, 0u32..1000), 0..300)
+    ) {
+        let (mut worker, stealer) = Deque::<u32>::new(capacity).unwrap().split();
+        let mut model = VecDeque::new();
+        
+        // The owner works the back of the deque, stealers take from the front
+        for (operation, value) in operations {
+            match operation {
+                0 => {
+                    if model.len() < capacity {
+                        prop_assert_eq!(worker.push(value), Ok(()));
+                        model.push_back(value);
+                    } else {
+                        prop_assert_eq!(worker.push(value), Err(value));
+                    }
+                }
+                1 => prop_assert_eq!(worker.pop(), model.pop_back()),
+                _ => match model.pop_front() {
+                    Some(expected) => prop_assert_eq!(stealer.steal(), Steal::Success(expected)),
+                    None => prop_assert_eq!(stealer.steal(), Steal::Empty),
+                },
+            }
+            prop_assert_eq!(worker.len(), model.len());
+            prop_assert_eq!(stealer.len(), model.len());
+        }
+    }
+
+    #[test]
+    fn prop_timer_wheel_matches_model(
+        operations in prop::collection::vec((0u8..3, 0u64..20_000), 0..200)
+    ) {
+        let mut wheel = TimerWheel::new(64);
+        let mut model: Vec<(u64, u32, _)> = Vec::new();
+        let mut now = 0;
+        let mut next_id = 0;
+        
+        for (kind, value) in operations {
+            match kind {
+                // Schedule relative to the current tick
+                0 => {
+                    let deadline = now + value;
+                    if let Ok(handle) = wheel.insert(deadline, next_id) {
+                        model.push((deadline, next_id, handle));
+                    } else {
+                        prop_assert_eq!(model.len(), 64);
+                    }
+                    next_id += 1;
+                }
+                // Cancel a pending timer
+                1 if !model.is_empty() => {
+                    let (_, id, handle) = model.remove(value as usize % model.len());
+                    prop_assert_eq!(wheel.cancel(handle), Some(id));
+                }
+                // Advance time
+                _ => {
+                    now += value % 5_000;
+                    let mut fired = Vec::new();
+                    wheel.tick(now, |deadline, id| fired.push((deadline, id)));
+                    
+                    let mut expected: Vec<_> = model
+                        .iter()
+                        .filter(|(deadline, _, _)| *deadline <= now)
+                        .map(|&(deadline, id, _)| (deadline, id))
+                        .collect();
+                    model.retain(|(deadline, _, _)| *deadline > now);
+                    
+                    // Exactly the due timers fire, in deadline order
+                    expected.sort_unstable();
+                    let mut sorted = fired.clone();
+                    sorted.sort_unstable();
+                    prop_assert_eq!(sorted, expected);
+                    prop_assert!(fired.windows(2).all(|pair| pair[0].0 <= pair[1].0));
+                }
+            }
+            prop_assert_eq!(wheel.len(), model.len());
+        }
+    }
+
+    #[test]
+    fn prop_mpsc_concurrent_producers(
+        capacity in (1usize..=6).prop_map(|n| 1 << n),
+        producers in 2usize..=4,
+        per_producer in 1u32..200
+    ) {
+        let queue = MpscQueue::<(usize, u32)>::new(capacity).unwrap();
+        let (producer, mut consumer) = queue.split();
+        
+        let handles: Vec<_> = (0..producers)
+            .map(|id| {
+                let producer = producer.clone();
+                thread::spawn(move || {
+                    for seq in 0..per_producer {
+                        while producer.push((id, seq)).is_err() {
+                            thread::yield_now();
+                        }
+                    }
+                })
+            })
+            .collect();
+        drop(producer);
+        
+        // Every producer's items arrive complete and in order
+        let mut next_seq = vec![0u32; producers];
+        let total = producers * per_producer as usize;
+        let mut received = 0;
+        while received < total {
+            match consumer.pop() {
+                Ok((id, seq)) => {
+                    prop_assert_eq!(seq, next_seq[id]);
+                    next_seq[id] += 1;
+                    received += 1;
+                }
+                Err(_) => thread::yield_now(),
+            }
+        }
+        
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        prop_assert!(consumer.is_empty());
+    }
+}
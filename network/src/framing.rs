@@ -0,0 +1,561 @@
+//! Length-prefixed framing over any byte stream
+//!
+//! Each frame is a length header followed by that many payload bytes. The
+//! header width and byte order are set by a [`FrameFormat`]; the length counts
+//! the payload only.
+//!
+//! [`FrameReader`] reassembles frames from a non-blocking reader, keeping
+//! partial frames between calls; it is the [`codec`](crate::codec) reader
+//! specialised to length-prefixed frames. [`FrameWriter`] sends a batch of frames with
+//! one vectored write, headers and payloads side by side, and only copies
+//! whatever the socket would not take.
+//!
+//! # Example
+//!
+//! ```
+//! use network::framing::{Endian, FrameFormat, FrameReader, FrameWriter, HeaderWidth};
+//!
+//! let format = FrameFormat::new(HeaderWidth::U16, Endian::Little);
+//! let mut wire = Vec::new();
+//! let mut writer = FrameWriter::new(format);
+//! writer.write_frames(&mut wire, &[b"hello", b"world"]).unwrap();
+//! assert_eq!(&wire[..7], b"\x05\x00hello");
+//!
+//! let mut reader = FrameReader::new(format);
+//! let mut frames = Vec::new();
+//! reader.read_frames(&mut &wire[..], usize::MAX, |frame| frames.push(frame.to_vec())).unwrap();
+//! assert_eq!(frames, vec![b"hello".to_vec(), b"world".to_vec()]);
+//! ```
+
+use std::io::{self, IoSlice, Read, Write};
+
+use crate::codec::{CodecReader, LengthPrefixedCodec};
+
+/// Largest frame accepted when no limit is configured, 16 MiB
+const DEFAULT_MAX_FRAME_LEN: usize = 16 << 20;
+
+/// Frames per vectored write, keeping two slices per frame under `IOV_MAX`
+const FRAMES_PER_WRITE: usize = 512;
+
+/// Width of the length header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeaderWidth {
+    U8,
+    U16,
+    U32,
+    U64,
+}
+
+impl HeaderWidth {
+    /// Returns the header size in bytes
+    pub fn bytes(self) -> usize {
+        match self {
+            HeaderWidth::U8 => 1,
+            HeaderWidth::U16 => 2,
+            HeaderWidth::U32 => 4,
+            HeaderWidth::U64 => 8,
+        }
+    }
+
+    /// Returns the largest length the header can express
+    pub fn max_len(self) -> u64 {
+        match self {
+            HeaderWidth::U8 => u8::MAX.into(),
+            HeaderWidth::U16 => u16::MAX.into(),
+            HeaderWidth::U32 => u32::MAX.into(),
+            HeaderWidth::U64 => u64::MAX,
+        }
+    }
+}
+
+/// Byte order of the length header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endian {
+    Big,
+    Little,
+}
+
+/// Layout of a frame's length header and the largest frame allowed
+///
+/// Defaults to a big-endian `u32` header and 16 MiB frames.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameFormat {
+    width: HeaderWidth,
+    endian: Endian,
+    max_frame_len: usize,
+}
+
+impl Default for FrameFormat {
+    fn default() -> Self {
+        FrameFormat::new(HeaderWidth::U32, Endian::Big)
+    }
+}
+
+impl FrameFormat {
+    /// Creates a format with the given header layout and a 16 MiB frame limit
+    pub fn new(width: HeaderWidth, endian: Endian) -> Self {
+        FrameFormat {
+            width,
+            endian,
+            max_frame_len: DEFAULT_MAX_FRAME_LEN,
+        }
+    }
+
+    /// Sets the largest payload accepted in either direction
+    ///
+    /// Frames over the limit are refused when sent and treated as a
+    /// protocol error when received, which bounds the reassembly buffer. The
+    /// header width caps the limit too.
+    pub fn with_max_frame_len(mut self, max_frame_len: usize) -> Self {
+        self.max_frame_len = max_frame_len;
+        self
+    }
+
+    /// Returns the header width
+    pub fn width(&self) -> HeaderWidth {
+        self.width
+    }
+
+    /// Returns the header byte order
+    pub fn endian(&self) -> Endian {
+        self.endian
+    }
+
+    /// Returns the header size in bytes
+    pub fn header_len(&self) -> usize {
+        self.width.bytes()
+    }
+
+    /// Returns the largest payload allowed, after capping by the header width
+    pub fn max_frame_len(&self) -> usize {
+        self.max_frame_len.min(usize::try_from(self.width.max_len()).unwrap_or(usize::MAX))
+    }
+
+    /// Writes the header for a `len`-byte payload into `out`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `out` is shorter than [`FrameFormat::header_len`].
+    pub fn encode_header(&self, len: usize, out: &mut [u8]) -> io::Result<()> {
+        if len > self.max_frame_len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("frame of {} bytes exceeds the {} byte limit", len, self.max_frame_len()),
+            ));
+        }
+        let bytes = match self.endian {
+            Endian::Big => (len as u64).to_be_bytes(),
+            Endian::Little => (len as u64).to_le_bytes(),
+        };
+        let width = self.header_len();
+        let header = match self.endian {
+            Endian::Big => &bytes[8 - width..],
+            Endian::Little => &bytes[..width],
+        };
+        out[..width].copy_from_slice(header);
+        Ok(())
+    }
+
+    /// Reads the payload length from a header
+    ///
+    /// # Panics
+    ///
+    /// Panics if `header` is shorter than [`FrameFormat::header_len`].
+    pub fn decode_header(&self, header: &[u8]) -> io::Result<usize> {
+        let len = self.read_len(header);
+        match usize::try_from(len) {
+            Ok(len) if len <= self.max_frame_len() => Ok(len),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("frame of {} bytes exceeds the {} byte limit", len, self.max_frame_len()),
+            )),
+        }
+    }
+
+    /// Reads the raw length from a header without checking it against the limit
+    pub(crate) fn read_len(&self, header: &[u8]) -> u64 {
+        let width = self.header_len();
+        let mut bytes = [0u8; 8];
+        match self.endian {
+            Endian::Big => {
+                bytes[8 - width..].copy_from_slice(&header[..width]);
+                u64::from_be_bytes(bytes)
+            }
+            Endian::Little => {
+                bytes[..width].copy_from_slice(&header[..width]);
+                u64::from_le_bytes(bytes)
+            }
+        }
+    }
+}
+
+/// Reassembles frames from a non-blocking byte stream
+///
+/// A [`CodecReader`] over a [`LengthPrefixedCodec`].
+#[derive(Debug)]
+pub struct FrameReader {
+    inner: CodecReader<LengthPrefixedCodec>,
+}
+
+impl FrameReader {
+    /// Creates a reader with an empty reassembly buffer
+    pub fn new(format: FrameFormat) -> Self {
+        FrameReader {
+            inner: CodecReader::new(LengthPrefixedCodec::new(format)),
+        }
+    }
+
+    /// Returns the frame format
+    pub fn format(&self) -> &FrameFormat {
+        self.inner.decoder().format()
+    }
+
+    /// Reads from `source` until it would block, passing each complete frame to `f`
+    ///
+    /// Stops early once `max_frames` frames were delivered, leaving anything
+    /// else in the stream; callers delivering into a ring pass its free room.
+    /// A partial frame stays buffered for the next call.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - A non-blocking stream; a blocking one makes this block until it closes
+    /// * `max_frames` - Most frames to deliver in this call
+    /// * `f` - Called with each payload in order
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(n)` - The number of frames delivered; check [`FrameReader::is_closed`] for end of stream
+    /// * `Err(io::Error)` - If reading failed, a header announced an oversized frame, or the stream
+    ///   ended inside a frame
+    pub fn read_frames<R, F>(&mut self, source: &mut R, max_frames: usize, f: F) -> io::Result<usize>
+    where
+        R: Read + ?Sized,
+        F: FnMut(&[u8]),
+    {
+        self.inner.read_items(source, max_frames, f)
+    }
+
+    /// Reads from `source` until it would block, passing each complete frame to `f` until it
+    /// declines one
+    ///
+    /// See [`CodecReader::read_items_while`].
+    pub fn read_frames_while<R, F>(&mut self, source: &mut R, f: F) -> io::Result<usize>
+    where
+        R: Read + ?Sized,
+        F: FnMut(&[u8]) -> bool,
+    {
+        self.inner.read_items_while(source, f)
+    }
+
+    /// Checks if the stream has reached its end
+    pub fn is_closed(&self) -> bool {
+        self.inner.is_closed()
+    }
+
+    /// Returns the number of bytes buffered towards frames not yet delivered
+    pub fn buffered(&self) -> usize {
+        self.inner.buffered()
+    }
+}
+
+/// Writes frames with vectored writes, buffering what the stream will not take
+#[derive(Debug)]
+pub struct FrameWriter {
+    format: FrameFormat,
+    /// Framed bytes still to be written, from `pending_start`
+    pending: Vec<u8>,
+    pending_start: usize,
+    headers: Vec<u8>,
+}
+
+impl FrameWriter {
+    /// Creates a writer with nothing pending
+    pub fn new(format: FrameFormat) -> Self {
+        FrameWriter {
+            format,
+            pending: Vec::new(),
+            pending_start: 0,
+            headers: Vec::new(),
+        }
+    }
+
+    /// Returns the frame format
+    pub fn format(&self) -> &FrameFormat {
+        &self.format
+    }
+
+    /// Writes `frames` to `sink`, each with its header
+    ///
+    /// When nothing is pending the frames go out in as few `writev` calls as
+    /// possible without being copied. Whatever the sink does not accept,
+    /// because it would block, is copied into the pending buffer and sent
+    /// by later calls or [`FrameWriter::flush`], so frames are never
+    /// reordered or lost.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - Every frame was written or buffered
+    /// * `Err(io::Error)` - If a frame exceeds the size limit, in which case nothing was written,
+    ///   or if writing failed
+    pub fn write_frames<W: Write + ?Sized>(&mut self, sink: &mut W, frames: &[&[u8]]) -> io::Result<()> {
+        let header_len = self.format.header_len();
+        self.headers.resize(frames.len() * header_len, 0);
+        for (frame, header) in frames.iter().zip(self.headers.chunks_exact_mut(header_len)) {
+            self.format.encode_header(frame.len(), header)?;
+        }
+
+        if !self.flush(sink)? {
+            self.buffer_frames(frames, 0, 0);
+            return Ok(());
+        }
+
+        // A header and a body slice per frame, kept on the stack so batching never allocates
+        let mut slices = [IoSlice::new(&[]); 2 * FRAMES_PER_WRITE];
+        let mut first = 0;
+        while first < frames.len() {
+            let batch = &frames[first..frames.len().min(first + FRAMES_PER_WRITE)];
+            let headers = &self.headers[first * header_len..];
+            for ((frame, header), pair) in batch
+                .iter()
+                .zip(headers.chunks_exact(header_len))
+                .zip(slices.chunks_exact_mut(2))
+            {
+                pair[0] = IoSlice::new(header);
+                pair[1] = IoSlice::new(frame);
+            }
+            let slices = &slices[..2 * batch.len()];
+
+            let total: usize = slices.iter().map(|slice| slice.len()).sum();
+            let written = write_vectored(sink, slices)?;
+            if written < total {
+                self.buffer_frames(frames, first, written);
+                return Ok(());
+            }
+            first += batch.len();
+        }
+        Ok(())
+    }
+
+    /// Writes as much of the pending buffer as `sink` accepts
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(true)` - Nothing is pending any more
+    /// * `Ok(false)` - The sink would block with bytes still pending
+    /// * `Err(io::Error)` - If writing failed
+    pub fn flush<W: Write + ?Sized>(&mut self, sink: &mut W) -> io::Result<bool> {
+        while self.pending_start < self.pending.len() {
+            match sink.write(&self.pending[self.pending_start..]) {
+                Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+                Ok(written) => self.pending_start += written,
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => return Ok(false),
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(err),
+            }
+        }
+        self.pending.clear();
+        self.pending_start = 0;
+        Ok(true)
+    }
+
+    /// Returns the number of bytes waiting to be written
+    ///
+    /// A growing value means the peer is not keeping up.
+    pub fn pending(&self) -> usize {
+        self.pending.len() - self.pending_start
+    }
+
+    /// Copies frames from index `first` into the pending buffer, skipping
+    /// the `skip` bytes of them already written
+    fn buffer_frames(&mut self, frames: &[&[u8]], first: usize, mut skip: usize) {
+        let header_len = self.format.header_len();
+        for (index, frame) in frames.iter().enumerate().skip(first) {
+            let header = &self.headers[index * header_len..(index + 1) * header_len];
+            for part in [header, frame] {
+                let from = skip.min(part.len());
+                self.pending.extend_from_slice(&part[from..]);
+                skip -= from;
+            }
+        }
+    }
+}
+
+/// Writes `slices` once, treating a full sink as zero bytes written
+fn write_vectored<W: Write + ?Sized>(sink: &mut W, slices: &[IoSlice<'_>]) -> io::Result<usize> {
+    loop {
+        match sink.write_vectored(slices) {
+            Ok(0) if slices.iter().any(|slice| !slice.is_empty()) => return Err(io::ErrorKind::WriteZero.into()),
+            Ok(written) => return Ok(written),
+            Err(err) if err.kind() == io::ErrorKind::WouldBlock => return Ok(0),
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Hands out at most `step` bytes per read, then would block
+    struct Trickle {
+        data: Vec<u8>,
+        position: usize,
+        step: usize,
+        blocked: bool,
+    }
+
+    impl Read for Trickle {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.blocked = !self.blocked;
+            if self.blocked {
+                return Err(io::ErrorKind::WouldBlock.into());
+            }
+            let len = self.step.min(buf.len()).min(self.data.len() - self.position);
+            buf[..len].copy_from_slice(&self.data[self.position..self.position + len]);
+            self.position += len;
+            Ok(len)
+        }
+    }
+
+    /// Accepts at most `room` bytes in total, then would block
+    struct Choke {
+        written: Vec<u8>,
+        room: usize,
+    }
+
+    impl Write for Choke {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.room == 0 {
+                return Err(io::ErrorKind::WouldBlock.into());
+            }
+            let len = buf.len().min(self.room);
+            self.written.extend_from_slice(&buf[..len]);
+            self.room -= len;
+            Ok(len)
+        }
+
+        fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
+            let mut written = 0;
+            for buf in bufs {
+                match self.write(buf) {
+                    Ok(len) => written += len,
+                    Err(_) if written > 0 => break,
+                    Err(err) => return Err(err),
+                }
+            }
+            Ok(written)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_header_layouts() {
+        let mut header = [0u8; 8];
+        let cases = [
+            (HeaderWidth::U8, Endian::Big, &[0x2a][..]),
+            (HeaderWidth::U16, Endian::Big, &[0x01, 0x2a][..]),
+            (HeaderWidth::U16, Endian::Little, &[0x2a, 0x01][..]),
+            (HeaderWidth::U32, Endian::Big, &[0, 0, 0x01, 0x2a][..]),
+            (HeaderWidth::U64, Endian::Little, &[0x2a, 0x01, 0, 0, 0, 0, 0, 0][..]),
+        ];
+        for (width, endian, expected) in cases {
+            let format = FrameFormat::new(width, endian);
+            let len = if width == HeaderWidth::U8 { 0x2a } else { 0x012a };
+            format.encode_header(len, &mut header).unwrap();
+            assert_eq!(&header[..format.header_len()], expected);
+            assert_eq!(format.decode_header(expected).unwrap(), len);
+        }
+
+        let format = FrameFormat::new(HeaderWidth::U8, Endian::Big);
+        assert_eq!(format.max_frame_len(), 255);
+        assert!(format.encode_header(256, &mut header).is_err());
+    }
+
+    #[test]
+    fn test_reassembles_partial_frames() {
+        let format = FrameFormat::new(HeaderWidth::U32, Endian::Little);
+        let mut wire = Vec::new();
+        FrameWriter::new(format)
+            .write_frames(&mut wire, &[b"first", b"", &[9; 100_000], b"last"])
+            .unwrap();
+
+        let mut source = Trickle { data: wire, position: 0, step: 3, blocked: false };
+        let mut reader = FrameReader::new(format);
+        let mut frames = Vec::new();
+        while frames.len() < 4 {
+            reader.read_frames(&mut source, usize::MAX, |frame| frames.push(frame.to_vec())).unwrap();
+            source.step = 7919;
+        }
+        assert_eq!(frames[0], b"first");
+        assert!(frames[1].is_empty());
+        assert_eq!(frames[2], vec![9; 100_000]);
+        assert_eq!(frames[3], b"last");
+        assert_eq!(reader.buffered(), 0);
+
+        // A frame cut off by end of stream is an error
+        let mut truncated = &[0u8, 0, 0, 5, 1, 2][..];
+        let mut reader = FrameReader::new(FrameFormat::default());
+        let err = reader.read_frames(&mut truncated, usize::MAX, |_| {}).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        assert!(reader.is_closed());
+    }
+
+    #[test]
+    fn test_rejects_oversized_frames() {
+        let format = FrameFormat::default().with_max_frame_len(8);
+        let mut sink = Vec::new();
+        let err = FrameWriter::new(format).write_frames(&mut sink, &[b"ok", &[0; 9]]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert!(sink.is_empty());
+
+        let mut source = &[0u8, 0, 0, 9][..];
+        let err = FrameReader::new(format).read_frames(&mut source, usize::MAX, |_| {}).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_buffers_what_the_sink_refuses() {
+        let format = FrameFormat::new(HeaderWidth::U16, Endian::Big);
+        let mut writer = FrameWriter::new(format);
+        let mut sink = Choke { written: Vec::new(), room: 5 };
+
+        writer.write_frames(&mut sink, &[b"abcd", b"efgh"]).unwrap();
+        assert_eq!(sink.written, b"\x00\x04abc");
+        assert_eq!(writer.pending(), 7);
+
+        // Later frames queue behind the pending bytes
+        writer.write_frames(&mut sink, &[b"ij"]).unwrap();
+        assert_eq!(writer.pending(), 11);
+
+        sink.room = usize::MAX;
+        assert!(writer.flush(&mut sink).unwrap());
+        assert_eq!(sink.written, b"\x00\x04abcd\x00\x04efgh\x00\x02ij");
+        assert_eq!(writer.pending(), 0);
+    }
+
+    #[test]
+    fn test_writes_more_frames_than_one_writev() {
+        let format = FrameFormat::new(HeaderWidth::U16, Endian::Big);
+        let bodies: Vec<[u8; 4]> = (0..3 * FRAMES_PER_WRITE as u32 / 2).map(u32::to_le_bytes).collect();
+        let frames: Vec<&[u8]> = bodies.iter().map(|body| &body[..]).collect();
+
+        // Refuse part way through the second batch
+        let mut sink = Choke { written: Vec::new(), room: (FRAMES_PER_WRITE + 10) * 6 + 3 };
+        let mut writer = FrameWriter::new(format);
+        writer.write_frames(&mut sink, &frames).unwrap();
+        assert_eq!(writer.pending(), frames.len() * 6 - sink.written.len());
+        sink.room = usize::MAX;
+        assert!(writer.flush(&mut sink).unwrap());
+
+        let mut source = &sink.written[..];
+        let mut received = Vec::new();
+        FrameReader::new(format)
+            .read_frames(&mut source, usize::MAX, |frame| received.push(frame.to_vec()))
+            .unwrap();
+        assert_eq!(received, bodies);
+    }
+}
@@ -1,14 +1,20 @@
-pub fn add(left: u64, right: u64) -> u64 {
-    left + right
-}
+//! Network I/O for feeding the `ferrite_core` queues
+//!
+//! - [`multicast`] receives UDP multicast market data in batches with
+//!   `recvmmsg` and hands the datagrams to a ring buffer
+//! - [`codec`] defines the `Encoder`/`Decoder` traits protocols implement
+//! - [`framing`] splits a byte stream into length-prefixed frames
+//! - [`tcp`] exchanges those frames over non-blocking TCP connections, using
+//!   io_uring where the `io-uring` feature is enabled and the kernel allows
+//! - [`reactor`] is an epoll event loop for driving those sockets, timers
+//!   and ring buffers from one thread
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn it_works() {
-        let result = add(2, 2);
-        assert_eq!(result, 4);
-    }
-}
+pub mod codec;
+pub mod framing;
+#[cfg(target_os = "linux")]
+pub mod multicast;
+#[cfg(target_os = "linux")]
+pub mod reactor;
+pub mod tcp;
+#[cfg(all(target_os = "linux", feature = "io-uring"))]
+mod uring;
@@ -0,0 +1,602 @@
+//! UDP multicast receiver for market data feeds
+//!
+//! [`MulticastReceiver`] joins one or more IPv4 multicast groups on a chosen
+//! interface and drains the socket in batches with a single `recvmmsg` call,
+//! either into a callback or straight into a [`ring_buffer::Producer`] for a
+//! decoding thread on the other side.
+//!
+//! The socket is bound to the wildcard address with `IP_MULTICAST_ALL`
+//! disabled, so it only sees traffic for the groups it joined even when other
+//! sockets on the host listen on the same port.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use ferrite_core::ring_buffer::RingBuffer;
+//! use network::multicast::{Datagram, MulticastReceiver};
+//! use std::net::Ipv4Addr;
+//!
+//! let mut receiver = MulticastReceiver::builder(30001)
+//!     .with_interface(Ipv4Addr::new(10, 0, 0, 5))
+//!     .with_group(Ipv4Addr::new(239, 1, 1, 1))
+//!     .with_recv_buffer_size(8 << 20)
+//!     .bind()
+//!     .unwrap();
+//!
+//! let (mut producer, mut consumer) = RingBuffer::<Datagram>::new(4096).unwrap().split();
+//! loop {
+//!     receiver.recv_into(&mut producer).unwrap();
+//!     while let Ok(datagram) = consumer.pop() {
+//!         println!("{} bytes from {}", datagram.len(), datagram.source());
+//!     }
+//! }
+//! ```
+//!
+//! [`ring_buffer::Producer`]: ferrite_core::ring_buffer::Producer
+
+use std::fmt;
+use std::io;
+use std::mem;
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4, UdpSocket};
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd, FromRawFd, OwnedFd, RawFd};
+use std::ptr;
+
+use ferrite_core::ring_buffer::Producer;
+
+/// Largest payload a [`Datagram`] holds, the Ethernet MTU
+///
+/// Anything longer is truncated and counted by
+/// [`MulticastReceiver::truncated_count`].
+pub const DATAGRAM_CAPACITY: usize = 1500;
+
+/// Batch size used when none is configured
+const DEFAULT_BATCH_SIZE: usize = 32;
+
+/// Largest batch `recvmmsg` accepts, `UIO_MAXIOV`
+const MAX_BATCH_SIZE: usize = 1024;
+
+/// A received datagram, stored inline so it can travel through a ring buffer
+/// without allocating
+#[derive(Clone)]
+pub struct Datagram {
+    source: SocketAddrV4,
+    len: usize,
+    data: [u8; DATAGRAM_CAPACITY],
+}
+
+impl Datagram {
+    /// Returns the address the datagram was sent from
+    pub fn source(&self) -> SocketAddrV4 {
+        self.source
+    }
+
+    /// Returns the received bytes
+    pub fn payload(&self) -> &[u8] {
+        &self.data[..self.len]
+    }
+
+    /// Returns the payload length in bytes
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Checks if the payload is empty
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl fmt::Debug for Datagram {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Datagram")
+            .field("source", &self.source)
+            .field("len", &self.len)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Builder for a [`MulticastReceiver`]
+#[derive(Debug, Clone)]
+pub struct MulticastReceiverBuilder {
+    port: u16,
+    interface: Ipv4Addr,
+    groups: Vec<Ipv4Addr>,
+    recv_buffer_size: Option<usize>,
+    batch_size: usize,
+    reuse_port: bool,
+    nonblocking: bool,
+}
+
+impl MulticastReceiverBuilder {
+    /// Creates a builder for a receiver on `port`
+    ///
+    /// Port 0 binds an ephemeral port, see [`MulticastReceiver::local_addr`].
+    pub fn new(port: u16) -> Self {
+        MulticastReceiverBuilder {
+            port,
+            interface: Ipv4Addr::UNSPECIFIED,
+            groups: Vec::new(),
+            recv_buffer_size: None,
+            batch_size: DEFAULT_BATCH_SIZE,
+            reuse_port: false,
+            nonblocking: true,
+        }
+    }
+
+    /// Joins groups on the interface with this address, rather than letting the kernel pick
+    pub fn with_interface(mut self, interface: Ipv4Addr) -> Self {
+        self.interface = interface;
+        self
+    }
+
+    /// Adds a multicast group to join
+    pub fn with_group(mut self, group: Ipv4Addr) -> Self {
+        self.groups.push(group);
+        self
+    }
+
+    /// Requests a kernel receive buffer of `bytes` with `SO_RCVBUF`
+    ///
+    /// The kernel doubles the request and caps it at `net.core.rmem_max`;
+    /// [`MulticastReceiver::recv_buffer_size`] reports what was granted.
+    pub fn with_recv_buffer_size(mut self, bytes: usize) -> Self {
+        self.recv_buffer_size = Some(bytes);
+        self
+    }
+
+    /// Sets the most datagrams read by one `recvmmsg` call, 32 by default
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+
+    /// Sets `SO_REUSEPORT` so several receivers can bind the same port
+    pub fn with_reuse_port(mut self, reuse_port: bool) -> Self {
+        self.reuse_port = reuse_port;
+        self
+    }
+
+    /// Chooses whether receives return immediately when no datagram is
+    /// waiting, which is the default, or block until one arrives
+    pub fn with_nonblocking(mut self, nonblocking: bool) -> Self {
+        self.nonblocking = nonblocking;
+        self
+    }
+
+    /// Creates the socket, binds it and joins every group
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(MulticastReceiver)` - A receiver that is already a member of every group
+    /// * `Err(io::Error)` - If the configuration is invalid or a socket call failed
+    pub fn bind(self) -> io::Result<MulticastReceiver> {
+        if self.groups.is_empty() {
+            return Err(invalid_input("at least one multicast group is required".to_string()));
+        }
+        if let Some(group) = self.groups.iter().find(|group| !group.is_multicast()) {
+            return Err(invalid_input(format!("{} is not a multicast address", group)));
+        }
+        if self.batch_size == 0 || self.batch_size > MAX_BATCH_SIZE {
+            return Err(invalid_input(format!(
+                "batch size {} must be between 1 and {}",
+                self.batch_size, MAX_BATCH_SIZE
+            )));
+        }
+
+        let fd = unsafe { libc::socket(libc::AF_INET, libc::SOCK_DGRAM | libc::SOCK_CLOEXEC, 0) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+
+        set_option(&fd, libc::SOL_SOCKET, libc::SO_REUSEADDR, 1)?;
+        if self.reuse_port {
+            set_option(&fd, libc::SOL_SOCKET, libc::SO_REUSEPORT, 1)?;
+        }
+        if let Some(bytes) = self.recv_buffer_size {
+            let bytes = libc::c_int::try_from(bytes)
+                .map_err(|_| invalid_input(format!("receive buffer size {} is too large", bytes)))?;
+            set_option(&fd, libc::SOL_SOCKET, libc::SO_RCVBUF, bytes)?;
+        }
+        set_option(&fd, libc::IPPROTO_IP, libc::IP_MULTICAST_ALL, 0)?;
+
+        let address = sockaddr_in(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, self.port));
+        let bound = unsafe {
+            libc::bind(
+                fd.as_raw_fd(),
+                &address as *const libc::sockaddr_in as *const libc::sockaddr,
+                mem::size_of::<libc::sockaddr_in>() as libc::socklen_t,
+            )
+        };
+        if bound < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let socket = UdpSocket::from(fd);
+        for group in &self.groups {
+            socket.join_multicast_v4(group, &self.interface)?;
+        }
+        socket.set_nonblocking(self.nonblocking)?;
+
+        Ok(MulticastReceiver::new(socket, self.groups, self.batch_size))
+    }
+}
+
+/// Receives datagrams from joined multicast groups in batches
+///
+/// Datagram buffers and `recvmmsg` headers are allocated once at bind time,
+/// so receiving never allocates. Dropping the receiver closes the socket,
+/// which leaves every group.
+pub struct MulticastReceiver {
+    socket: UdpSocket,
+    groups: Vec<Ipv4Addr>,
+    truncated: u64,
+    /// One `DATAGRAM_CAPACITY` slice per header
+    buffers: Box<[u8]>,
+    iovecs: Box<[libc::iovec]>,
+    addresses: Box<[libc::sockaddr_in]>,
+    /// Point into `iovecs` and `addresses`, which never move once boxed
+    headers: Box<[libc::mmsghdr]>,
+}
+
+// The raw pointers in the headers only reach into buffers the receiver owns
+unsafe impl Send for MulticastReceiver {}
+
+impl MulticastReceiver {
+    /// Returns a builder for a receiver on `port`
+    pub fn builder(port: u16) -> MulticastReceiverBuilder {
+        MulticastReceiverBuilder::new(port)
+    }
+
+    fn new(socket: UdpSocket, groups: Vec<Ipv4Addr>, batch_size: usize) -> Self {
+        let mut buffers = vec![0u8; batch_size * DATAGRAM_CAPACITY].into_boxed_slice();
+        let mut iovecs: Box<[libc::iovec]> = buffers
+            .chunks_exact_mut(DATAGRAM_CAPACITY)
+            .map(|buffer| libc::iovec {
+                iov_base: buffer.as_mut_ptr() as *mut libc::c_void,
+                iov_len: DATAGRAM_CAPACITY,
+            })
+            .collect();
+        let mut addresses: Box<[libc::sockaddr_in]> =
+            (0..batch_size).map(|_| unsafe { mem::zeroed() }).collect();
+        let headers = iovecs
+            .iter_mut()
+            .zip(addresses.iter_mut())
+            .map(|(iovec, address)| {
+                let mut header: libc::mmsghdr = unsafe { mem::zeroed() };
+                header.msg_hdr.msg_name = address as *mut libc::sockaddr_in as *mut libc::c_void;
+                header.msg_hdr.msg_iov = iovec;
+                header.msg_hdr.msg_iovlen = 1;
+                header
+            })
+            .collect();
+
+        MulticastReceiver {
+            socket,
+            groups,
+            truncated: 0,
+            buffers,
+            iovecs,
+            addresses,
+            headers,
+        }
+    }
+
+    /// Receives up to one batch of datagrams and passes each to `f`
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - Called with each payload and its source address, in arrival order
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(n)` - The number of datagrams received, 0 if none were waiting on a non-blocking socket
+    /// * `Err(io::Error)` - If `recvmmsg` failed
+    pub fn recv_batch<F: FnMut(&[u8], SocketAddrV4)>(&mut self, mut f: F) -> io::Result<usize> {
+        let received = self.recv_mmsg(self.headers.len())?;
+        for index in 0..received {
+            let (payload, source) = self.datagram(index);
+            f(payload, source);
+        }
+        Ok(received)
+    }
+
+    /// Receives as many datagrams as fit in `producer`'s ring, up to one batch
+    ///
+    /// Never reads more than the ring has room for, so nothing is dropped
+    /// here; when the decoder falls behind, datagrams wait in the socket's
+    /// receive buffer instead.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(n)` - The number of datagrams pushed, 0 if the ring is full or none were waiting
+    /// * `Err(io::Error)` - If `recvmmsg` failed
+    pub fn recv_into(&mut self, producer: &mut Producer<Datagram>) -> io::Result<usize> {
+        let room = producer.remaining_capacity().min(self.headers.len());
+        if room == 0 {
+            return Ok(0);
+        }
+
+        let received = self.recv_mmsg(room)?;
+        for index in 0..received {
+            let (payload, source) = self.datagram(index);
+            let mut datagram = Datagram {
+                source,
+                len: payload.len(),
+                data: [0; DATAGRAM_CAPACITY],
+            };
+            datagram.data[..payload.len()].copy_from_slice(payload);
+            let pushed = producer.push(datagram);
+            debug_assert!(pushed.is_ok(), "ring lost room reserved for a datagram");
+        }
+        Ok(received)
+    }
+
+    /// Returns how many datagrams were longer than [`DATAGRAM_CAPACITY`] and cut short
+    pub fn truncated_count(&self) -> u64 {
+        self.truncated
+    }
+
+    /// Returns the groups this receiver joined
+    pub fn groups(&self) -> &[Ipv4Addr] {
+        &self.groups
+    }
+
+    /// Returns the most datagrams read by one receive call
+    pub fn batch_size(&self) -> usize {
+        self.headers.len()
+    }
+
+    /// Returns the address the socket is bound to
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.socket.local_addr()
+    }
+
+    /// Returns the kernel receive buffer size actually granted
+    pub fn recv_buffer_size(&self) -> io::Result<usize> {
+        let mut value: libc::c_int = 0;
+        let mut len = mem::size_of::<libc::c_int>() as libc::socklen_t;
+        let result = unsafe {
+            libc::getsockopt(
+                self.socket.as_raw_fd(),
+                libc::SOL_SOCKET,
+                libc::SO_RCVBUF,
+                &mut value as *mut libc::c_int as *mut libc::c_void,
+                &mut len,
+            )
+        };
+        if result < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(value as usize)
+    }
+
+    /// Reads up to `count` datagrams into the first `count` buffers
+    fn recv_mmsg(&mut self, count: usize) -> io::Result<usize> {
+        for header in &mut self.headers[..count] {
+            header.msg_hdr.msg_namelen = mem::size_of::<libc::sockaddr_in>() as libc::socklen_t;
+            header.msg_hdr.msg_flags = 0;
+            header.msg_len = 0;
+        }
+
+        let received = unsafe {
+            libc::recvmmsg(
+                self.socket.as_raw_fd(),
+                self.headers.as_mut_ptr(),
+                count as libc::c_uint,
+                libc::MSG_WAITFORONE,
+                ptr::null_mut(),
+            )
+        };
+        if received < 0 {
+            let err = io::Error::last_os_error();
+            return match err.kind() {
+                io::ErrorKind::WouldBlock | io::ErrorKind::Interrupted => Ok(0),
+                _ => Err(err),
+            };
+        }
+
+        let received = received as usize;
+        for header in &self.headers[..received] {
+            if header.msg_hdr.msg_flags & libc::MSG_TRUNC != 0 {
+                self.truncated += 1;
+            }
+        }
+        Ok(received)
+    }
+
+    /// Returns the payload and source of the datagram in buffer `index`
+    fn datagram(&self, index: usize) -> (&[u8], SocketAddrV4) {
+        let len = (self.headers[index].msg_len as usize).min(DATAGRAM_CAPACITY);
+        let start = index * DATAGRAM_CAPACITY;
+        let address = &self.addresses[index];
+        let source = SocketAddrV4::new(
+            Ipv4Addr::from(u32::from_be(address.sin_addr.s_addr)),
+            u16::from_be(address.sin_port),
+        );
+        (&self.buffers[start..start + len], source)
+    }
+}
+
+impl fmt::Debug for MulticastReceiver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MulticastReceiver")
+            .field("socket", &self.socket)
+            .field("groups", &self.groups)
+            .field("batch_size", &self.iovecs.len())
+            .field("truncated", &self.truncated)
+            .finish()
+    }
+}
+
+impl AsRawFd for MulticastReceiver {
+    fn as_raw_fd(&self) -> RawFd {
+        self.socket.as_raw_fd()
+    }
+}
+
+impl AsFd for MulticastReceiver {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.socket.as_fd()
+    }
+}
+
+fn invalid_input(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+fn set_option(fd: &OwnedFd, level: libc::c_int, name: libc::c_int, value: libc::c_int) -> io::Result<()> {
+    let result = unsafe {
+        libc::setsockopt(
+            fd.as_raw_fd(),
+            level,
+            name,
+            &value as *const libc::c_int as *const libc::c_void,
+            mem::size_of::<libc::c_int>() as libc::socklen_t,
+        )
+    };
+    if result < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+fn sockaddr_in(address: SocketAddrV4) -> libc::sockaddr_in {
+    let mut raw: libc::sockaddr_in = unsafe { mem::zeroed() };
+    raw.sin_family = libc::AF_INET as libc::sa_family_t;
+    raw.sin_port = address.port().to_be();
+    raw.sin_addr.s_addr = u32::from(*address.ip()).to_be();
+    raw
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ferrite_core::ring_buffer::RingBuffer;
+    use std::time::{Duration, Instant};
+
+    const LOOPBACK: Ipv4Addr = Ipv4Addr::LOCALHOST;
+
+    fn receiver(group: Ipv4Addr) -> MulticastReceiver {
+        MulticastReceiver::builder(0)
+            .with_interface(LOOPBACK)
+            .with_group(group)
+            .with_batch_size(8)
+            .bind()
+            .unwrap()
+    }
+
+    /// Returns a socket that sends multicast out of the loopback interface
+    fn sender() -> UdpSocket {
+        let socket = UdpSocket::bind((LOOPBACK, 0)).unwrap();
+        let interface = libc::in_addr { s_addr: u32::from(LOOPBACK).to_be() };
+        let result = unsafe {
+            libc::setsockopt(
+                socket.as_raw_fd(),
+                libc::IPPROTO_IP,
+                libc::IP_MULTICAST_IF,
+                &interface as *const libc::in_addr as *const libc::c_void,
+                mem::size_of::<libc::in_addr>() as libc::socklen_t,
+            )
+        };
+        assert_eq!(result, 0, "{}", io::Error::last_os_error());
+        socket.set_multicast_loop_v4(true).unwrap();
+        socket
+    }
+
+    fn port(receiver: &MulticastReceiver) -> u16 {
+        receiver.local_addr().unwrap().port()
+    }
+
+    /// Receives until `expected` datagrams arrived or a second passed
+    fn collect(receiver: &mut MulticastReceiver, expected: usize) -> Vec<(Vec<u8>, SocketAddrV4)> {
+        let mut received = Vec::new();
+        let deadline = Instant::now() + Duration::from_secs(1);
+        while received.len() < expected && Instant::now() < deadline {
+            receiver
+                .recv_batch(|payload, source| received.push((payload.to_vec(), source)))
+                .unwrap();
+        }
+        received
+    }
+
+    #[test]
+    fn test_receives_joined_group_only() {
+        let group = Ipv4Addr::new(239, 255, 71, 1);
+        let mut joined = receiver(group);
+        let mut other = MulticastReceiver::builder(port(&joined))
+            .with_interface(LOOPBACK)
+            .with_group(Ipv4Addr::new(239, 255, 71, 2))
+            .bind()
+            .unwrap();
+
+        let sender = sender();
+        for seq in 0u8..3 {
+            sender.send_to(&[seq; 16], (group, port(&joined))).unwrap();
+        }
+
+        let received = collect(&mut joined, 3);
+        assert_eq!(received.len(), 3);
+        for (seq, (payload, source)) in received.iter().enumerate() {
+            assert_eq!(payload, &[seq as u8; 16]);
+            assert_eq!(SocketAddr::V4(*source), sender.local_addr().unwrap());
+        }
+        assert!(collect(&mut other, 1).is_empty());
+    }
+
+    #[test]
+    fn test_recv_into_waits_for_ring_room() {
+        let group = Ipv4Addr::new(239, 255, 71, 3);
+        let mut receiver = receiver(group);
+        let (mut producer, mut consumer) = RingBuffer::<Datagram>::new(4).unwrap().split();
+
+        let sender = sender();
+        for seq in 0u8..6 {
+            sender.send_to(&[seq], (group, port(&receiver))).unwrap();
+        }
+
+        let deadline = Instant::now() + Duration::from_secs(1);
+        let mut received = Vec::new();
+        while received.len() < 6 && Instant::now() < deadline {
+            receiver.recv_into(&mut producer).unwrap();
+            assert!(consumer.len() <= 4);
+            while let Ok(datagram) = consumer.pop() {
+                received.push(datagram.payload()[0]);
+            }
+        }
+        assert_eq!(received, vec![0, 1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn test_truncates_oversized_datagrams() {
+        let group = Ipv4Addr::new(239, 255, 71, 4);
+        let mut receiver = receiver(group);
+
+        sender().send_to(&[7; DATAGRAM_CAPACITY + 100], (group, port(&receiver))).unwrap();
+
+        let received = collect(&mut receiver, 1);
+        assert_eq!(received.len(), 1);
+        assert_eq!(received[0].0.len(), DATAGRAM_CAPACITY);
+        assert_eq!(receiver.truncated_count(), 1);
+    }
+
+    #[test]
+    fn test_socket_options() {
+        let receiver = MulticastReceiver::builder(0)
+            .with_group(Ipv4Addr::new(239, 255, 71, 5))
+            .with_recv_buffer_size(64 * 1024)
+            .bind()
+            .unwrap();
+        assert!(receiver.recv_buffer_size().unwrap() >= 64 * 1024);
+        assert_eq!(receiver.batch_size(), DEFAULT_BATCH_SIZE);
+
+        let unicast = MulticastReceiver::builder(0).with_group(LOOPBACK).bind();
+        assert_eq!(unicast.unwrap_err().kind(), io::ErrorKind::InvalidInput);
+        assert!(MulticastReceiver::builder(0).bind().is_err());
+        assert!(MulticastReceiver::builder(0)
+            .with_group(Ipv4Addr::new(239, 255, 71, 5))
+            .with_batch_size(0)
+            .bind()
+            .is_err());
+    }
+}
@@ -0,0 +1,601 @@
+//! Single-threaded epoll event loop
+//!
+//! A [`Reactor`] waits on an epoll instance and hands each readiness event
+//! to a [`Handler`]. Sources are registered with a caller-chosen [`Token`]
+//! through the reactor's [`Registry`]: sockets, the eventfd behind a
+//! `ferrite_core` ring consumer or [`Notifier`], and [`Timer`]s. The event
+//! buffer is allocated once, so the loop itself never allocates.
+//!
+//! In [`PollMode::BusyPoll`] the reactor calls `epoll_wait` with a zero
+//! timeout and never sleeps, for threads with a core to themselves. Other
+//! threads wake a blocking reactor by pushing into a ring buffer created
+//! with `with_notifier`: its consumer is registered like any socket, and the
+//! ring is emptied with [`drain_ring`].
+//!
+//! # Example
+//!
+//! ```
+//! use ferrite_core::ring_buffer::RingBuffer;
+//! use network::reactor::{drain_ring, Event, Interest, Reactor, Registry, Token};
+//! use std::ops::ControlFlow;
+//! use std::thread;
+//!
+//! const COMMANDS: Token = Token(0);
+//!
+//! let (mut producer, mut consumer) = RingBuffer::<u32>::new(64).unwrap().with_notifier().unwrap().split();
+//! let mut reactor = Reactor::new(64).unwrap();
+//! reactor.registry().register(&consumer, COMMANDS, Interest::READABLE).unwrap();
+//!
+//! let control = thread::spawn(move || producer.push(42).unwrap());
+//!
+//! let mut received = Vec::new();
+//! drain_ring(&mut consumer, |command| received.push(command));
+//! if received.is_empty() {
+//!     reactor
+//!         .run(&mut |_: &Registry, event: Event| {
+//!             assert_eq!(event.token(), COMMANDS);
+//!             drain_ring(&mut consumer, |command| received.push(command));
+//!             if received.is_empty() { ControlFlow::Continue(()) } else { ControlFlow::Break(()) }
+//!         })
+//!         .unwrap();
+//! }
+//! control.join().unwrap();
+//! assert_eq!(received, vec![42]);
+//! ```
+//!
+//! [`Notifier`]: ferrite_core::notify::Notifier
+
+use std::io;
+use std::ops::{BitOr, ControlFlow};
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd, FromRawFd, OwnedFd, RawFd};
+use std::ptr;
+use std::time::Duration;
+
+use ferrite_core::ring_buffer::NotifyingConsumer;
+
+/// Identifies a registered source in the events it produces
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Token(pub usize);
+
+/// Readiness a source is registered for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Interest(u32);
+
+impl Interest {
+    /// Readable, including the peer closing its side
+    pub const READABLE: Interest = Interest((libc::EPOLLIN | libc::EPOLLRDHUP) as u32);
+    /// Writable
+    pub const WRITABLE: Interest = Interest(libc::EPOLLOUT as u32);
+    /// Edge-triggered: report each change in readiness once rather than
+    /// while it lasts; combine with [`Interest::READABLE`] or [`Interest::WRITABLE`]
+    pub const EDGE: Interest = Interest(libc::EPOLLET as u32);
+
+    /// Checks if this interest includes readability
+    pub fn is_readable(self) -> bool {
+        self.0 & libc::EPOLLIN as u32 != 0
+    }
+
+    /// Checks if this interest includes writability
+    pub fn is_writable(self) -> bool {
+        self.0 & libc::EPOLLOUT as u32 != 0
+    }
+}
+
+impl BitOr for Interest {
+    type Output = Interest;
+
+    fn bitor(self, other: Interest) -> Interest {
+        Interest(self.0 | other.0)
+    }
+}
+
+/// A readiness event for one registered source
+#[derive(Clone, Copy)]
+pub struct Event {
+    token: Token,
+    events: u32,
+}
+
+impl Event {
+    /// Returns the token the source was registered with
+    pub fn token(&self) -> Token {
+        self.token
+    }
+
+    /// Checks if the source can be read without blocking
+    pub fn is_readable(&self) -> bool {
+        self.events & libc::EPOLLIN as u32 != 0
+    }
+
+    /// Checks if the source can be written without blocking
+    pub fn is_writable(&self) -> bool {
+        self.events & libc::EPOLLOUT as u32 != 0
+    }
+
+    /// Checks if the peer closed the connection or hung up
+    pub fn is_closed(&self) -> bool {
+        self.events & (libc::EPOLLHUP | libc::EPOLLRDHUP) as u32 != 0
+    }
+
+    /// Checks if the source reported an error
+    pub fn is_error(&self) -> bool {
+        self.events & libc::EPOLLERR as u32 != 0
+    }
+}
+
+impl std::fmt::Debug for Event {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Event")
+            .field("token", &self.token)
+            .field("readable", &self.is_readable())
+            .field("writable", &self.is_writable())
+            .field("closed", &self.is_closed())
+            .field("error", &self.is_error())
+            .finish()
+    }
+}
+
+/// How a [`Reactor`] waits for events
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PollMode {
+    /// Sleep in `epoll_wait` until an event arrives
+    #[default]
+    Blocking,
+    /// Never sleep: `epoll_wait` returns at once, for threads with a dedicated core
+    BusyPoll,
+    /// Sleep until an event arrives or the timeout passes
+    Timeout(Duration),
+}
+
+impl PollMode {
+    /// Returns the `epoll_wait` timeout in milliseconds
+    fn timeout_ms(self) -> libc::c_int {
+        match self {
+            PollMode::Blocking => -1,
+            PollMode::BusyPoll => 0,
+            // Round up so a short timeout does not turn into a busy poll
+            PollMode::Timeout(timeout) => {
+                let ms = timeout.as_nanos().div_ceil(1_000_000);
+                ms.min(libc::c_int::MAX as u128) as libc::c_int
+            }
+        }
+    }
+}
+
+/// Handles readiness events dispatched by a [`Reactor`]
+///
+/// Implemented for any `FnMut(&Registry, Event) -> ControlFlow<()>`.
+pub trait Handler {
+    /// Handles one event
+    ///
+    /// The registry may be used to add, change or remove sources. Returning
+    /// `ControlFlow::Break` stops [`Reactor::run`] once this event is done.
+    fn on_event(&mut self, registry: &Registry, event: Event) -> ControlFlow<()>;
+}
+
+impl<F> Handler for F
+where
+    F: FnMut(&Registry, Event) -> ControlFlow<()>,
+{
+    fn on_event(&mut self, registry: &Registry, event: Event) -> ControlFlow<()> {
+        self(registry, event)
+    }
+}
+
+/// Registers sources with a reactor's epoll instance
+#[derive(Debug)]
+pub struct Registry {
+    epoll: OwnedFd,
+}
+
+impl Registry {
+    /// Starts watching `source` for `interest`, tagging its events with `token`
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - The source is registered
+    /// * `Err(io::Error)` - If the source is already registered or cannot be polled
+    pub fn register<S: AsRawFd + ?Sized>(&self, source: &S, token: Token, interest: Interest) -> io::Result<()> {
+        self.control(libc::EPOLL_CTL_ADD, source.as_raw_fd(), token, interest)
+    }
+
+    /// Changes the token or interest of a registered source
+    pub fn reregister<S: AsRawFd + ?Sized>(&self, source: &S, token: Token, interest: Interest) -> io::Result<()> {
+        self.control(libc::EPOLL_CTL_MOD, source.as_raw_fd(), token, interest)
+    }
+
+    /// Stops watching `source`
+    ///
+    /// Closing a source's descriptor also removes it, as long as no
+    /// duplicate of the descriptor remains open.
+    pub fn deregister<S: AsRawFd + ?Sized>(&self, source: &S) -> io::Result<()> {
+        let result = unsafe {
+            libc::epoll_ctl(self.epoll.as_raw_fd(), libc::EPOLL_CTL_DEL, source.as_raw_fd(), ptr::null_mut())
+        };
+        if result < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    fn control(&self, op: libc::c_int, fd: RawFd, token: Token, interest: Interest) -> io::Result<()> {
+        let mut event = libc::epoll_event {
+            events: interest.0,
+            u64: token.0 as u64,
+        };
+        let result = unsafe { libc::epoll_ctl(self.epoll.as_raw_fd(), op, fd, &mut event) };
+        if result < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
+
+/// A single-threaded epoll event loop
+pub struct Reactor {
+    registry: Registry,
+    events: Box<[libc::epoll_event]>,
+    mode: PollMode,
+}
+
+impl Reactor {
+    /// Creates a reactor that handles up to `capacity` events per wait
+    ///
+    /// # Arguments
+    ///
+    /// * `capacity` - Size of the event buffer; further ready sources are reported on the next wait
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Reactor)` - A reactor in [`PollMode::Blocking`] with nothing registered
+    /// * `Err(io::Error)` - If `capacity` is 0 or the kernel refused to create the epoll instance
+    pub fn new(capacity: usize) -> io::Result<Self> {
+        if capacity == 0 || capacity > libc::c_int::MAX as usize {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("event capacity {} must be between 1 and {}", capacity, libc::c_int::MAX),
+            ));
+        }
+        let fd = unsafe { libc::epoll_create1(libc::EPOLL_CLOEXEC) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(Reactor {
+            registry: Registry {
+                epoll: unsafe { OwnedFd::from_raw_fd(fd) },
+            },
+            events: vec![libc::epoll_event { events: 0, u64: 0 }; capacity].into_boxed_slice(),
+            mode: PollMode::Blocking,
+        })
+    }
+
+    /// Sets how the reactor waits for events
+    pub fn with_mode(mut self, mode: PollMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Returns how the reactor waits for events
+    pub fn mode(&self) -> PollMode {
+        self.mode
+    }
+
+    /// Returns the registry for adding and removing sources
+    pub fn registry(&self) -> &Registry {
+        &self.registry
+    }
+
+    /// Waits once, according to the poll mode, and dispatches every ready event
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(ControlFlow::Continue(()))` - Every event was handled, possibly none
+    /// * `Ok(ControlFlow::Break(()))` - The handler asked to stop; later events in this batch
+    ///   are reported again by the next wait if still ready
+    /// * `Err(io::Error)` - If `epoll_wait` failed
+    pub fn poll<H: Handler + ?Sized>(&mut self, handler: &mut H) -> io::Result<ControlFlow<()>> {
+        let ready = unsafe {
+            libc::epoll_wait(
+                self.registry.epoll.as_raw_fd(),
+                self.events.as_mut_ptr(),
+                self.events.len() as libc::c_int,
+                self.mode.timeout_ms(),
+            )
+        };
+        if ready < 0 {
+            let err = io::Error::last_os_error();
+            if err.kind() == io::ErrorKind::Interrupted {
+                return Ok(ControlFlow::Continue(()));
+            }
+            return Err(err);
+        }
+
+        for raw in &self.events[..ready as usize] {
+            let event = Event {
+                token: Token(raw.u64 as usize),
+                events: raw.events,
+            };
+            if handler.on_event(&self.registry, event).is_break() {
+                return Ok(ControlFlow::Break(()));
+            }
+        }
+        Ok(ControlFlow::Continue(()))
+    }
+
+    /// Polls and dispatches events until the handler returns `ControlFlow::Break`
+    pub fn run<H: Handler + ?Sized>(&mut self, handler: &mut H) -> io::Result<()> {
+        while self.poll(handler)?.is_continue() {}
+        Ok(())
+    }
+}
+
+impl std::fmt::Debug for Reactor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Reactor")
+            .field("registry", &self.registry)
+            .field("capacity", &self.events.len())
+            .field("mode", &self.mode)
+            .finish()
+    }
+}
+
+/// Pops every item from a notifier-backed ring, then re-arms its notifier
+///
+/// Call this from the handler for the consumer's token. Afterwards the
+/// consumer's descriptor only becomes readable again for new pushes, so a
+/// blocking reactor sleeps until there is more work.
+///
+/// # Returns
+///
+/// The number of items passed to `f`
+pub fn drain_ring<T, F: FnMut(T)>(consumer: &mut NotifyingConsumer<T>, mut f: F) -> usize {
+    let mut drained = 0;
+    loop {
+        while let Ok(item) = consumer.pop() {
+            f(item);
+            drained += 1;
+        }
+        if consumer.arm_notifier() {
+            return drained;
+        }
+    }
+}
+
+/// A `timerfd` that becomes readable when it expires
+///
+/// Created disarmed, non-blocking and close-on-exec on `CLOCK_MONOTONIC`.
+#[derive(Debug)]
+pub struct Timer {
+    fd: OwnedFd,
+}
+
+impl Timer {
+    /// Creates a disarmed timer
+    pub fn new() -> io::Result<Self> {
+        let fd = unsafe {
+            libc::timerfd_create(libc::CLOCK_MONOTONIC, libc::TFD_NONBLOCK | libc::TFD_CLOEXEC)
+        };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Timer {
+            fd: unsafe { OwnedFd::from_raw_fd(fd) },
+        })
+    }
+
+    /// Arms the timer to expire once, `after` from now
+    pub fn set_after(&self, after: Duration) -> io::Result<()> {
+        self.set(after.max(Duration::from_nanos(1)), Duration::ZERO)
+    }
+
+    /// Arms the timer to expire every `interval`, starting `interval` from now
+    pub fn set_interval(&self, interval: Duration) -> io::Result<()> {
+        let interval = interval.max(Duration::from_nanos(1));
+        self.set(interval, interval)
+    }
+
+    /// Disarms the timer
+    pub fn cancel(&self) -> io::Result<()> {
+        self.set(Duration::ZERO, Duration::ZERO)
+    }
+
+    /// Returns how many times the timer expired since the last call, and
+    /// makes its descriptor unreadable until the next expiry
+    pub fn expirations(&self) -> io::Result<u64> {
+        let mut value: u64 = 0;
+        let read = unsafe {
+            libc::read(
+                self.fd.as_raw_fd(),
+                &mut value as *mut u64 as *mut libc::c_void,
+                std::mem::size_of::<u64>(),
+            )
+        };
+        if read < 0 {
+            let err = io::Error::last_os_error();
+            if err.kind() == io::ErrorKind::WouldBlock {
+                return Ok(0);
+            }
+            return Err(err);
+        }
+        Ok(value)
+    }
+
+    fn set(&self, value: Duration, interval: Duration) -> io::Result<()> {
+        let spec = libc::itimerspec {
+            it_interval: timespec(interval),
+            it_value: timespec(value),
+        };
+        let result = unsafe { libc::timerfd_settime(self.fd.as_raw_fd(), 0, &spec, ptr::null_mut()) };
+        if result < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
+
+impl AsRawFd for Timer {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd.as_raw_fd()
+    }
+}
+
+impl AsFd for Timer {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.fd.as_fd()
+    }
+}
+
+fn timespec(duration: Duration) -> libc::timespec {
+    libc::timespec {
+        tv_sec: duration.as_secs().min(libc::time_t::MAX as u64) as libc::time_t,
+        tv_nsec: duration.subsec_nanos() as libc::c_long,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ferrite_core::notify::Notifier;
+    use ferrite_core::ring_buffer::RingBuffer;
+    use std::io::Write;
+    use std::net::{TcpListener, TcpStream};
+    use std::thread;
+    use std::time::Instant;
+
+    /// Polls once and returns the events dispatched
+    fn poll_events(reactor: &mut Reactor) -> Vec<Event> {
+        let mut events = Vec::new();
+        let flow = reactor
+            .poll(&mut |_: &Registry, event: Event| {
+                events.push(event);
+                ControlFlow::Continue(())
+            })
+            .unwrap();
+        assert!(flow.is_continue());
+        events
+    }
+
+    #[test]
+    fn test_timer_wakes_blocking_loop() {
+        const TICK: Token = Token(7);
+        let mut reactor = Reactor::new(8).unwrap();
+        let timer = Timer::new().unwrap();
+        reactor.registry().register(&timer, TICK, Interest::READABLE).unwrap();
+
+        let start = Instant::now();
+        timer.set_interval(Duration::from_millis(2)).unwrap();
+        let mut ticks = 0;
+        reactor
+            .run(&mut |_: &Registry, event: Event| {
+                assert_eq!(event.token(), TICK);
+                ticks += timer.expirations().unwrap();
+                if ticks >= 3 {
+                    ControlFlow::Break(())
+                } else {
+                    ControlFlow::Continue(())
+                }
+            })
+            .unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(6));
+
+        timer.cancel().unwrap();
+        let mut reactor = reactor.with_mode(PollMode::Timeout(Duration::from_millis(10)));
+        assert!(poll_events(&mut reactor).is_empty());
+    }
+
+    #[test]
+    fn test_ring_commands_wake_loop() {
+        const COMMANDS: Token = Token(1);
+        let (mut producer, mut consumer) = RingBuffer::<u32>::new(8).unwrap().with_notifier().unwrap().split();
+        let mut reactor = Reactor::new(8).unwrap();
+        reactor.registry().register(&consumer, COMMANDS, Interest::READABLE).unwrap();
+        assert!(consumer.arm_notifier());
+
+        let control = thread::spawn(move || {
+            for command in 0..100 {
+                while producer.push(command).is_err() {
+                    thread::yield_now();
+                }
+                if command % 10 == 0 {
+                    thread::sleep(Duration::from_millis(1));
+                }
+            }
+        });
+
+        let mut received = Vec::new();
+        reactor
+            .run(&mut |_: &Registry, event: Event| {
+                assert!(event.is_readable());
+                drain_ring(&mut consumer, |command| received.push(command));
+                if received.len() == 100 {
+                    ControlFlow::Break(())
+                } else {
+                    ControlFlow::Continue(())
+                }
+            })
+            .unwrap();
+        control.join().unwrap();
+        assert_eq!(received, (0..100).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_busy_poll_dispatches_socket_readiness() {
+        const CONNECTION: Token = Token(2);
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (server, _) = listener.accept().unwrap();
+
+        let mut reactor = Reactor::new(4).unwrap().with_mode(PollMode::BusyPoll);
+        let registry = reactor.registry();
+        registry.register(&server, CONNECTION, Interest::READABLE | Interest::WRITABLE).unwrap();
+        registry.reregister(&server, CONNECTION, Interest::READABLE).unwrap();
+
+        // Busy polling never sleeps, even with nothing ready
+        let start = Instant::now();
+        assert!(poll_events(&mut reactor).is_empty());
+        assert!(start.elapsed() < Duration::from_millis(100));
+
+        client.write_all(b"ping").unwrap();
+        drop(client);
+        let mut seen = Vec::new();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !seen.iter().any(|event: &Event| event.is_closed()) {
+            seen.extend(poll_events(&mut reactor));
+            assert!(Instant::now() < deadline, "no close event");
+        }
+        assert!(seen.iter().all(|event| event.token() == CONNECTION && event.is_readable()));
+
+        reactor.registry().deregister(&server).unwrap();
+        assert!(poll_events(&mut reactor).is_empty());
+    }
+
+    #[test]
+    fn test_handler_registers_sources() {
+        const NOTIFIER: Token = Token(3);
+        const TIMER: Token = Token(4);
+        let mut reactor = Reactor::new(1).unwrap();
+        let notifier = Notifier::new().unwrap();
+        let timer = Timer::new().unwrap();
+        reactor.registry().register(&notifier, NOTIFIER, Interest::READABLE).unwrap();
+        assert!(reactor.registry().register(&notifier, NOTIFIER, Interest::READABLE).is_err());
+        assert!(Reactor::new(0).is_err());
+
+        // The notifier's handler arms and registers the timer, whose handler stops the loop
+        notifier.notify().unwrap();
+        let mut order = Vec::new();
+        reactor
+            .run(&mut |registry: &Registry, event: Event| {
+                order.push(event.token());
+                if event.token() == NOTIFIER {
+                    notifier.drain().unwrap();
+                    timer.set_after(Duration::from_millis(1)).unwrap();
+                    registry.register(&timer, TIMER, Interest::READABLE | Interest::EDGE).unwrap();
+                    return ControlFlow::Continue(());
+                }
+                ControlFlow::Break(())
+            })
+            .unwrap();
+        assert_eq!(order, vec![NOTIFIER, TIMER]);
+    }
+}
@@ -0,0 +1,542 @@
+//! Framed TCP connections
+//!
+//! [`FramedStream`] wraps a non-blocking `TcpStream` with a [`FrameReader`]
+//! and a [`FrameWriter`], so both ends exchange whole length-prefixed frames.
+//! Clients open one with [`FramedStream::connect`]; servers accept them from a
+//! [`TcpServer`].
+//!
+//! Nothing here blocks: receives return what has arrived, and sends that the
+//! socket cannot take yet stay buffered until [`FramedStream::flush`]. Poll
+//! both from a busy loop or register the descriptors with a reactor.
+//!
+//! With the `io-uring` feature a stream can instead run on [`Backend::IoUring`],
+//! which submits its receives and sends through a per-connection io_uring.
+//! Kernels without the needed support fall back to [`Backend::Epoll`] at
+//! runtime, so callers can ask for io_uring unconditionally.
+//!
+//! # Example
+//!
+//! ```
+//! use network::framing::FrameFormat;
+//! use network::tcp::{FramedStream, TcpServer};
+//!
+//! let server = TcpServer::bind("127.0.0.1:0", FrameFormat::default()).unwrap();
+//! let mut client = FramedStream::connect(server.local_addr().unwrap(), FrameFormat::default()).unwrap();
+//! client.send_batch(&[b"new order", b"cancel"]).unwrap();
+//!
+//! let (mut connection, _) = loop {
+//!     if let Some(accepted) = server.accept().unwrap() {
+//!         break accepted;
+//!     }
+//! };
+//! let mut frames = Vec::new();
+//! while frames.len() < 2 {
+//!     connection.recv(|frame| frames.push(frame.to_vec())).unwrap();
+//! }
+//! assert_eq!(frames, vec![b"new order".to_vec(), b"cancel".to_vec()]);
+//! ```
+
+use std::io::{self, IoSlice, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd, RawFd};
+
+use ferrite_core::ring_buffer::Producer;
+
+use crate::framing::{FrameFormat, FrameReader, FrameWriter};
+#[cfg(all(target_os = "linux", feature = "io-uring"))]
+use crate::uring::{self, UringSocket};
+
+/// The I/O implementation behind a [`FramedStream`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Backend {
+    /// Non-blocking `recv` and `writev` syscalls, driven by readiness polling
+    #[default]
+    Epoll,
+    /// A per-connection io_uring with a multishot receive into provided
+    /// buffers and sends from registered buffers
+    ///
+    /// Needs the `io-uring` feature and Linux 6.0 or later. Poll these
+    /// streams from a busy loop: readiness of the descriptor says nothing
+    /// about data the ring already received.
+    IoUring,
+}
+
+impl Backend {
+    /// Checks if this backend can run in this build on this kernel
+    ///
+    /// The io_uring check runs once per process and is cached.
+    pub fn is_available(self) -> bool {
+        match self {
+            Backend::Epoll => true,
+            #[cfg(all(target_os = "linux", feature = "io-uring"))]
+            Backend::IoUring => uring::is_supported(),
+            #[cfg(not(all(target_os = "linux", feature = "io-uring")))]
+            Backend::IoUring => false,
+        }
+    }
+
+    /// Returns the backend a stream asking for this one gets
+    pub fn resolve(self) -> Backend {
+        if self.is_available() {
+            self
+        } else {
+            Backend::Epoll
+        }
+    }
+}
+
+/// The socket operations of one backend
+#[derive(Debug)]
+enum Io {
+    Socket(TcpStream),
+    #[cfg(all(target_os = "linux", feature = "io-uring"))]
+    Uring(Box<UringSocket>),
+}
+
+impl Io {
+    fn stream(&self) -> &TcpStream {
+        match self {
+            Io::Socket(stream) => stream,
+            #[cfg(all(target_os = "linux", feature = "io-uring"))]
+            Io::Uring(socket) => socket.stream(),
+        }
+    }
+
+    fn backend(&self) -> Backend {
+        match self {
+            Io::Socket(_) => Backend::Epoll,
+            #[cfg(all(target_os = "linux", feature = "io-uring"))]
+            Io::Uring(_) => Backend::IoUring,
+        }
+    }
+
+    /// Returns the bytes the backend accepted but has not sent yet
+    fn pending(&self) -> usize {
+        match self {
+            Io::Socket(_) => 0,
+            #[cfg(all(target_os = "linux", feature = "io-uring"))]
+            Io::Uring(socket) => socket.pending(),
+        }
+    }
+}
+
+impl Read for Io {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Io::Socket(stream) => stream.read(buf),
+            #[cfg(all(target_os = "linux", feature = "io-uring"))]
+            Io::Uring(socket) => socket.read(buf),
+        }
+    }
+}
+
+impl Write for Io {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Io::Socket(stream) => stream.write(buf),
+            #[cfg(all(target_os = "linux", feature = "io-uring"))]
+            Io::Uring(socket) => socket.write(buf),
+        }
+    }
+
+    fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
+        match self {
+            Io::Socket(stream) => stream.write_vectored(bufs),
+            #[cfg(all(target_os = "linux", feature = "io-uring"))]
+            Io::Uring(socket) => socket.write_vectored(bufs),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Io::Socket(stream) => stream.flush(),
+            #[cfg(all(target_os = "linux", feature = "io-uring"))]
+            Io::Uring(socket) => socket.flush(),
+        }
+    }
+}
+
+/// A non-blocking TCP connection that sends and receives frames
+#[derive(Debug)]
+pub struct FramedStream {
+    io: Io,
+    reader: FrameReader,
+    writer: FrameWriter,
+}
+
+impl FramedStream {
+    /// Connects to `addr` and returns the connection in non-blocking mode
+    ///
+    /// The connect itself blocks until the handshake completes.
+    pub fn connect<A: ToSocketAddrs>(addr: A, format: FrameFormat) -> io::Result<Self> {
+        Self::from_stream(TcpStream::connect(addr)?, format)
+    }
+
+    /// Wraps an established connection
+    ///
+    /// Switches the socket to non-blocking mode and disables Nagle's
+    /// algorithm, since frames are already batched by [`FramedStream::send_batch`].
+    pub fn from_stream(stream: TcpStream, format: FrameFormat) -> io::Result<Self> {
+        Self::from_stream_with_backend(stream, format, Backend::Epoll)
+    }
+
+    /// Connects to `addr` like [`FramedStream::connect`], running the connection on `backend`
+    pub fn connect_with_backend<A: ToSocketAddrs>(addr: A, format: FrameFormat, backend: Backend) -> io::Result<Self> {
+        Self::from_stream_with_backend(TcpStream::connect(addr)?, format, backend)
+    }
+
+    /// Wraps an established connection like [`FramedStream::from_stream`], running it on `backend`
+    ///
+    /// Falls back to [`Backend::Epoll`] if `backend` is not available; check
+    /// [`FramedStream::backend`] for the one in use.
+    pub fn from_stream_with_backend(stream: TcpStream, format: FrameFormat, backend: Backend) -> io::Result<Self> {
+        stream.set_nodelay(true)?;
+        let io = match backend.resolve() {
+            Backend::Epoll => {
+                stream.set_nonblocking(true)?;
+                Io::Socket(stream)
+            }
+            // The ring parks what the socket can't complete, and would hand
+            // EAGAIN back instead on a non-blocking socket
+            #[cfg(all(target_os = "linux", feature = "io-uring"))]
+            Backend::IoUring => {
+                stream.set_nonblocking(false)?;
+                Io::Uring(Box::new(UringSocket::new(stream)?))
+            }
+            #[cfg(not(all(target_os = "linux", feature = "io-uring")))]
+            Backend::IoUring => unreachable!("io_uring is never available without the feature"),
+        };
+        Ok(FramedStream {
+            io,
+            reader: FrameReader::new(format),
+            writer: FrameWriter::new(format),
+        })
+    }
+
+    /// Returns the backend running this connection
+    pub fn backend(&self) -> Backend {
+        self.io.backend()
+    }
+
+    /// Sends one frame
+    ///
+    /// See [`FramedStream::send_batch`].
+    pub fn send(&mut self, frame: &[u8]) -> io::Result<()> {
+        self.send_batch(&[frame])
+    }
+
+    /// Sends `frames` in order, with a single `writev` where the socket takes them all
+    ///
+    /// Bytes the socket would not take are buffered and go out, ahead of any
+    /// later frames, on the next send or [`FramedStream::flush`].
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - Every frame was written or buffered
+    /// * `Err(io::Error)` - If a frame exceeds the size limit, in which case nothing was sent,
+    ///   or if the connection failed
+    pub fn send_batch(&mut self, frames: &[&[u8]]) -> io::Result<()> {
+        self.writer.write_frames(&mut self.io, frames)
+    }
+
+    /// Writes as much buffered output as the socket accepts
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(true)` - Nothing is left to send
+    /// * `Ok(false)` - The socket is full and bytes are still buffered
+    /// * `Err(io::Error)` - If the connection failed
+    pub fn flush(&mut self) -> io::Result<bool> {
+        self.io.flush()?;
+        let flushed = self.writer.flush(&mut self.io)?;
+        Ok(flushed && self.io.pending() == 0)
+    }
+
+    /// Returns the number of bytes buffered for sending
+    pub fn pending(&self) -> usize {
+        self.writer.pending() + self.io.pending()
+    }
+
+    /// Receives every frame that has fully arrived and passes each to `f`
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(n)` - The number of frames received; check [`FramedStream::is_closed`] for end of stream
+    /// * `Err(io::Error)` - If the connection failed, the peer sent an oversized frame, or it closed
+    ///   the connection inside a frame
+    pub fn recv<F: FnMut(&[u8])>(&mut self, f: F) -> io::Result<usize> {
+        self.reader.read_frames(&mut self.io, usize::MAX, f)
+    }
+
+    /// Receives as many whole frames as fit in `producer`'s byte ring
+    ///
+    /// Each frame is pushed with its length header in this stream's format,
+    /// so nothing is allocated per frame; the consumer decodes the ring with a
+    /// [`CodecReader`] over a [`LengthPrefixedCodec`], reading through a
+    /// [`RingReader`]. Stops reading at the first frame that does not fit, so
+    /// a slow consumer pushes back on the peer through TCP flow control rather
+    /// than dropping frames. The ring must hold the largest frame plus its
+    /// header, or that frame is never delivered.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(n)` - The number of frames pushed
+    /// * `Err(io::Error)` - As for [`FramedStream::recv`]
+    ///
+    /// [`CodecReader`]: crate::codec::CodecReader
+    /// [`LengthPrefixedCodec`]: crate::codec::LengthPrefixedCodec
+    /// [`RingReader`]: crate::codec::RingReader
+    pub fn recv_into(&mut self, producer: &mut Producer<u8>) -> io::Result<usize> {
+        let format = *self.reader.format();
+        let header_len = format.header_len();
+        self.reader.read_frames_while(&mut self.io, |frame| {
+            if producer.remaining_capacity() < header_len + frame.len() {
+                return false;
+            }
+            let mut header = [0u8; 8];
+            format
+                .encode_header(frame.len(), &mut header[..header_len])
+                .expect("received frame exceeds its own format's limit");
+            let pushed = producer.push_iter(header[..header_len].iter().chain(frame).copied());
+            debug_assert_eq!(pushed, header_len + frame.len(), "ring lost room reserved for a frame");
+            true
+        })
+    }
+
+    /// Checks if the peer has closed its side of the connection
+    pub fn is_closed(&self) -> bool {
+        self.reader.is_closed()
+    }
+
+    /// Returns the address of the peer
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        self.io.stream().peer_addr()
+    }
+
+    /// Returns the local address of the connection
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.io.stream().local_addr()
+    }
+}
+
+impl AsRawFd for FramedStream {
+    fn as_raw_fd(&self) -> RawFd {
+        self.io.stream().as_raw_fd()
+    }
+}
+
+impl AsFd for FramedStream {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.io.stream().as_fd()
+    }
+}
+
+/// A non-blocking listener that accepts framed connections
+#[derive(Debug)]
+pub struct TcpServer {
+    listener: TcpListener,
+    format: FrameFormat,
+    backend: Backend,
+}
+
+impl TcpServer {
+    /// Listens on `addr`, framing every accepted connection with `format`
+    pub fn bind<A: ToSocketAddrs>(addr: A, format: FrameFormat) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        listener.set_nonblocking(true)?;
+        Ok(TcpServer { listener, format, backend: Backend::Epoll })
+    }
+
+    /// Runs accepted connections on `backend`, falling back as [`FramedStream::from_stream_with_backend`] does
+    pub fn with_backend(mut self, backend: Backend) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    /// Accepts a waiting connection
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Some((stream, addr)))` - A new connection and its peer address
+    /// * `Ok(None)` - If no connection is waiting
+    /// * `Err(io::Error)` - If accepting failed
+    pub fn accept(&self) -> io::Result<Option<(FramedStream, SocketAddr)>> {
+        match self.listener.accept() {
+            Ok((stream, addr)) => Ok(Some((FramedStream::from_stream_with_backend(stream, self.format, self.backend)?, addr))),
+            Err(err) if err.kind() == io::ErrorKind::WouldBlock => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Returns the address the server listens on
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Returns the frame format of accepted connections
+    pub fn format(&self) -> &FrameFormat {
+        &self.format
+    }
+
+    /// Returns the backend accepted connections ask for
+    pub fn backend(&self) -> Backend {
+        self.backend
+    }
+}
+
+impl AsRawFd for TcpServer {
+    fn as_raw_fd(&self) -> RawFd {
+        self.listener.as_raw_fd()
+    }
+}
+
+impl AsFd for TcpServer {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.listener.as_fd()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::{CodecReader, LengthPrefixedCodec, RingReader};
+    use crate::framing::{Endian, HeaderWidth};
+    use ferrite_core::ring_buffer::RingBuffer;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    /// Every backend, so each test runs against io_uring as well where it is available
+    const BACKENDS: [Backend; 2] = [Backend::Epoll, Backend::IoUring];
+
+    fn connected_pair(format: FrameFormat, backend: Backend) -> (FramedStream, FramedStream) {
+        let server = TcpServer::bind("127.0.0.1:0", format).unwrap().with_backend(backend);
+        let client = FramedStream::connect_with_backend(server.local_addr().unwrap(), format, backend).unwrap();
+        assert_eq!(client.backend(), backend.resolve());
+        let deadline = Instant::now() + Duration::from_secs(5);
+        loop {
+            if let Some((connection, addr)) = server.accept().unwrap() {
+                assert_eq!(addr, client.local_addr().unwrap());
+                assert_eq!(connection.backend(), backend.resolve());
+                return (client, connection);
+            }
+            assert!(Instant::now() < deadline, "no connection accepted");
+            thread::yield_now();
+        }
+    }
+
+    /// Receives until `expected` frames arrived, flushing `other` meanwhile
+    fn receive(stream: &mut FramedStream, other: &mut FramedStream, expected: usize) -> Vec<Vec<u8>> {
+        let mut frames = Vec::new();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while frames.len() < expected {
+            other.flush().unwrap();
+            stream.recv(|frame| frames.push(frame.to_vec())).unwrap();
+            assert!(Instant::now() < deadline, "received {} of {} frames", frames.len(), expected);
+        }
+        frames
+    }
+
+    #[test]
+    fn test_exchanges_frames_both_ways() {
+        for backend in BACKENDS {
+            let format = FrameFormat::new(HeaderWidth::U16, Endian::Little);
+            let (mut client, mut server) = connected_pair(format, backend);
+
+            client.send_batch(&[b"one", b"", b"three"]).unwrap();
+            assert_eq!(receive(&mut server, &mut client, 3), vec![b"one".to_vec(), vec![], b"three".to_vec()]);
+
+            server.send(&[7; 65_535]).unwrap();
+            assert_eq!(receive(&mut client, &mut server, 1), vec![vec![7; 65_535]]);
+            assert!(client.send(&[0; 65_536]).is_err());
+        }
+    }
+
+    #[test]
+    fn test_buffers_sends_until_peer_reads() {
+        for backend in BACKENDS {
+            let (mut client, mut server) = connected_pair(FrameFormat::default(), backend);
+            let payload = vec![1u8; 64 * 1024];
+
+            // Far more than the socket buffers hold, with nobody reading yet
+            for _ in 0..256 {
+                client.send(&payload).unwrap();
+            }
+            assert!(client.pending() > 0);
+
+            let frames = receive(&mut server, &mut client, 256);
+            assert!(frames.iter().all(|frame| *frame == payload));
+            assert!(client.flush().unwrap());
+            assert_eq!(client.pending(), 0);
+        }
+    }
+
+    #[test]
+    fn test_recv_into_feeds_consumer_thread() {
+        for backend in BACKENDS {
+            let format = FrameFormat::default();
+            let (mut client, mut server) = connected_pair(format, backend);
+            // Room for seven 4-byte frames with their headers
+            let (mut producer, mut consumer) = RingBuffer::<u8>::new(64).unwrap().split();
+            const FRAMES: u32 = 10_000;
+
+            let decoder = thread::spawn(move || {
+                let mut reader = CodecReader::new(LengthPrefixedCodec::new(format));
+                let mut next = 0u32;
+                while next < FRAMES {
+                    let decoded = reader
+                        .read_items(&mut RingReader::new(&mut consumer), usize::MAX, |frame| {
+                            assert_eq!(frame, next.to_le_bytes());
+                            next += 1;
+                        })
+                        .unwrap();
+                    if decoded == 0 {
+                        thread::yield_now();
+                    }
+                }
+            });
+
+            let frames: Vec<[u8; 4]> = (0..FRAMES).map(u32::to_le_bytes).collect();
+            for chunk in frames.chunks(100) {
+                let batch: Vec<&[u8]> = chunk.iter().map(|frame| &frame[..]).collect();
+                client.send_batch(&batch).unwrap();
+            }
+
+            let mut received = 0;
+            let deadline = Instant::now() + Duration::from_secs(10);
+            while received < FRAMES as usize {
+                client.flush().unwrap();
+                received += server.recv_into(&mut producer).unwrap();
+                assert!(Instant::now() < deadline, "received {} frames", received);
+            }
+            decoder.join().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_detects_peer_close() {
+        for backend in BACKENDS {
+            let (mut client, mut server) = connected_pair(FrameFormat::default(), backend);
+            client.send(b"bye").unwrap();
+            drop(client);
+
+            let mut frames = Vec::new();
+            let deadline = Instant::now() + Duration::from_secs(5);
+            while !server.is_closed() {
+                server.recv(|frame| frames.push(frame.to_vec())).unwrap();
+                assert!(Instant::now() < deadline);
+            }
+            assert_eq!(frames, vec![b"bye".to_vec()]);
+        }
+    }
+
+    #[test]
+    fn test_backend_falls_back_when_unavailable() {
+        assert!(Backend::Epoll.is_available());
+        assert_eq!(Backend::default().resolve(), Backend::Epoll);
+        if !cfg!(all(target_os = "linux", feature = "io-uring")) {
+            assert!(!Backend::IoUring.is_available());
+        }
+        let expected = if Backend::IoUring.is_available() { Backend::IoUring } else { Backend::Epoll };
+        assert_eq!(Backend::IoUring.resolve(), expected);
+    }
+}
@@ -0,0 +1,463 @@
+//! io_uring socket I/O
+//!
+//! [`UringSocket`] moves a connection's reads and writes onto a private
+//! io_uring instead of issuing `recv` and `writev` syscalls. One multishot
+//! receive stays armed and the kernel fills buffers from a provided buffer
+//! ring as data arrives; sends are copied into a fixed pool of registered
+//! buffers and go out with `IORING_OP_WRITE_FIXED`. Each call makes a single
+//! `io_uring_enter`, submitting everything it queued.
+//!
+//! That enter happens even with nothing to submit: the kernel defers work
+//! such as retrying a receive or a send that waited for the socket to the
+//! submitting thread, and only runs it when the thread enters the kernel. A
+//! busy loop that never did would stall those operations.
+//!
+//! The socket implements `Read` and `Write` with non-blocking semantics, so
+//! the framing layer drives it exactly like a `TcpStream`.
+
+use std::alloc::{self, Layout};
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::{self, IoSlice, Read, Write};
+use std::net::TcpStream;
+use std::os::fd::AsRawFd;
+use std::os::unix::net::UnixStream;
+use std::ptr;
+use std::slice;
+use std::sync::atomic::{AtomicU16, Ordering};
+use std::sync::OnceLock;
+
+use io_uring::{cqueue, opcode, squeue, types, IoUring, Probe};
+
+const RECV: u64 = 1;
+const SEND: u64 = 2;
+const CANCEL: u64 = 3;
+
+const RING_ENTRIES: u32 = 16;
+const BUFFER_GROUP: u16 = 0;
+const PAGE_SIZE: usize = 4096;
+
+/// Number of provided receive buffers, a power of two as the kernel requires
+const RECV_BUFFERS: u16 = 64;
+const RECV_BUFFER_SIZE: usize = 16 * 1024;
+
+/// Number of registered send buffers
+const SEND_BUFFERS: usize = 8;
+const SEND_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Checks once whether the running kernel supports everything [`UringSocket`] uses
+///
+/// That is io_uring itself, registered buffers, provided buffer rings and
+/// multishot receive, so in practice Linux 6.0 or later with io_uring enabled.
+pub(crate) fn is_supported() -> bool {
+    static SUPPORTED: OnceLock<bool> = OnceLock::new();
+    *SUPPORTED.get_or_init(|| probe().is_ok())
+}
+
+/// Runs a multishot receive of one byte over a socket pair
+fn probe() -> io::Result<()> {
+    let buffers = BufferRing::new();
+    let send_region = Region::new(PAGE_SIZE);
+    let (mut left, right) = UnixStream::pair()?;
+    let mut ring = IoUring::new(RING_ENTRIES)?;
+
+    let mut opcodes = Probe::new();
+    ring.submitter().register_probe(&mut opcodes)?;
+    for code in [opcode::RecvMulti::CODE, opcode::WriteFixed::CODE, opcode::AsyncCancel::CODE] {
+        if !opcodes.is_supported(code) {
+            return Err(io::ErrorKind::Unsupported.into());
+        }
+    }
+    buffers.register(&ring)?;
+    let iovec = libc::iovec { iov_base: send_region.as_ptr().cast(), iov_len: PAGE_SIZE };
+    // SAFETY: the region outlives the ring, which is dropped first
+    unsafe { ring.submitter().register_buffers(&[iovec])? };
+
+    let recv = opcode::RecvMulti::new(types::Fd(right.as_raw_fd()), BUFFER_GROUP).build().user_data(RECV);
+    push(&mut ring, &recv)?;
+    ring.submit()?;
+    left.write_all(b"x")?;
+
+    let mut received = None;
+    let mut armed = true;
+    while armed {
+        ring.submit_and_wait(1)?;
+        for cqe in ring.completion() {
+            if cqe.user_data() != RECV {
+                continue;
+            }
+            armed = cqueue::more(cqe.flags());
+            received.get_or_insert(cqe.result());
+        }
+        if armed && received.is_some() {
+            push(&mut ring, &opcode::AsyncCancel::new(RECV).build().user_data(CANCEL))?;
+        }
+    }
+    match received {
+        Some(1) => Ok(()),
+        Some(result) if result < 0 => Err(io::Error::from_raw_os_error(-result)),
+        _ => Err(io::ErrorKind::Unsupported.into()),
+    }
+}
+
+fn push(ring: &mut IoUring, entry: &squeue::Entry) -> io::Result<()> {
+    // SAFETY: callers keep every buffer the entry points at alive until it completes
+    unsafe { ring.submission().push(entry) }.map_err(|_| io::Error::other("submission queue full"))
+}
+
+/// Page-aligned, zeroed memory the kernel reads and writes directly
+struct Region {
+    ptr: *mut u8,
+    layout: Layout,
+    leaked: bool,
+}
+
+impl Region {
+    fn new(len: usize) -> Self {
+        let layout = Layout::from_size_align(len, PAGE_SIZE).expect("region size overflows");
+        // SAFETY: every region is at least a page long
+        let ptr = unsafe { alloc::alloc_zeroed(layout) };
+        if ptr.is_null() {
+            alloc::handle_alloc_error(layout);
+        }
+        Region { ptr, layout, leaked: false }
+    }
+
+    fn as_ptr(&self) -> *mut u8 {
+        self.ptr
+    }
+
+    /// Never frees the memory, for when the kernel may still be using it
+    fn leak(&mut self) {
+        self.leaked = true;
+    }
+}
+
+impl Drop for Region {
+    fn drop(&mut self) {
+        if !self.leaked {
+            // SAFETY: allocated in `Region::new` with the same layout
+            unsafe { alloc::dealloc(self.ptr, self.layout) };
+        }
+    }
+}
+
+/// The provided buffers multishot receives pick from
+struct BufferRing {
+    entries: Region,
+    buffers: Region,
+    tail: u16,
+}
+
+impl BufferRing {
+    /// Creates the ring with every buffer handed to the kernel
+    fn new() -> Self {
+        let entries = Region::new((RECV_BUFFERS as usize * 16).max(PAGE_SIZE));
+        let buffers = Region::new(RECV_BUFFERS as usize * RECV_BUFFER_SIZE);
+        let mut ring = BufferRing { entries, buffers, tail: 0 };
+        for bid in 0..RECV_BUFFERS {
+            ring.recycle(bid);
+        }
+        ring
+    }
+
+    fn register(&self, ring: &IoUring) -> io::Result<()> {
+        // SAFETY: the entries stay allocated until the ring is gone
+        unsafe {
+            ring.submitter()
+                .register_buf_ring_with_flags(self.entries.as_ptr() as u64, RECV_BUFFERS, BUFFER_GROUP, 0)
+        }
+    }
+
+    /// Returns the first `len` bytes the kernel received into buffer `bid`
+    fn buffer(&self, bid: u16, len: usize) -> &[u8] {
+        assert!(bid < RECV_BUFFERS && len <= RECV_BUFFER_SIZE);
+        // SAFETY: in bounds, and the kernel no longer writes to a buffer it completed
+        unsafe { slice::from_raw_parts(self.buffers.as_ptr().add(bid as usize * RECV_BUFFER_SIZE), len) }
+    }
+
+    /// Hands buffer `bid` back to the kernel
+    fn recycle(&mut self, bid: u16) {
+        let base = self.entries.as_ptr().cast::<types::BufRingEntry>();
+        let addr = self.buffers.as_ptr() as u64 + (bid as usize * RECV_BUFFER_SIZE) as u64;
+        // SAFETY: the slot at the tail is ours until the tail is published, and
+        // the tail shares memory with the first entry only in fields we don't write
+        unsafe {
+            let entry = &mut *base.add((self.tail & (RECV_BUFFERS - 1)) as usize);
+            entry.set_addr(addr);
+            entry.set_len(RECV_BUFFER_SIZE as u32);
+            entry.set_bid(bid);
+        }
+        self.tail = self.tail.wrapping_add(1);
+        // SAFETY: the tail is an aligned u16 inside the entries region
+        let tail = unsafe { AtomicU16::from_ptr(types::BufRingEntry::tail(base).cast_mut()) };
+        tail.store(self.tail, Ordering::Release);
+    }
+}
+
+/// Part of a buffer holding bytes yet to be read or sent
+struct Span {
+    index: u16,
+    start: usize,
+    end: usize,
+}
+
+/// A TCP connection whose I/O goes through its own io_uring
+pub(crate) struct UringSocket {
+    ring: IoUring,
+    stream: TcpStream,
+    recv_buffers: BufferRing,
+    received: VecDeque<Span>,
+    recv_armed: bool,
+    eof: bool,
+    errno: Option<i32>,
+    send_region: Region,
+    free: Vec<u16>,
+    queued: VecDeque<Span>,
+    send_in_flight: bool,
+}
+
+// SAFETY: the raw pointers are to memory owned by the socket itself
+unsafe impl Send for UringSocket {}
+
+impl UringSocket {
+    /// Takes over `stream` and arms the first receive
+    ///
+    /// The stream should be in blocking mode: the ring parks operations the
+    /// socket cannot complete yet, so nothing blocks the caller either way.
+    pub(crate) fn new(stream: TcpStream) -> io::Result<Self> {
+        let ring = IoUring::new(RING_ENTRIES)?;
+        let recv_buffers = BufferRing::new();
+        recv_buffers.register(&ring)?;
+
+        let send_region = Region::new(SEND_BUFFERS * SEND_BUFFER_SIZE);
+        let iovecs: Vec<libc::iovec> = (0..SEND_BUFFERS)
+            .map(|index| libc::iovec {
+                // SAFETY: in bounds of the region
+                iov_base: unsafe { send_region.as_ptr().add(index * SEND_BUFFER_SIZE) }.cast(),
+                iov_len: SEND_BUFFER_SIZE,
+            })
+            .collect();
+        // SAFETY: the region lives as long as the ring, and `Drop` waits for
+        // writes from it to finish
+        unsafe { ring.submitter().register_buffers(&iovecs)? };
+
+        let mut socket = UringSocket {
+            ring,
+            stream,
+            recv_buffers,
+            received: VecDeque::with_capacity(RECV_BUFFERS as usize),
+            recv_armed: false,
+            eof: false,
+            errno: None,
+            send_region,
+            free: (0..SEND_BUFFERS as u16).rev().collect(),
+            queued: VecDeque::with_capacity(SEND_BUFFERS),
+            send_in_flight: false,
+        };
+        socket.submit()?;
+        Ok(socket)
+    }
+
+    /// Returns the underlying stream
+    pub(crate) fn stream(&self) -> &TcpStream {
+        &self.stream
+    }
+
+    /// Returns the number of bytes copied into send buffers but not yet sent
+    pub(crate) fn pending(&self) -> usize {
+        self.queued.iter().map(|span| span.end - span.start).sum()
+    }
+
+    /// Reaps completions, then queues the next send and re-arms the receive,
+    /// submitting both in one call that also runs deferred work
+    fn submit(&mut self) -> io::Result<()> {
+        self.reap();
+        let fd = types::Fd(self.stream.as_raw_fd());
+
+        // With every buffer waiting to be read the receive would only fail with ENOBUFS
+        let has_room = self.received.len() < RECV_BUFFERS as usize;
+        if !self.recv_armed && !self.eof && self.errno.is_none() && has_room {
+            push(&mut self.ring, &opcode::RecvMulti::new(fd, BUFFER_GROUP).build().user_data(RECV))?;
+            self.recv_armed = true;
+        }
+        if !self.send_in_flight && self.errno.is_none() {
+            if let Some(span) = self.queued.front() {
+                // SAFETY: in bounds of the region
+                let data = unsafe { self.send_region.as_ptr().add(span.index as usize * SEND_BUFFER_SIZE + span.start) };
+                let len = (span.end - span.start) as u32;
+                push(&mut self.ring, &opcode::WriteFixed::new(fd, data, len, span.index).build().user_data(SEND))?;
+                self.send_in_flight = true;
+            }
+        }
+        self.ring.submit()?;
+        self.reap();
+        Ok(())
+    }
+
+    fn reap(&mut self) {
+        for cqe in self.ring.completion() {
+            let result = cqe.result();
+            match cqe.user_data() {
+                RECV => {
+                    self.recv_armed &= cqueue::more(cqe.flags());
+                    if result > 0 {
+                        let index = cqueue::buffer_select(cqe.flags()).expect("receive completed without a buffer");
+                        self.received.push_back(Span { index, start: 0, end: result as usize });
+                    } else if result == 0 {
+                        self.eof = true;
+                    } else if -result != libc::ENOBUFS && -result != libc::ECANCELED {
+                        self.errno = Some(-result);
+                    }
+                }
+                SEND => {
+                    self.send_in_flight = false;
+                    if result < 0 {
+                        if -result != libc::ECANCELED {
+                            self.errno = Some(-result);
+                        }
+                        continue;
+                    }
+                    let span = self.queued.front_mut().expect("send completed with nothing queued");
+                    span.start += result as usize;
+                    if span.start == span.end {
+                        self.free.push(span.index);
+                        self.queued.pop_front();
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Copies as much of `data` as fits into send buffers
+    fn copy_in(&mut self, data: &[u8]) -> usize {
+        // The buffer being sent can't grow, so only append behind it
+        let appendable = match self.queued.back() {
+            Some(span) => span.end < SEND_BUFFER_SIZE && (self.queued.len() > 1 || !self.send_in_flight),
+            None => false,
+        };
+        if !appendable {
+            let Some(index) = self.free.pop() else { return 0 };
+            self.queued.push_back(Span { index, start: 0, end: 0 });
+        }
+        let span = self.queued.back_mut().expect("a buffer was just queued");
+        let len = data.len().min(SEND_BUFFER_SIZE - span.end);
+        // SAFETY: in bounds of the region, and the kernel only reads the queued part
+        unsafe {
+            let dst = self.send_region.as_ptr().add(span.index as usize * SEND_BUFFER_SIZE + span.end);
+            ptr::copy_nonoverlapping(data.as_ptr(), dst, len);
+        }
+        span.end += len;
+        len
+    }
+
+    fn error(&self) -> Option<io::Error> {
+        self.errno.map(io::Error::from_raw_os_error)
+    }
+}
+
+impl Read for UringSocket {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.submit()?;
+        let mut read = 0;
+        while read < buf.len() {
+            let Some(span) = self.received.front_mut() else { break };
+            let len = (span.end - span.start).min(buf.len() - read);
+            let data = self.recv_buffers.buffer(span.index, span.end);
+            buf[read..read + len].copy_from_slice(&data[span.start..span.start + len]);
+            span.start += len;
+            read += len;
+            if span.start == span.end {
+                let index = span.index;
+                self.received.pop_front();
+                self.recv_buffers.recycle(index);
+            }
+        }
+
+        if read > 0 {
+            Ok(read)
+        } else if let Some(err) = self.error() {
+            Err(err)
+        } else if self.eof {
+            Ok(0)
+        } else {
+            Err(io::ErrorKind::WouldBlock.into())
+        }
+    }
+}
+
+impl Write for UringSocket {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.write_vectored(&[IoSlice::new(buf)])
+    }
+
+    fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
+        self.reap();
+        if let Some(err) = self.error() {
+            return Err(err);
+        }
+
+        let mut written = 0;
+        'copy: for buf in bufs {
+            let mut rest = &buf[..];
+            while !rest.is_empty() {
+                let len = self.copy_in(rest);
+                if len == 0 {
+                    break 'copy;
+                }
+                written += len;
+                rest = &rest[len..];
+            }
+        }
+        self.submit()?;
+
+        if written == 0 && bufs.iter().any(|buf| !buf.is_empty()) {
+            Err(io::ErrorKind::WouldBlock.into())
+        } else {
+            Ok(written)
+        }
+    }
+
+    /// Reaps finished sends and submits the next one, without waiting
+    fn flush(&mut self) -> io::Result<()> {
+        self.submit()?;
+        self.error().map_or(Ok(()), Err)
+    }
+}
+
+impl Drop for UringSocket {
+    fn drop(&mut self) {
+        // The kernel uses our buffers until its operations complete, so cancel
+        // them and wait before the memory goes away
+        for (armed, user_data) in [(self.recv_armed, RECV), (self.send_in_flight, SEND)] {
+            if armed {
+                let _ = push(&mut self.ring, &opcode::AsyncCancel::new(user_data).build().user_data(CANCEL));
+            }
+        }
+        while self.recv_armed || self.send_in_flight {
+            match self.ring.submit_and_wait(1) {
+                Ok(_) => self.reap(),
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(_) => {
+                    // Leak rather than free memory the kernel may still touch
+                    self.recv_buffers.entries.leak();
+                    self.recv_buffers.buffers.leak();
+                    self.send_region.leak();
+                    break;
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Debug for UringSocket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UringSocket")
+            .field("stream", &self.stream)
+            .field("received", &self.received.len())
+            .field("pending", &self.pending())
+            .field("eof", &self.eof)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,151 @@
+use proptest::prelude::*;
+use network::codec::{CodecReader, Decoder, Encoder, FixedSizeCodec, LengthPrefixedCodec, LineCodec};
+use network::framing::{Endian, FrameFormat, HeaderWidth};
+use std::fmt::Debug;
+use std::io::{self, Read};
+
+/// Hands out `data` in pieces of the given sizes, would-blocking between them
+struct Chunked {
+    data: Vec<u8>,
+    position: usize,
+    sizes: Vec<usize>,
+    next: usize,
+    blocked: bool,
+}
+
+impl Chunked {
+    fn new(data: Vec<u8>, sizes: &[usize]) -> Self {
+        Chunked { data, position: 0, sizes: sizes.to_vec(), next: 0, blocked: false }
+    }
+}
+
+impl Read for Chunked {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.blocked = !self.blocked;
+        if self.blocked {
+            return Err(io::ErrorKind::WouldBlock.into());
+        }
+        let size = self.sizes[self.next % self.sizes.len()];
+        self.next += 1;
+        let len = size.min(buf.len()).min(self.data.len() - self.position);
+        buf[..len].copy_from_slice(&self.data[self.position..self.position + len]);
+        self.position += len;
+        Ok(len)
+    }
+}
+
+/// Decodes `wire` through a `CodecReader` fed in `chunks`, collecting messages until it
+/// closes or fails
+fn stream_decode<D>(decoder: D, wire: Vec<u8>, chunks: &[usize]) -> (Vec<Vec<u8>>, io::Result<()>)
+where
+    D: for<'a> Decoder<Item<'a> = &'a [u8]>,
+    D::Error: Into<io::Error>,
+{
+    let mut reader = CodecReader::new(decoder);
+    let mut source = Chunked::new(wire, chunks);
+    let mut messages = Vec::new();
+    while !reader.is_closed() {
+        if let Err(err) = reader.read_items(&mut source, usize::MAX, |message| messages.push(message.to_vec())) {
+            return (messages, Err(err));
+        }
+    }
+    (messages, Ok(()))
+}
+
+/// Encodes `messages` and checks they decode back unchanged however the wire is split
+fn check_roundtrip<C>(mut codec: C, messages: &[Vec<u8>], chunks: &[usize]) -> Result<(), TestCaseError>
+where
+    C: Encoder<[u8]> + for<'a> Decoder<Item<'a> = &'a [u8]> + Clone,
+    <C as Encoder<[u8]>>::Error: Debug,
+    <C as Decoder>::Error: Into<io::Error> + Debug,
+{
+    let mut wire = Vec::new();
+    for message in messages {
+        codec.encode(message, &mut wire).unwrap();
+    }
+
+    let mut decoded = Vec::new();
+    let consumed = codec.clone().decode_each(&wire, |message| decoded.push(message.to_vec())).unwrap();
+    prop_assert_eq!(consumed, wire.len());
+    prop_assert_eq!(&decoded, messages);
+
+    let (streamed, result) = stream_decode(codec, wire, chunks);
+    prop_assert!(result.is_ok(), "{:?}", result);
+    prop_assert_eq!(&streamed, messages);
+    Ok(())
+}
+
+/// Decodes arbitrary bytes whole and streamed, checking both agree
+fn check_arbitrary<D>(decoder: D, bytes: Vec<u8>, chunks: &[usize]) -> Result<(), TestCaseError>
+where
+    D: for<'a> Decoder<Item<'a> = &'a [u8]> + Clone,
+    D::Error: Into<io::Error>,
+{
+    let mut whole = Vec::new();
+    let result = decoder.clone().decode_each(&bytes, |message| whole.push(message.to_vec()));
+    let (streamed, stream_result) = stream_decode(decoder, bytes.clone(), chunks);
+
+    prop_assert_eq!(streamed, whole);
+    match result {
+        Ok(consumed) if consumed == bytes.len() => prop_assert!(stream_result.is_ok()),
+        Ok(consumed) => {
+            prop_assert!(consumed < bytes.len());
+            prop_assert_eq!(stream_result.unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+        }
+        Err(_) => prop_assert_eq!(stream_result.unwrap_err().kind(), io::ErrorKind::InvalidData),
+    }
+    Ok(())
+}
+
+fn format_strategy() -> impl Strategy<Value = FrameFormat> {
+    (
+        prop_oneof![Just(HeaderWidth::U8), Just(HeaderWidth::U16), Just(HeaderWidth::U32), Just(HeaderWidth::U64)],
+        prop_oneof![Just(Endian::Big), Just(Endian::Little)],
+    )
+        .prop_map(|(width, endian)| FrameFormat::new(width, endian).with_max_frame_len(200))
+}
+
+fn chunks_strategy() -> impl Strategy<Value = Vec<usize>> {
+    prop::collection::vec(1usize..64, 1..8)
+}
+
+proptest! {
+    #[test]
+    fn prop_length_prefixed_roundtrip(
+        format in format_strategy(),
+        messages in prop::collection::vec(prop::collection::vec(any::<u8>(), 0..200), 0..30),
+        chunks in chunks_strategy()
+    ) {
+        check_roundtrip(LengthPrefixedCodec::new(format), &messages, &chunks)?;
+    }
+
+    #[test]
+    fn prop_line_roundtrip(
+        messages in prop::collection::vec("[^\r\n]{0,80}".prop_map(String::into_bytes), 0..30),
+        chunks in chunks_strategy()
+    ) {
+        check_roundtrip(LineCodec::new(320), &messages, &chunks)?;
+    }
+
+    #[test]
+    fn prop_fixed_size_roundtrip(
+        (size, messages) in (1usize..32).prop_flat_map(|size| {
+            (Just(size), prop::collection::vec(prop::collection::vec(any::<u8>(), size), 0..30))
+        }),
+        chunks in chunks_strategy()
+    ) {
+        check_roundtrip(FixedSizeCodec::new(size), &messages, &chunks)?;
+    }
+
+    #[test]
+    fn prop_decoders_agree_on_arbitrary_input(
+        format in format_strategy(),
+        bytes in prop::collection::vec(prop_oneof![any::<u8>(), Just(b'\n'), Just(0u8)], 0..600),
+        size in 1usize..32,
+        chunks in chunks_strategy()
+    ) {
+        check_arbitrary(LengthPrefixedCodec::new(format), bytes.clone(), &chunks)?;
+        check_arbitrary(LineCodec::new(40), bytes.clone(), &chunks)?;
+        check_arbitrary(FixedSizeCodec::new(size), bytes, &chunks)?;
+    }
+}